@@ -0,0 +1,170 @@
+use bevy::prelude::*;
+
+use crate::arena_gen::{regenerate_arena, spawn_generated_level, FreeCells};
+use crate::arena_layout::ArenaFiles;
+use crate::audio::SoundEvent;
+use crate::game_objects::{Player, WallBundle};
+use crate::game_utils::{Direction, ResetGameEvent, WallDamageEvent};
+use crate::level1::spawn_level_1;
+use crate::level2::spawn_level_2;
+use crate::rng::reseed_rng;
+use crate::walls::{damage_walls, move_kinematic_walls, rebuild_walls, reset_walls};
+use crate::StapidSet;
+
+// the outer walls, the selected level and the walls that move, break and come back
+pub struct ArenaPlugin;
+
+#[derive(Clone, Copy, PartialEq)]
+pub enum ArenaKind {
+    Level1,
+    Level2,
+    Generated,
+}
+
+#[derive(Resource, Clone)]
+pub struct ArenaConfig {
+    pub kind: ArenaKind,
+    pub fixed_seed: Option<u64>,
+    pub seed: u64,
+}
+
+// where players may come back in deathmatch, every arena keeps the four starting corners
+#[derive(Resource, Clone)]
+pub struct SpawnPoints {
+    pub points: Vec<Vec2>,
+}
+
+impl Plugin for ArenaPlugin {
+    fn build(&self, app: &mut App) {
+        app.insert_resource(ArenaConfig::from_args())
+            .init_resource::<SpawnPoints>()
+            .init_resource::<ArenaFiles>()
+            .init_resource::<FreeCells>()
+            .add_event::<ResetGameEvent>()
+            .add_event::<WallDamageEvent>()
+            .add_event::<SoundEvent>()
+            .add_systems(
+                Startup,
+                (
+                    spawn_walls,
+                    spawn_level_1.run_if(level_1_selected),
+                    spawn_level_2.run_if(level_2_selected),
+                    spawn_generated_level.run_if(generated_arena_selected),
+                )
+                    .chain(),
+            )
+            .add_systems(
+                FixedUpdate,
+                move_kinematic_walls.in_set(StapidSet::MoveWalls),
+            )
+            .add_systems(
+                FixedUpdate,
+                (damage_walls, rebuild_walls)
+                    .chain()
+                    .in_set(StapidSet::WallDamage),
+            )
+            .add_systems(
+                Update,
+                (
+                    reset_walls,
+                    regenerate_arena
+                        .after(reseed_rng)
+                        .run_if(generated_arena_selected),
+                )
+                    .chain()
+                    .in_set(StapidSet::Reset),
+            );
+    }
+}
+
+impl ArenaKind {
+    pub fn name(&self) -> &'static str {
+        match self {
+            ArenaKind::Level1 => "level1",
+            ArenaKind::Level2 => "level2",
+            ArenaKind::Generated => "generated",
+        }
+    }
+
+    pub fn from_name(name: &str) -> Option<ArenaKind> {
+        match name {
+            "level1" => Some(ArenaKind::Level1),
+            "level2" => Some(ArenaKind::Level2),
+            "generated" => Some(ArenaKind::Generated),
+            _ => None,
+        }
+    }
+}
+
+impl ArenaConfig {
+    pub fn from_args() -> ArenaConfig {
+        let args: Vec<String> = std::env::args().collect();
+        let value_of = |flag: &str| {
+            args.iter()
+                .position(|arg| arg == flag)
+                .and_then(|index| args.get(index + 1))
+        };
+        let fixed_seed = value_of("--arena-seed").and_then(|seed| seed.parse().ok());
+        let mut kind = value_of("--arena")
+            .and_then(|name| ArenaKind::from_name(name))
+            .unwrap_or(ArenaKind::Level1);
+        if fixed_seed.is_some() || args.iter().any(|arg| arg == "--generated-arena") {
+            kind = ArenaKind::Generated;
+        }
+        ArenaConfig {
+            kind,
+            fixed_seed,
+            seed: fixed_seed.unwrap_or(0),
+        }
+    }
+}
+
+impl Default for SpawnPoints {
+    fn default() -> Self {
+        SpawnPoints {
+            points: [1, 2, 3, 4].map(Player::spawn_point).to_vec(),
+        }
+    }
+}
+
+impl SpawnPoints {
+    pub fn with_extra(extra: &[Vec2]) -> SpawnPoints {
+        let mut spawn_points = SpawnPoints::default();
+        spawn_points.points.extend_from_slice(extra);
+        spawn_points
+    }
+
+    // the point whose closest enemy is the furthest away
+    pub fn furthest_from(&self, enemies: &[Vec2]) -> Vec2 {
+        let closest_enemy = |point: &Vec2| {
+            enemies
+                .iter()
+                .map(|enemy| point.distance(*enemy))
+                .fold(f32::INFINITY, f32::min)
+        };
+        self.points
+            .iter()
+            .copied()
+            .max_by(|a, b| closest_enemy(a).total_cmp(&closest_enemy(b)))
+            .unwrap_or(Vec2::ZERO)
+    }
+}
+
+pub fn level_1_selected(arena: Res<ArenaConfig>) -> bool {
+    arena.kind == ArenaKind::Level1
+}
+
+pub fn level_2_selected(arena: Res<ArenaConfig>) -> bool {
+    arena.kind == ArenaKind::Level2
+}
+
+pub fn generated_arena_selected(arena: Res<ArenaConfig>) -> bool {
+    arena.kind == ArenaKind::Generated
+}
+
+pub fn spawn_walls(mut commands: Commands, asset_server: Res<AssetServer>) {
+    commands.spawn(WallBundle::new(Direction::Up, &asset_server));
+    commands.spawn(WallBundle::new(Direction::Down, &asset_server));
+    commands.spawn(WallBundle::new(Direction::Right, &asset_server));
+    commands.spawn(WallBundle::new(Direction::Left, &asset_server));
+}
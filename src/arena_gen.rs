@@ -0,0 +1,296 @@
+use bevy::prelude::*;
+use rand::{rngs::StdRng, Rng, SeedableRng};
+use std::collections::VecDeque;
+
+use crate::arena::{ArenaConfig, SpawnPoints};
+use crate::constants::{WALL_BOTTOM, WALL_LEFT, WALL_RIGHT, WALL_THICKNESS, WALL_TOP};
+use crate::game_objects::{Player, Wall, WallBundle};
+use crate::game_utils::{Collider, Direction, ResetGameEvent};
+use crate::level1::spawn_floor;
+use crate::rng::GameRng;
+
+const CELL_SIZE: f32 = 50.0;
+const GRID_COLUMNS: i32 = ((WALL_RIGHT - WALL_LEFT) / CELL_SIZE) as i32;
+const GRID_ROWS: i32 = ((WALL_BOTTOM - WALL_TOP) / CELL_SIZE) as i32;
+const SPAWN_CLEARANCE: f32 = 2.0 * CELL_SIZE;
+const CENTER_CLEARANCE: f32 = 2.0 * CELL_SIZE;
+const SEGMENT_ATTEMPTS: i32 = 60;
+const MAX_SEGMENTS_PER_QUARTER: usize = 7;
+const MAX_BLOCKED_CELLS: f32 = 0.2;
+
+#[derive(Component, Clone)]
+pub struct GeneratedWall;
+
+// the middle of every cell no generated wall runs through, power-ups only show up there
+#[derive(Resource, Clone, Default)]
+pub struct FreeCells {
+    pub cells: Vec<Vec2>,
+}
+
+pub struct GeneratedLayout {
+    pub segments: Vec<GridSegment>,
+    pub free_cells: Vec<Vec2>,
+}
+
+#[derive(Clone, Copy, PartialEq)]
+pub struct GridSegment {
+    pub column: i32,
+    pub row: i32,
+    pub length: i32,
+    pub horizontal: bool,
+}
+
+impl GridSegment {
+    fn cells(&self) -> Vec<(i32, i32)> {
+        (0..self.length)
+            .map(|offset| {
+                if self.horizontal {
+                    (self.column + offset, self.row)
+                } else {
+                    (self.column, self.row + offset)
+                }
+            })
+            .collect()
+    }
+
+    fn mirrored(&self, flip_x: bool, flip_y: bool) -> GridSegment {
+        let (mut column, mut row) = (self.column, self.row);
+        if flip_x {
+            column = match self.horizontal {
+                true => GRID_COLUMNS - 1 - (self.column + self.length - 1),
+                false => GRID_COLUMNS - 1 - self.column,
+            };
+        }
+        if flip_y {
+            row = match self.horizontal {
+                true => GRID_ROWS - 1 - self.row,
+                false => GRID_ROWS - 1 - (self.row + self.length - 1),
+            };
+        }
+        GridSegment {
+            column,
+            row,
+            length: self.length,
+            horizontal: self.horizontal,
+        }
+    }
+
+    pub fn transform(&self) -> Transform {
+        let length = self.length as f32 * CELL_SIZE;
+        let (x, y, scale_x, scale_y) = if self.horizontal {
+            (
+                WALL_LEFT + self.column as f32 * CELL_SIZE + length / 2.0,
+                WALL_TOP + (self.row as f32 + 0.5) * CELL_SIZE,
+                length,
+                WALL_THICKNESS,
+            )
+        } else {
+            (
+                WALL_LEFT + (self.column as f32 + 0.5) * CELL_SIZE,
+                WALL_TOP + self.row as f32 * CELL_SIZE + length / 2.0,
+                WALL_THICKNESS,
+                length,
+            )
+        };
+        Transform {
+            translation: Vec3 { x, y, z: 2.0 },
+            scale: Vec3 {
+                x: scale_x,
+                y: scale_y,
+                z: 0.0,
+            },
+            ..default()
+        }
+    }
+}
+
+// walls are only rolled for the lower left quarter and mirrored onto the other three,
+// every candidate is dropped again if it would cut one spawn off from the others
+pub fn generate_layout(seed: u64) -> GeneratedLayout {
+    let mut rng = StdRng::seed_from_u64(seed);
+    let mut blocked = vec![false; (GRID_COLUMNS * GRID_ROWS) as usize];
+    for column in 0..GRID_COLUMNS {
+        for row in 0..GRID_ROWS {
+            // the outer walls leave less than a player width in the border ring
+            if column == 0 || row == 0 || column == GRID_COLUMNS - 1 || row == GRID_ROWS - 1 {
+                blocked[cell_index(column, row)] = true;
+            }
+        }
+    }
+    let border_cells = blocked.iter().filter(|cell| **cell).count();
+    let mut segments = Vec::new();
+    let mut placed = 0;
+    for _ in 0..SEGMENT_ATTEMPTS {
+        if placed >= MAX_SEGMENTS_PER_QUARTER {
+            break;
+        }
+        let horizontal = rng.gen_bool(0.5);
+        let segment = GridSegment {
+            column: rng.gen_range(1..GRID_COLUMNS / 2),
+            row: rng.gen_range(1..GRID_ROWS / 2),
+            length: rng.gen_range(2..=5),
+            horizontal,
+        };
+        let end = *segment.cells().last().unwrap();
+        if end.0 >= GRID_COLUMNS / 2 || end.1 >= GRID_ROWS / 2 {
+            continue;
+        }
+        let group = [
+            segment,
+            segment.mirrored(true, false),
+            segment.mirrored(false, true),
+            segment.mirrored(true, true),
+        ];
+        let cells: Vec<(i32, i32)> = group.iter().flat_map(|segment| segment.cells()).collect();
+        if cells
+            .iter()
+            .any(|(column, row)| is_reserved(*column, *row) || blocked[cell_index(*column, *row)])
+        {
+            continue;
+        }
+        let mut candidate = blocked.clone();
+        for (column, row) in &cells {
+            candidate[cell_index(*column, *row)] = true;
+        }
+        let inner_cells = candidate.len() - border_cells;
+        let blocked_cells = candidate.iter().filter(|cell| **cell).count() - border_cells;
+        if blocked_cells as f32 > inner_cells as f32 * MAX_BLOCKED_CELLS {
+            continue;
+        }
+        if !all_spawns_reachable(&candidate) {
+            continue;
+        }
+        blocked = candidate;
+        segments.extend_from_slice(&group);
+        placed += 1;
+    }
+    let mut free_cells = Vec::new();
+    for row in 0..GRID_ROWS {
+        for column in 0..GRID_COLUMNS {
+            if !blocked[cell_index(column, row)] {
+                free_cells.push(cell_center(column, row));
+            }
+        }
+    }
+    GeneratedLayout {
+        segments,
+        free_cells,
+    }
+}
+
+pub fn spawn_generated_level(mut commands: Commands, asset_server: Res<AssetServer>) {
+    // the middle is kept open just like the spawn zones
+    commands.insert_resource(SpawnPoints::with_extra(&[Vec2::ZERO]));
+    spawn_floor(&mut commands, &asset_server);
+}
+
+pub fn regenerate_arena(
+    mut commands: Commands,
+    mut arena: ResMut<ArenaConfig>,
+    existing_walls: Query<Entity, With<GeneratedWall>>,
+    asset_server: Res<AssetServer>,
+    mut game_rng: ResMut<GameRng>,
+    mut free_cells: ResMut<FreeCells>,
+    mut event_reader: EventReader<ResetGameEvent>,
+) {
+    if event_reader.read().last().is_none() {
+        return;
+    }
+    for entity in &existing_walls {
+        commands.entity(entity).despawn();
+    }
+    arena.seed = arena.fixed_seed.unwrap_or_else(|| game_rng.gen());
+    info!("generated arena with seed {}", arena.seed);
+    let layout = generate_layout(arena.seed);
+    free_cells.cells = layout.free_cells;
+    for segment in layout.segments {
+        commands.spawn((
+            create_generated_wall(&segment, &asset_server),
+            Wall {},
+            GeneratedWall,
+        ));
+    }
+}
+
+fn create_generated_wall(segment: &GridSegment, asset_server: &Res<AssetServer>) -> WallBundle {
+    WallBundle {
+        direction: Direction::None,
+        sprite_bundle: SpriteBundle {
+            transform: segment.transform(),
+            sprite: Sprite {
+                custom_size: Option::Some(Vec2 { x: 1.0, y: 1.0 }),
+                ..default()
+            },
+            texture: match segment.horizontal {
+                true => asset_server.load("/assets/images/walls/bricks_100_8.png"),
+                false => asset_server.load("/assets/images/walls/bricks_8_100_rotate.png"),
+            },
+            ..default()
+        },
+        collider: Collider,
+    }
+}
+
+fn cell_index(column: i32, row: i32) -> usize {
+    (row * GRID_COLUMNS + column) as usize
+}
+
+fn cell_center(column: i32, row: i32) -> Vec2 {
+    Vec2::new(
+        WALL_LEFT + (column as f32 + 0.5) * CELL_SIZE,
+        WALL_TOP + (row as f32 + 0.5) * CELL_SIZE,
+    )
+}
+
+fn cell_of(position: Vec2) -> (i32, i32) {
+    (
+        ((position.x - WALL_LEFT) / CELL_SIZE) as i32,
+        ((position.y - WALL_TOP) / CELL_SIZE) as i32,
+    )
+}
+
+fn spawn_points() -> [Vec2; 4] {
+    [1, 2, 3, 4].map(Player::spawn_point)
+}
+
+// spawn zones stay empty and the middle is kept open for power-ups
+fn is_reserved(column: i32, row: i32) -> bool {
+    let center = cell_center(column, row);
+    let near_spawn = spawn_points().iter().any(|spawn| {
+        (center.x - spawn.x).abs() <= SPAWN_CLEARANCE
+            && (center.y - spawn.y).abs() <= SPAWN_CLEARANCE
+    });
+    near_spawn || (center.x.abs() <= CENTER_CLEARANCE && center.y.abs() <= CENTER_CLEARANCE)
+}
+
+fn all_spawns_reachable(blocked: &[bool]) -> bool {
+    let spawns = spawn_points().map(cell_of);
+    let mut visited = vec![false; blocked.len()];
+    let mut queue = VecDeque::from([spawns[0]]);
+    visited[cell_index(spawns[0].0, spawns[0].1)] = true;
+    while let Some((column, row)) = queue.pop_front() {
+        for (next_column, next_row) in [
+            (column + 1, row),
+            (column - 1, row),
+            (column, row + 1),
+            (column, row - 1),
+        ] {
+            if next_column < 0
+                || next_row < 0
+                || next_column >= GRID_COLUMNS
+                || next_row >= GRID_ROWS
+            {
+                continue;
+            }
+            let index = cell_index(next_column, next_row);
+            if blocked[index] || visited[index] {
+                continue;
+            }
+            visited[index] = true;
+            queue.push_back((next_column, next_row));
+        }
+    }
+    spawns
+        .iter()
+        .all(|(column, row)| visited[cell_index(*column, *row)])
+}
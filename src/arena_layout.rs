@@ -0,0 +1,128 @@
+use bevy::{prelude::*, utils::Duration};
+use serde::Deserialize;
+use std::path::PathBuf;
+
+use crate::arena::ArenaKind;
+use crate::game_objects::{
+    Destructible, KinematicWall, MovingWall, RotatingWall, Wall, WallBundle,
+};
+use crate::game_utils::{Collider, Direction};
+
+const ARENA_DIR: &str = "assets/arenas";
+const LEVEL_1: &str = include_str!("../assets/arenas/level1.ron");
+const LEVEL_2: &str = include_str!("../assets/arenas/level2.ron");
+
+// where the walls of the drawn levels are read from, one `<arena name>.ron` each
+#[derive(Resource, Clone)]
+pub struct ArenaFiles {
+    pub dir: PathBuf,
+}
+
+#[derive(Deserialize, Clone, Default)]
+pub struct ArenaLayout {
+    pub walls: Vec<WallSpec>,
+}
+
+#[derive(Deserialize, Clone)]
+pub struct WallSpec {
+    pub position: (f32, f32),
+    pub size: (f32, f32),
+    // in assets/images/walls
+    pub texture: String,
+    // hit points, a wall without them stops every bullet
+    #[serde(default)]
+    pub destructible: Option<i32>,
+    // seconds until a destroyed wall grows back, it stays open without
+    #[serde(default)]
+    pub rebuild: Option<u64>,
+    #[serde(default)]
+    pub motion: Option<WallMotion>,
+}
+
+#[derive(Deserialize, Clone)]
+pub enum WallMotion {
+    // around its own middle, radians per second
+    Spin { speed: f32 },
+    // back and forth between its position and `to`, pixels per second
+    Slide { to: (f32, f32), speed: f32 },
+}
+
+impl Default for ArenaFiles {
+    fn default() -> Self {
+        ArenaFiles {
+            dir: PathBuf::from(ARENA_DIR),
+        }
+    }
+}
+
+impl ArenaLayout {
+    // a missing file plays the layout the game was built with, a broken one too after a warning
+    pub fn load(files: &ArenaFiles, kind: ArenaKind) -> ArenaLayout {
+        let built_in = match kind {
+            ArenaKind::Level1 => LEVEL_1,
+            ArenaKind::Level2 => LEVEL_2,
+            ArenaKind::Generated => return ArenaLayout::default(),
+        };
+        let path = files.dir.join(format!("{}.ron", kind.name()));
+        let content = std::fs::read_to_string(&path).unwrap_or_else(|_| built_in.to_string());
+        match ron::from_str(&content) {
+            Ok(layout) => layout,
+            Err(error) => {
+                warn!("ignoring arena {}: {}", path.display(), error);
+                ron::from_str(built_in).unwrap_or_default()
+            }
+        }
+    }
+
+    pub fn spawn_walls(&self, commands: &mut Commands, asset_server: &Res<AssetServer>) {
+        for spec in &self.walls {
+            let position = Vec2::from(spec.position);
+            let mut wall = commands.spawn((
+                WallBundle {
+                    direction: Direction::None,
+                    sprite_bundle: SpriteBundle {
+                        transform: Transform {
+                            translation: position.extend(2.0),
+                            scale: Vec2::from(spec.size).extend(0.0),
+                            ..default()
+                        },
+                        sprite: Sprite {
+                            custom_size: Option::Some(Vec2 { x: 1.0, y: 1.0 }),
+                            ..default()
+                        },
+                        texture: asset_server
+                            .load(format!("/assets/images/walls/{}", spec.texture)),
+                        ..default()
+                    },
+                    collider: Collider,
+                },
+                Wall {},
+            ));
+            if let Some(hp) = spec.destructible {
+                wall.insert(Destructible::new(hp, spec.rebuild.map(Duration::from_secs)));
+            }
+            match spec.motion {
+                Some(WallMotion::Spin { speed }) => {
+                    wall.insert((
+                        RotatingWall {
+                            pivot: position,
+                            angular_speed: speed,
+                        },
+                        KinematicWall::default(),
+                    ));
+                }
+                Some(WallMotion::Slide { to, speed }) => {
+                    wall.insert((
+                        MovingWall {
+                            waypoints: vec![Vec2::from(to), position],
+                            speed,
+                            next: 0,
+                        },
+                        KinematicWall::default(),
+                    ));
+                }
+                None => (),
+            }
+        }
+    }
+}
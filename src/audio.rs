@@ -0,0 +1,50 @@
+use bevy::prelude::*;
+
+// gameplay only says which sound to play, AudioFxPlugin turns that into actual audio so
+// a headless app can leave it out
+#[derive(Event, Clone, Copy)]
+pub enum SoundEvent {
+    Shot,
+    Hit,
+    HitWall,
+    BouncyWall,
+    Frozen,
+    Explosion,
+    PowerUp,
+}
+
+pub struct AudioFxPlugin;
+
+impl Plugin for AudioFxPlugin {
+    fn build(&self, app: &mut App) {
+        app.add_event::<SoundEvent>()
+            .add_systems(PostUpdate, play_sounds);
+    }
+}
+
+impl SoundEvent {
+    pub fn path(&self) -> &'static str {
+        match self {
+            SoundEvent::Shot => "/assets/sounds/shot.wav",
+            SoundEvent::Hit => "/assets/sounds/hit.wav",
+            SoundEvent::HitWall => "/assets/sounds/hitwall.wav",
+            SoundEvent::BouncyWall => "/assets/sounds/bouncywall.wav",
+            SoundEvent::Frozen => "/assets/sounds/frozen.wav",
+            SoundEvent::Explosion => "/assets/sounds/explosion.wav",
+            SoundEvent::PowerUp => "/assets/sounds/powerup.wav",
+        }
+    }
+}
+
+pub fn play_sounds(
+    mut commands: Commands,
+    asset_server: Res<AssetServer>,
+    mut event_reader: EventReader<SoundEvent>,
+) {
+    for sound in event_reader.read() {
+        commands.spawn(AudioBundle {
+            source: asset_server.load(sound.path()),
+            ..default()
+        });
+    }
+}
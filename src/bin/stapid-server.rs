@@ -0,0 +1,6 @@
+use stapid::server::{self, ServerConfig};
+
+// a port that is taken ends the server with an error instead of a silent exit
+fn main() -> Result<(), String> {
+    server::run(ServerConfig::from_args())
+}
@@ -0,0 +1,560 @@
+use bevy::{
+    input::InputSystem,
+    prelude::*,
+    time::{TimeSystem, TimeUpdateStrategy},
+    utils::{Duration, HashMap, Instant},
+};
+use std::{
+    collections::VecDeque,
+    io::ErrorKind,
+    net::{Ipv4Addr, SocketAddr, UdpSocket},
+};
+
+use crate::arena::{ArenaConfig, ArenaKind};
+use crate::game_objects::{get_direction_sprite, Destructible, Player};
+use crate::game_utils::{
+    Bindings, BulletType, Collider, GameState, PlayerSlots, ResetGameEvent, UpdateUIEvent,
+};
+use crate::input::move_all_players;
+use crate::netcode::NetSetup;
+use crate::profiles::Profiles;
+use crate::replay::{apply_input, read_input, TICK};
+use crate::round::RoundState;
+use crate::rules::MatchRules;
+use crate::server::{
+    direction_code, direction_from_code, power_code, wall_order, ClientMessage, ObjectKind,
+    ObjectState, ServerMessage, Snapshot,
+};
+use crate::StapidSet;
+
+// remote players and objects are drawn this far in the past, between two snapshots
+pub const INTERPOLATION: Duration = Duration::from_millis(100);
+// unacknowledged inputs repeated in every packet, a lost one is covered by the next
+const RESEND: usize = 16;
+// a prediction further off than this jumps to the server's position instead of catching up
+const SNAP_DISTANCE: f32 = 100.0;
+const SNAPSHOTS_KEPT: usize = 30;
+const JOIN_EVERY: Duration = Duration::from_millis(250);
+const MAX_BUDGET: Duration = Duration::from_micros(4 * 16_667);
+
+pub struct ClientPlugin;
+
+// `--connect <address>` plays on a `stapid-server`
+#[derive(Resource, Clone)]
+pub struct ClientConfig {
+    pub server: SocketAddr,
+}
+
+#[derive(Clone, Copy, PartialEq, Debug)]
+pub enum LinkPhase {
+    Connecting,
+    Running,
+    Refused,
+}
+
+#[derive(Resource)]
+pub struct ServerLink {
+    pub phase: LinkPhase,
+    pub player: usize,
+    pub players: usize,
+    pub snapshots_received: u32,
+    // how often the own player had to be moved to where the server has it, and by how much
+    // the last time
+    pub corrections: u32,
+    pub last_error: f32,
+    socket: UdpSocket,
+    server: SocketAddr,
+    last_join: Option<Instant>,
+    seq: u32,
+    // inputs the server has not acknowledged and where they moved the own player
+    pending: VecDeque<(u32, u16)>,
+    predicted: VecDeque<(u32, Vec2)>,
+    snapshots: VecDeque<(Instant, Snapshot)>,
+    fresh: bool,
+    round: u32,
+    advancing: bool,
+    budget: Duration,
+    last_frame: Option<Instant>,
+    raw_keys: Option<Input<KeyCode>>,
+    // keys pressed on frames that simulate no tick, they go out with the next one
+    pressed: u16,
+}
+
+// something the server sent, drawn at its interpolated position
+#[derive(Component)]
+pub struct Replicated {
+    pub id: u64,
+}
+
+impl Plugin for ClientPlugin {
+    fn build(&self, app: &mut App) {
+        if let Some(config) = ClientConfig::from_args() {
+            app.insert_resource(config);
+        }
+        app.add_systems(
+            PreStartup,
+            connect_to_server.run_if(resource_exists::<ClientConfig>()),
+        )
+        .add_systems(First, pace_link.before(TimeSystem).run_if(connected))
+        .add_systems(PreUpdate, send_input.after(InputSystem).run_if(connected))
+        .add_systems(
+            Update,
+            (move_all_players, apply_snapshot)
+                .chain()
+                .after(StapidSet::Input)
+                .before(StapidSet::Effects)
+                .run_if(connected)
+                .run_if(in_state(GameState::Playing)),
+        )
+        .add_systems(Last, restore_keys.run_if(connected))
+        // the server decides about hits, spawns and the round, the client only moves its own
+        // player ahead of it
+        .configure_sets(
+            FixedUpdate,
+            (
+                StapidSet::Collision,
+                StapidSet::WallDamage,
+                StapidSet::MoveBullets,
+            )
+                .run_if(not(connected)),
+        )
+        .configure_sets(
+            Update,
+            (
+                StapidSet::Spawn,
+                StapidSet::Input,
+                StapidSet::Round,
+                StapidSet::KillCam,
+            )
+                .run_if(not(connected)),
+        );
+    }
+}
+
+impl ClientConfig {
+    pub fn from_args() -> Option<ClientConfig> {
+        let args: Vec<String> = std::env::args().collect();
+        let index = args.iter().position(|arg| arg == "--connect")?;
+        let server = args.get(index + 1)?.parse().ok()?;
+        Some(ClientConfig { server })
+    }
+}
+
+pub fn connected(link: Option<Res<ServerLink>>) -> bool {
+    link.is_some()
+}
+
+pub fn connect_to_server(mut commands: Commands, config: Res<ClientConfig>) {
+    let socket = UdpSocket::bind((Ipv4Addr::UNSPECIFIED, 0)).and_then(|socket| {
+        socket.set_nonblocking(true)?;
+        Ok(socket)
+    });
+    let socket = match socket {
+        Ok(socket) => socket,
+        Err(error) => {
+            error!("could not open a socket: {}", error);
+            return;
+        }
+    };
+    info!("connecting to {}", config.server);
+    commands.insert_resource(ServerLink {
+        phase: LinkPhase::Connecting,
+        player: 0,
+        players: 0,
+        snapshots_received: 0,
+        corrections: 0,
+        last_error: 0.0,
+        socket,
+        server: config.server,
+        last_join: None,
+        seq: 0,
+        pending: VecDeque::new(),
+        predicted: VecDeque::new(),
+        snapshots: VecDeque::new(),
+        fresh: false,
+        round: 0,
+        advancing: false,
+        budget: TICK,
+        last_frame: None,
+        raw_keys: None,
+        pressed: 0,
+    });
+    commands.insert_resource(TimeUpdateStrategy::ManualDuration(Duration::ZERO));
+}
+
+impl ServerLink {
+    fn send(&self, message: &ClientMessage) {
+        // the server not being up yet looks the same as a lost packet
+        let _ = self.socket.send_to(&message.encode(), self.server);
+    }
+
+    fn receive(&self) -> Vec<ServerMessage> {
+        let mut messages = Vec::new();
+        let mut buffer = vec![0; 65536];
+        loop {
+            match self.socket.recv_from(&mut buffer) {
+                Ok((size, address)) if address == self.server => {
+                    messages.extend(ServerMessage::decode(&buffer[..size]));
+                }
+                Ok(_) => (),
+                Err(error) if error.kind() == ErrorKind::WouldBlock => break,
+                Err(_) => continue,
+            }
+        }
+        messages
+    }
+
+    pub fn latest(&self) -> Option<&Snapshot> {
+        self.snapshots.back().map(|(_, snapshot)| snapshot)
+    }
+
+    // the two snapshots around the moment drawn right now and how far between them it is
+    fn interpolation(&self, now: Instant) -> Option<(&Snapshot, &Snapshot, f32)> {
+        let render = now.checked_sub(INTERPOLATION)?;
+        let after = self.snapshots.iter().position(|(at, _)| *at > render);
+        let (first, last) = (self.snapshots.front()?, self.snapshots.back()?);
+        Some(match after {
+            None => (&last.1, &last.1, 1.0),
+            Some(0) => (&first.1, &first.1, 0.0),
+            Some(index) => {
+                let (from_at, from) = &self.snapshots[index - 1];
+                let (to_at, to) = &self.snapshots[index];
+                let span = (*to_at - *from_at).as_secs_f32().max(f32::EPSILON);
+                (
+                    from,
+                    to,
+                    ((render - *from_at).as_secs_f32() / span).min(1.0),
+                )
+            }
+        })
+    }
+}
+
+// sends the join until the server answers, then takes in snapshots and decides whether this
+// frame moves the own player a tick ahead
+#[allow(clippy::too_many_arguments)]
+pub fn pace_link(
+    mut link: ResMut<ServerLink>,
+    mut strategy: ResMut<TimeUpdateStrategy>,
+    mut rules: ResMut<MatchRules>,
+    mut arena: ResMut<ArenaConfig>,
+    mut slots: ResMut<PlayerSlots>,
+    mut next_state: ResMut<NextState<GameState>>,
+) {
+    let now = Instant::now();
+    for message in link.receive() {
+        match message {
+            ServerMessage::Accepted {
+                player,
+                players,
+                setup,
+            } if link.phase == LinkPhase::Connecting => {
+                info!("joined as player {} of {}", player, players);
+                let NetSetup {
+                    rules: server_rules,
+                    arena: arena_name,
+                    arena_seed,
+                    ..
+                } = setup;
+                *rules = server_rules;
+                *arena = ArenaConfig {
+                    kind: ArenaKind::from_name(&arena_name).unwrap_or(ArenaKind::Level1),
+                    fixed_seed: arena_seed,
+                    seed: arena_seed.unwrap_or(0),
+                };
+                *slots = PlayerSlots::networked(players as usize, player as usize);
+                link.player = player as usize;
+                link.players = players as usize;
+                link.phase = LinkPhase::Running;
+                next_state.set(GameState::Playing);
+            }
+            ServerMessage::Accepted { .. } => (),
+            ServerMessage::Full if link.phase == LinkPhase::Connecting => {
+                error!("the server is full");
+                link.phase = LinkPhase::Refused;
+            }
+            ServerMessage::Full => (),
+            ServerMessage::Snapshot(snapshot) => {
+                if link
+                    .latest()
+                    .is_some_and(|latest| latest.tick >= snapshot.tick)
+                {
+                    continue;
+                }
+                link.snapshots.push_back((now, snapshot));
+                if link.snapshots.len() > SNAPSHOTS_KEPT {
+                    link.snapshots.pop_front();
+                }
+                link.snapshots_received += 1;
+                link.fresh = true;
+            }
+        }
+    }
+    let elapsed = link.last_frame.map_or(Duration::ZERO, |last| now - last);
+    link.last_frame = Some(now);
+    link.advancing = false;
+    match link.phase {
+        LinkPhase::Connecting => {
+            if link.last_join.is_none_or(|last| now - last > JOIN_EVERY) {
+                link.send(&ClientMessage::Join);
+                link.last_join = Some(now);
+            }
+        }
+        LinkPhase::Running => {
+            link.budget = (link.budget + elapsed).min(MAX_BUDGET);
+            if link.budget >= TICK {
+                link.budget -= TICK;
+                link.advancing = true;
+            }
+        }
+        LinkPhase::Refused => (),
+    }
+    *strategy = TimeUpdateStrategy::ManualDuration(match link.advancing {
+        true => TICK,
+        false => Duration::ZERO,
+    });
+}
+
+// the player steers with the keys of their profile, the input goes to the server and onto
+// the own player's keys for the prediction
+pub fn send_input(
+    mut link: ResMut<ServerLink>,
+    mut keys: ResMut<Input<KeyCode>>,
+    profiles: Res<Profiles>,
+) {
+    let raw_keys = keys.clone();
+    let mut mapped = Input::default();
+    let input = read_input(&profiles.local_bindings(), &raw_keys);
+    link.pressed |= input & 0xff00;
+    if link.advancing {
+        let input = input | std::mem::take(&mut link.pressed);
+        link.seq += 1;
+        let seq = link.seq;
+        link.pending.push_back((seq, input));
+        let resent = link.pending.len().saturating_sub(RESEND);
+        link.send(&ClientMessage::Input {
+            player: link.player as u8,
+            first: link.pending[resent].0,
+            inputs: link
+                .pending
+                .iter()
+                .skip(resent)
+                .map(|(_, input)| *input)
+                .collect(),
+        });
+        apply_input(&Bindings::for_slot(link.player as i32), input, &mut mapped);
+    }
+    link.raw_keys = Some(raw_keys);
+    *keys = mapped;
+}
+
+pub fn restore_keys(mut link: ResMut<ServerLink>, mut keys: ResMut<Input<KeyCode>>) {
+    if let Some(raw_keys) = link.raw_keys.take() {
+        *keys = raw_keys;
+    }
+}
+
+// corrects the own prediction with the newest snapshot and puts everything else where it was
+// a moment ago
+#[allow(clippy::too_many_arguments)]
+#[allow(clippy::type_complexity)]
+pub fn apply_snapshot(
+    mut commands: Commands,
+    mut link: ResMut<ServerLink>,
+    mut players: Query<(
+        &mut Player,
+        &mut Transform,
+        &mut Visibility,
+        &mut Handle<Image>,
+    )>,
+    mut objects: Query<(Entity, &Replicated, &mut Transform), Without<Player>>,
+    mut walls: Query<
+        (Entity, &Transform, &mut Visibility),
+        (With<Destructible>, Without<Player>, Without<Replicated>),
+    >,
+    mut round: ResMut<RoundState>,
+    mut arena: ResMut<ArenaConfig>,
+    asset_server: Res<AssetServer>,
+    mut event_writer: EventWriter<UpdateUIEvent>,
+    mut event_writer_reset: EventWriter<ResetGameEvent>,
+) {
+    let own = link.player as i32;
+    if link.advancing {
+        let seq = link.seq;
+        if let Some((_, transform, _, _)) = players
+            .iter()
+            .find(|(player, _, _, _)| player.player_number == own)
+        {
+            let position = transform.translation.truncate();
+            link.predicted.push_back((seq, position));
+        }
+    }
+    let now = Instant::now();
+    if std::mem::take(&mut link.fresh) {
+        let Some(latest) = link.latest().cloned() else {
+            return;
+        };
+        if latest.round != link.round {
+            link.round = latest.round;
+            link.predicted.clear();
+            arena.fixed_seed = Some(latest.arena_seed);
+            event_writer_reset.send_default();
+            return;
+        }
+        link.pending.retain(|(seq, _)| *seq > latest.ack);
+        let link = link.as_mut();
+        for (mut player, mut transform, mut visibility, _) in &mut players {
+            if player.player_number != own {
+                continue;
+            }
+            let Some(state) = latest.players.iter().find(|state| state.number == own) else {
+                *visibility = Visibility::Hidden;
+                continue;
+            };
+            *visibility = Visibility::Inherited;
+            let predicted = link
+                .predicted
+                .iter()
+                .find(|(seq, _)| *seq == latest.ack)
+                .map(|(_, position)| *position);
+            let current = transform.translation.truncate();
+            let error = state.position - predicted.unwrap_or(current);
+            link.last_error = error.length();
+            if error.length() > SNAP_DISTANCE {
+                transform.translation = state.position.extend(transform.translation.z);
+                link.predicted.clear();
+                link.corrections += 1;
+            } else if predicted.is_some() && error.length() > 0.01 {
+                transform.translation += error.extend(0.0);
+                for (_, position) in link.predicted.iter_mut() {
+                    *position += error;
+                }
+                link.corrections += 1;
+            }
+            link.predicted.retain(|(seq, _)| *seq > latest.ack);
+            let power = state
+                .power
+                .and_then(|power| BulletType::convert_int(power as i32));
+            if player.lifes != state.lifes
+                || player.power_up_type.as_ref().and_then(power_code) != state.power
+            {
+                event_writer.send(UpdateUIEvent {
+                    player_number: own as usize,
+                });
+            }
+            player.lifes = state.lifes;
+            player.powerup = power.is_some();
+            player.power_up_type = power;
+            player.stunned = state.stunned;
+            player.invulnerable = state.invulnerable;
+        }
+        let mut order: Vec<(Entity, &Transform, Mut<Visibility>)> = walls.iter_mut().collect();
+        let positions = wall_order(order.iter().map(|(_, transform, _)| *transform));
+        for (position, index) in positions.into_iter().enumerate() {
+            let (entity, _, visibility) = &mut order[index];
+            let broken = latest.broken.contains(&(position as u16));
+            match (broken, **visibility == Visibility::Hidden) {
+                (true, false) => {
+                    **visibility = Visibility::Hidden;
+                    commands.entity(*entity).remove::<Collider>();
+                }
+                (false, true) => {
+                    **visibility = Visibility::Inherited;
+                    commands.entity(*entity).insert(Collider);
+                }
+                _ => (),
+            }
+        }
+        round.over = latest.over;
+        round.winner = latest.winner;
+        round.sudden_death = latest.sudden_death;
+        round.timer = latest
+            .remaining_ms
+            .map(|remaining| Timer::new(Duration::from_millis(remaining as u64), TimerMode::Once));
+    }
+
+    let Some((from, to, t)) = link.interpolation(now) else {
+        return;
+    };
+    for (mut player, mut transform, mut visibility, mut sprite) in &mut players {
+        if player.player_number == own {
+            continue;
+        }
+        let Some(state) = to
+            .players
+            .iter()
+            .find(|state| state.number == player.player_number)
+        else {
+            *visibility = Visibility::Hidden;
+            continue;
+        };
+        let start = from
+            .players
+            .iter()
+            .find(|from| from.number == state.number)
+            .map_or(state.position, |from| from.position);
+        transform.translation = start
+            .lerp(state.position, t)
+            .extend(transform.translation.z);
+        *visibility = Visibility::Inherited;
+        let direction_x = direction_from_code(state.direction_x);
+        let direction_y = direction_from_code(state.direction_y);
+        let turned = direction_code(&player.direction.direction_x) != state.direction_x
+            || direction_code(&player.direction.direction_y) != state.direction_y;
+        if turned {
+            *sprite = asset_server.load(get_direction_sprite(&direction_x, &direction_y));
+            player.direction.direction_x = direction_x;
+            player.direction.direction_y = direction_y;
+        }
+        player.lifes = state.lifes;
+    }
+    let targets: HashMap<u64, &ObjectState> = to
+        .objects
+        .iter()
+        .map(|object| (object.id, object))
+        .collect();
+    let starts: HashMap<u64, Vec2> = from
+        .objects
+        .iter()
+        .map(|object| (object.id, object.position))
+        .collect();
+    let mut shown = Vec::new();
+    for (entity, replicated, mut transform) in &mut objects {
+        let Some(object) = targets.get(&replicated.id) else {
+            commands.entity(entity).despawn();
+            continue;
+        };
+        let start = starts.get(&object.id).copied().unwrap_or(object.position);
+        transform.translation = start
+            .lerp(object.position, t)
+            .extend(transform.translation.z);
+        transform.scale = object.size.extend(1.0);
+        shown.push(object.id);
+    }
+    for object in to
+        .objects
+        .iter()
+        .filter(|object| !shown.contains(&object.id))
+    {
+        let z = match object.kind {
+            ObjectKind::Totem => 1.0,
+            _ => 2.0,
+        };
+        commands.spawn((
+            Replicated { id: object.id },
+            SpriteBundle {
+                sprite: Sprite {
+                    custom_size: Some(Vec2::ONE),
+                    ..default()
+                },
+                texture: asset_server.load(object.kind.texture()),
+                transform: Transform {
+                    translation: object.position.extend(z),
+                    scale: object.size.extend(1.0),
+                    ..default()
+                },
+                ..default()
+            },
+        ));
+    }
+}
@@ -1,62 +1,146 @@
-use crate::game_objects::{get_direction_sprite, Bullet, Explosion, Player, PowerUp};
-use crate::game_utils::{
-    AnimationTimer, BulletType, Collider, DirectionHelper, HitCooldownTimer, PlayerHitEvent,
-    PlayerPowerUpEvent, TimerType, UpdateUIEvent,
+use crate::audio::SoundEvent;
+use crate::constants::{
+    BOTTOM_BOUND, LEFT_BOUND, RIGHT_BOUND, SHOVE_KNOCKBACK, SHOVE_REACH, TOP_BOUND,
+};
+use crate::game_objects::{
+    get_direction_sprite, Bullet, Destructible, Explosion, Hazard, KinematicWall, Player, PowerUp,
+    Teleported,
 };
-use bevy::{
-    prelude::*, sprite::collide_aabb::collide, sprite::collide_aabb::Collision, utils::Duration,
+use crate::game_utils::{
+    AnimationTimer, BulletType, Collider, HitCooldownTimer, PlayerDamageEvent, PlayerHitEvent,
+    PlayerPowerUpEvent, PowerUpPickedEvent, TimerType, UpdateUIEvent, WallDamageEvent,
 };
+use crate::geometry::{reflect, Obb};
+use crate::rng::GameRng;
+use crate::rules::MatchRules;
+use bevy::{prelude::*, sprite::collide_aabb::collide};
 use rand::prelude::*;
 
+pub type WallFilter = (
+    With<Collider>,
+    Without<Player>,
+    Without<Bullet>,
+    Without<PowerUp>,
+    Without<Explosion>,
+);
+
+#[allow(clippy::too_many_arguments)]
+#[allow(clippy::type_complexity)]
 pub fn collision_explosion(
     mut commands: Commands,
-    mut player_query: Query<(&Transform, &mut Handle<Image>, &mut Player)>,
-    mut collider_query: Query<(Entity, &Transform, &Explosion), With<Collider>>,
+    mut player_query: Query<(&mut Transform, &mut Handle<Image>, &mut Player)>,
+    explosion_query: Query<(&Transform, &Explosion), (Added<Explosion>, Without<Player>)>,
+    wall_query: Query<&Transform, WallFilter>,
+    destructible_query: Query<(Entity, &Transform), (With<Destructible>, WallFilter)>,
     mut event_writer: EventWriter<UpdateUIEvent>,
     mut event_writer_player_hit: EventWriter<PlayerHitEvent>,
+    mut event_writer_damage: EventWriter<PlayerDamageEvent>,
+    mut event_writer_wall: EventWriter<WallDamageEvent>,
+    rules: Res<MatchRules>,
     asset_server: ResMut<AssetServer>,
 ) {
-    for (player_transform, mut player_sprite, mut player) in &mut player_query {
-        if player.invulnerable {
-            continue;
-        }
-        for (_collider_entity, transform, _explosion) in &mut collider_query {
-            let collision = collide(
-                transform.translation,
-                transform.scale.truncate(),
-                player_transform.translation,
-                player_transform.scale.truncate(),
-            );
-            if collision.is_some() {
-                *player_sprite = asset_server.load(get_direction_sprite(
-                    &player.direction.direction_x,
-                    &player.direction.direction_y,
-                ));
-                player.lifes -= 2;
-                event_writer.send(UpdateUIEvent {
-                    player_number: player.player_number as usize,
+    for (explosion_transform, explosion) in &explosion_query {
+        let center = explosion_transform.translation.truncate();
+        for (wall_entity, wall_transform) in &destructible_query {
+            let distance = Obb::from_transform(wall_transform).distance_to(center);
+            if distance <= explosion.radius {
+                event_writer_wall.send(WallDamageEvent {
+                    wall: wall_entity,
+                    damage: explosion.damage_at(distance),
                 });
-                if player.lifes > 0 {
-                    player.invulnerable = true;
-                    commands.spawn((HitCooldownTimer {
-                        timer: Timer::new(Duration::from_secs(2), TimerMode::Once),
-                        associated_player: player.name.clone(),
-                        timer_type: TimerType::Invulnerable,
-                    },));
-                    event_writer_player_hit.send_default();
-                }
             }
         }
+        for (mut player_transform, mut player_sprite, mut player) in &mut player_query {
+            if player.is_invulnerable() {
+                continue;
+            }
+            let player_position = player_transform.translation.truncate();
+            let player_size = player_transform.scale.truncate();
+            let distance = Obb::from_aabb(player_position, player_size).distance_to(center);
+            if distance > explosion.radius {
+                continue;
+            }
+            let blocked = wall_query.iter().any(|wall| {
+                let wall = Obb::from_transform(wall);
+                !wall.contains(center) && wall.segment_hits(center, player_position)
+            });
+            if blocked {
+                continue;
+            }
+            let strength = 1.0 - distance / explosion.radius;
+            let damage = explosion.damage_at(distance);
+            let push =
+                (player_position - center).normalize_or_zero() * explosion.knockback * strength;
+            let target = knockback_position(player_position, player_size, push, &wall_query);
+            player_transform.translation.x = target.x;
+            player_transform.translation.y = target.y;
+
+            *player_sprite = asset_server.load(get_direction_sprite(
+                &player.direction.direction_x,
+                &player.direction.direction_y,
+            ));
+            event_writer_damage.send(PlayerDamageEvent {
+                victim: player.player_number,
+                attacker: explosion.owner,
+                bullet_type: Some(BulletType::ExplosiveBullet),
+                damage: damage.min(player.lifes),
+                stunned: false,
+                lethal: player.lifes > 0 && player.lifes <= damage,
+            });
+            player.lifes -= damage;
+            player.last_hit_by = explosion.owner;
+            event_writer.send(UpdateUIEvent {
+                player_number: player.player_number as usize,
+            });
+            if player.lifes > 0 {
+                player.invulnerable = true;
+                commands.spawn((HitCooldownTimer {
+                    timer: Timer::new(rules.invulnerability(), TimerMode::Once),
+                    associated_player: player.player_number,
+                    timer_type: TimerType::Invulnerable,
+                },));
+                event_writer_player_hit.send_default();
+            }
+        }
+    }
+}
+
+fn knockback_position(
+    position: Vec2,
+    size: Vec2,
+    push: Vec2,
+    wall_query: &Query<&Transform, WallFilter>,
+) -> Vec2 {
+    let steps = (push.length() / 4.0).ceil().max(1.0) as i32;
+    let step = push / steps as f32;
+    let mut current = position;
+    for _ in 0..steps {
+        let next = Vec2::new(
+            (current.x + step.x).clamp(LEFT_BOUND, RIGHT_BOUND),
+            (current.y + step.y).clamp(TOP_BOUND, BOTTOM_BOUND),
+        );
+        let player = Obb::from_aabb(next, size);
+        let hits_wall = wall_query
+            .iter()
+            .any(|wall| Obb::from_transform(wall).penetration(&player).is_some());
+        if hits_wall {
+            break;
+        }
+        current = next;
     }
+    current
 }
 
+#[allow(clippy::too_many_arguments)]
 pub fn collision_powerup(
     mut commands: Commands,
     mut player_query: Query<(&Transform, &mut Player)>,
     mut collider_query: Query<(Entity, &Transform, &PowerUp), With<Collider>>,
     mut event_writer: EventWriter<UpdateUIEvent>,
     mut event_writer_powerup: EventWriter<PlayerPowerUpEvent>,
-    asset_server: ResMut<AssetServer>,
+    mut event_writer_picked: EventWriter<PowerUpPickedEvent>,
+    mut event_writer_sound: EventWriter<SoundEvent>,
+    mut game_rng: ResMut<GameRng>,
 ) {
     for (player_transform, mut player) in &mut player_query {
         for (collider_entity, transform, _maybe_powerup) in &mut collider_query {
@@ -67,27 +151,31 @@ pub fn collision_powerup(
                 player_transform.scale.truncate(),
             );
             if collision.is_some() {
-                let mut rng = rand::thread_rng();
-                let bullet_random = rng.gen_range(0..=2);
+                let bullet_random = game_rng.gen_range(0..=2);
                 player.powerup = true;
                 player.power_up_type = BulletType::convert_int(bullet_random);
+                if let Some(bullet_type) = player.power_up_type.clone() {
+                    event_writer_picked.send(PowerUpPickedEvent {
+                        player_number: player.player_number,
+                        bullet_type,
+                    });
+                }
                 commands.entity(collider_entity).despawn();
                 event_writer.send(UpdateUIEvent {
                     player_number: player.player_number as usize,
                 });
                 event_writer_powerup.send_default();
 
-                commands.spawn(AudioBundle {
-                    source: asset_server.load("/assets/sounds/powerup.wav"),
-                    ..default()
-                });
+                event_writer_sound.send(SoundEvent::PowerUp);
             }
         }
     }
 }
 
+#[allow(clippy::too_many_arguments)]
 #[allow(clippy::type_complexity)]
 pub fn collision_player(
+    mut commands: Commands,
     collider_query: Query<
         &Transform,
         (
@@ -95,54 +183,260 @@ pub fn collision_player(
             Without<Bullet>,
             Without<PowerUp>,
             Without<Explosion>,
+            Without<Player>,
+            Without<KinematicWall>,
         ),
     >,
-    mut player_query: Query<(&Transform, &mut Player)>,
+    kinematic_query: Query<&Transform, (With<KinematicWall>, With<Collider>, Without<Player>)>,
+    mut player_query: Query<(&mut Transform, &mut Player)>,
+    mut event_writer: EventWriter<UpdateUIEvent>,
+    mut event_writer_player_hit: EventWriter<PlayerHitEvent>,
+    mut event_writer_damage: EventWriter<PlayerDamageEvent>,
+    rules: Res<MatchRules>,
+    mut event_writer_sound: EventWriter<SoundEvent>,
 ) {
-    for (player_transform, mut player) in &mut player_query {
-        let mut b_was_collision_up = false;
-        let mut b_was_collision_down = false;
-        let mut b_was_collision_right = false;
-        let mut b_was_collision_left = false;
-        for transform in collider_query.iter() {
-            if let Some(direction) = collide(
-                transform.translation,
+    for (mut player_transform, mut player) in &mut player_query {
+        let size = player_transform.scale.truncate();
+        // moving and rotating walls shove the player, without room left the player gets crushed
+        for wall_transform in &kinematic_query {
+            let position = player_transform.translation.truncate();
+            let Some(push) =
+                Obb::from_transform(wall_transform).penetration(&Obb::from_aabb(position, size))
+            else {
+                continue;
+            };
+            let pushed = position + push;
+            let pushed_body = Obb::from_aabb(pushed, size);
+            let crushed = pushed.x < LEFT_BOUND
+                || pushed.x > RIGHT_BOUND
+                || pushed.y < TOP_BOUND
+                || pushed.y > BOTTOM_BOUND
+                || collider_query.iter().any(|transform| {
+                    Obb::from_transform(transform)
+                        .penetration(&pushed_body)
+                        .is_some()
+                });
+            if !crushed {
+                player_transform.translation.x = pushed.x;
+                player_transform.translation.y = pushed.y;
+                continue;
+            }
+            if player.is_invulnerable() {
+                continue;
+            }
+            // the arena took this life, nobody gets the kill for it
+            player.last_hit_by = None;
+            player.decrement_life();
+            event_writer_damage.send(PlayerDamageEvent {
+                victim: player.player_number,
+                attacker: None,
+                bullet_type: None,
+                damage: 1,
+                stunned: false,
+                lethal: player.lifes == 0,
+            });
+            event_writer.send(UpdateUIEvent {
+                player_number: player.player_number as usize,
+            });
+            event_writer_sound.send(SoundEvent::Hit);
+            if player.lifes > 0 {
+                event_writer_player_hit.send_default();
+                player.invulnerable = true;
+                commands.spawn((HitCooldownTimer {
+                    timer: Timer::new(rules.invulnerability(), TimerMode::Once),
+                    associated_player: player.player_number,
+                    timer_type: TimerType::Invulnerable,
+                },));
+            }
+        }
+    }
+}
+
+// players never stand inside each other, a shoving player knocks whoever is in front away
+pub fn collision_player_bodies(
+    mut player_query: Query<(Entity, &mut Transform, &mut Player)>,
+    wall_query: Query<&Transform, WallFilter>,
+    mut event_writer_sound: EventWriter<SoundEvent>,
+) {
+    let mut bodies: Vec<(Entity, Vec2, Vec2)> = player_query
+        .iter()
+        .map(|(entity, transform, _)| {
+            (
+                entity,
+                transform.translation.truncate(),
                 transform.scale.truncate(),
-                player_transform.translation,
-                player_transform.scale.truncate() + 1.15,
-            ) {
-                match direction {
-                    Collision::Right => {
-                        player.direction_block.right = true;
-                        b_was_collision_right = true;
-                    }
-                    Collision::Left => {
-                        player.direction_block.left = true;
-                        b_was_collision_left = true;
+            )
+        })
+        .collect();
+    for first in 0..bodies.len() {
+        for second in first + 1..bodies.len() {
+            let (_, position, size) = bodies[first];
+            let (_, other_position, other_size) = bodies[second];
+            let Some(push) = Obb::from_aabb(position, size)
+                .penetration(&Obb::from_aabb(other_position, other_size))
+            else {
+                continue;
+            };
+            // whatever the first player can't give way because of a wall the second one has to
+            let moved = knockback_position(position, size, -push / 2.0, &wall_query) - position;
+            bodies[first].1 += moved;
+            bodies[second].1 =
+                knockback_position(other_position, other_size, push + moved, &wall_query);
+        }
+    }
+
+    let mut shoves = Vec::new();
+    for (entity, _, player) in &player_query {
+        if player.shoving {
+            shoves.push((entity, player.direction.to_vec2().normalize_or_zero()));
+        }
+    }
+    let mut stopped = Vec::new();
+    for (shover, facing) in shoves {
+        let Some((_, position, size)) = bodies
+            .iter()
+            .find(|(entity, _, _)| *entity == shover)
+            .copied()
+        else {
+            continue;
+        };
+        // the dash itself stops at the other player, so look a little ahead
+        let reach = Obb::from_aabb(position + facing * SHOVE_REACH, size);
+        for (entity, target_position, target_size) in bodies.iter_mut() {
+            if *entity == shover
+                || (*target_position - position).dot(facing) <= 0.0
+                || reach
+                    .penetration(&Obb::from_aabb(*target_position, *target_size))
+                    .is_none()
+            {
+                continue;
+            }
+            *target_position = knockback_position(
+                *target_position,
+                *target_size,
+                facing * SHOVE_KNOCKBACK,
+                &wall_query,
+            );
+            stopped.push(shover);
+            event_writer_sound.send(SoundEvent::Hit);
+        }
+    }
+
+    for (entity, position, _) in bodies {
+        let Ok((_, mut transform, mut player)) = player_query.get_mut(entity) else {
+            continue;
+        };
+        transform.translation.x = position.x;
+        transform.translation.y = position.y;
+        if stopped.contains(&entity) {
+            player.shoving = false;
+            player.momentum = Vec2::ZERO;
+        }
+    }
+}
+
+#[allow(clippy::too_many_arguments)]
+#[allow(clippy::type_complexity)]
+pub fn collision_hazards(
+    mut commands: Commands,
+    mut player_query: Query<(Entity, &mut Transform, &mut Player, Option<&Teleported>)>,
+    mut bullet_query: Query<
+        (Entity, &mut Transform, Option<&Teleported>),
+        (With<Bullet>, Without<Player>),
+    >,
+    hazard_query: Query<(&Transform, &Hazard), (Without<Player>, Without<Bullet>)>,
+    wall_query: Query<&Transform, WallFilter>,
+    mut event_writer: EventWriter<UpdateUIEvent>,
+    mut event_writer_player_hit: EventWriter<PlayerHitEvent>,
+    mut event_writer_damage: EventWriter<PlayerDamageEvent>,
+    rules: Res<MatchRules>,
+    mut event_writer_sound: EventWriter<SoundEvent>,
+    time: Res<Time>,
+) {
+    for (entity, mut player_transform, mut player, teleported) in &mut player_query {
+        let position = player_transform.translation.truncate();
+        let size = player_transform.scale.truncate();
+        let mut on_teleporter = false;
+        player.on_ice = false;
+        for (hazard_transform, hazard) in &hazard_query {
+            if !Obb::from_transform(hazard_transform).contains(position) {
+                continue;
+            }
+            match hazard {
+                Hazard::Ice => {
+                    player.on_ice = true;
+                }
+                Hazard::Spikes => {
+                    if player.is_invulnerable() {
+                        continue;
                     }
-                    Collision::Top => {
-                        player.direction_block.up = true;
-                        b_was_collision_up = true;
+                    player.last_hit_by = None;
+                    player.decrement_life();
+                    event_writer_damage.send(PlayerDamageEvent {
+                        victim: player.player_number,
+                        attacker: None,
+                        bullet_type: None,
+                        damage: 1,
+                        stunned: false,
+                        lethal: player.lifes == 0,
+                    });
+                    event_writer.send(UpdateUIEvent {
+                        player_number: player.player_number as usize,
+                    });
+                    event_writer_sound.send(SoundEvent::Hit);
+                    if player.lifes > 0 {
+                        event_writer_player_hit.send_default();
+                        player.invulnerable = true;
+                        commands.spawn((HitCooldownTimer {
+                            timer: Timer::new(rules.invulnerability(), TimerMode::Once),
+                            associated_player: player.player_number,
+                            timer_type: TimerType::Invulnerable,
+                        },));
                     }
-                    Collision::Bottom => {
-                        player.direction_block.down = true;
-                        b_was_collision_down = true;
+                }
+                Hazard::Teleporter { target } => {
+                    on_teleporter = true;
+                    if teleported.is_none() {
+                        player_transform.translation.x = target.x;
+                        player_transform.translation.y = target.y;
+                        commands.entity(entity).insert(Teleported);
                     }
-                    Collision::Inside => (),
+                }
+                Hazard::Conveyor { push } => {
+                    let target = knockback_position(
+                        player_transform.translation.truncate(),
+                        size,
+                        *push * time.delta_seconds(),
+                        &wall_query,
+                    );
+                    player_transform.translation.x = target.x;
+                    player_transform.translation.y = target.y;
                 }
             }
         }
-        if !b_was_collision_up {
-            player.direction_block.up = false;
+        if !on_teleporter && teleported.is_some() {
+            commands.entity(entity).remove::<Teleported>();
         }
-        if !b_was_collision_down {
-            player.direction_block.down = false;
-        }
-        if !b_was_collision_right {
-            player.direction_block.right = false;
+    }
+    for (entity, mut bullet_transform, teleported) in &mut bullet_query {
+        let position = bullet_transform.translation.truncate();
+        let mut on_teleporter = false;
+        for (hazard_transform, hazard) in &hazard_query {
+            let Hazard::Teleporter { target } = hazard else {
+                continue;
+            };
+            if !Obb::from_transform(hazard_transform).contains(position) {
+                continue;
+            }
+            on_teleporter = true;
+            if teleported.is_none() {
+                bullet_transform.translation.x = target.x;
+                bullet_transform.translation.y = target.y;
+                commands.entity(entity).insert(Teleported);
+            }
         }
-        if !b_was_collision_left {
-            player.direction_block.left = false;
+        if !on_teleporter && teleported.is_some() {
+            commands.entity(entity).remove::<Teleported>();
         }
     }
 }
@@ -151,45 +445,69 @@ pub fn collision_player(
 #[allow(clippy::type_complexity)]
 pub fn collision_bullet(
     mut commands: Commands,
-    mut bullet_query: Query<(Entity, &Transform, &mut Bullet)>,
+    mut bullet_query: Query<(Entity, &mut Transform, &mut Bullet), Without<Collider>>,
     mut collider_query: Query<
-        (&Transform, &mut Handle<Image>, Option<&mut Player>),
+        (
+            Entity,
+            &Transform,
+            &mut Handle<Image>,
+            Option<&mut Player>,
+            Option<&Destructible>,
+            Option<&KinematicWall>,
+        ),
         With<Collider>,
     >,
     mut event_writer: EventWriter<UpdateUIEvent>,
     mut event_writer_player_hit: EventWriter<PlayerHitEvent>,
+    mut event_writer_damage: EventWriter<PlayerDamageEvent>,
+    mut event_writer_wall: EventWriter<WallDamageEvent>,
+    rules: Res<MatchRules>,
+    mut event_writer_sound: EventWriter<SoundEvent>,
     asset_server: ResMut<AssetServer>,
     mut texture_atlases: ResMut<Assets<TextureAtlas>>,
+    time: Res<Time>,
 ) {
-    for (bullet_entity, bullet_transform, mut bullet) in &mut bullet_query {
-        let bullet_size = bullet_transform.scale.truncate();
-        for (transform, mut player_sprite, mut maybe_player) in &mut collider_query {
-            if let Some(collision) = collide(
-                bullet_transform.translation,
-                bullet_size,
-                transform.translation,
-                transform.scale.truncate(),
-            ) {
+    for (bullet_entity, mut bullet_transform, mut bullet) in &mut bullet_query {
+        for (
+            collider_entity,
+            transform,
+            mut player_sprite,
+            mut maybe_player,
+            maybe_destructible,
+            maybe_kinematic,
+        ) in &mut collider_query
+        {
+            let bullet_body = Obb::from_transform(&bullet_transform);
+            if let Some(push) = Obb::from_transform(transform).penetration(&bullet_body) {
                 match bullet.bullet_type {
                     BulletType::NormalBullet => {
                         commands.entity(bullet_entity).despawn();
                         if maybe_player.is_none() {
-                            commands.spawn(AudioBundle {
-                                source: asset_server.load("/assets/sounds/hitwall.wav"),
-                                ..default()
-                            });
+                            if maybe_destructible.is_some() {
+                                event_writer_wall.send(WallDamageEvent {
+                                    wall: collider_entity,
+                                    damage: 1,
+                                });
+                            }
+                            event_writer_sound.send(SoundEvent::HitWall);
                             continue;
                         }
                         let player = &mut **maybe_player.as_mut().unwrap();
-                        if !player.invulnerable {
+                        if !player.is_invulnerable() {
                             player.decrement_life();
+                            player.last_hit_by = Some(bullet.owner);
+                            event_writer_damage.send(PlayerDamageEvent {
+                                victim: player.player_number,
+                                attacker: Some(bullet.owner),
+                                bullet_type: Some(BulletType::NormalBullet),
+                                damage: 1,
+                                stunned: false,
+                                lethal: player.lifes == 0,
+                            });
                             event_writer.send(UpdateUIEvent {
                                 player_number: player.player_number as usize,
                             });
-                            commands.spawn(AudioBundle {
-                                source: asset_server.load("/assets/sounds/hit.wav"),
-                                ..default()
-                            });
+                            event_writer_sound.send(SoundEvent::Hit);
                             if player.lifes > 0 {
                                 event_writer_player_hit.send_default();
                                 player.stunned = false;
@@ -199,8 +517,8 @@ pub fn collision_bullet(
                                 ));
                                 player.invulnerable = true;
                                 commands.spawn((HitCooldownTimer {
-                                    timer: Timer::new(Duration::from_secs(2), TimerMode::Once),
-                                    associated_player: player.name.clone(),
+                                    timer: Timer::new(rules.invulnerability(), TimerMode::Once),
+                                    associated_player: player.player_number,
                                     timer_type: TimerType::Invulnerable,
                                 },));
                             }
@@ -209,32 +527,39 @@ pub fn collision_bullet(
                     BulletType::IceBullet => {
                         commands.entity(bullet_entity).despawn();
                         if maybe_player.is_none() {
-                            commands.spawn(AudioBundle {
-                                source: asset_server.load("/assets/sounds/hitwall.wav"),
-                                ..default()
-                            });
+                            if maybe_destructible.is_some() {
+                                event_writer_wall.send(WallDamageEvent {
+                                    wall: collider_entity,
+                                    damage: 1,
+                                });
+                            }
+                            event_writer_sound.send(SoundEvent::HitWall);
                             continue;
                         }
                         let player = &mut **maybe_player.as_mut().unwrap();
-                        if !player.invulnerable && !player.stunned {
+                        if !player.is_invulnerable() && !player.stunned {
                             player.stunned = true;
+                            event_writer_damage.send(PlayerDamageEvent {
+                                victim: player.player_number,
+                                attacker: Some(bullet.owner),
+                                bullet_type: Some(BulletType::IceBullet),
+                                damage: 0,
+                                stunned: true,
+                                lethal: false,
+                            });
                             *player_sprite =
                                 asset_server.load("/assets/images/player/player_frozen.png");
-                            commands.spawn(AudioBundle {
-                                source: asset_server.load("/assets/sounds/frozen.wav"),
-                                ..default()
-                            });
+                            event_writer_sound.send(SoundEvent::Frozen);
                             commands.spawn((HitCooldownTimer {
-                                timer: Timer::new(Duration::from_secs(2), TimerMode::Once),
-                                associated_player: player.name.clone(),
+                                timer: Timer::new(rules.stun(), TimerMode::Once),
+                                associated_player: player.player_number,
                                 timer_type: TimerType::Stun,
                             },));
                         }
                     }
                     BulletType::ExplosiveBullet => {
                         commands.entity(bullet_entity).despawn();
-                        let texture_handle =
-                            asset_server.load("/assets/images/explosion_anim.png");
+                        let texture_handle = asset_server.load("/assets/images/explosion_anim.png");
                         let texture_atlas = TextureAtlas::from_grid(
                             texture_handle,
                             Vec2::new(32.0, 32.0),
@@ -244,10 +569,7 @@ pub fn collision_bullet(
                             None,
                         );
                         let texture_atlas_handle = texture_atlases.add(texture_atlas);
-                        commands.spawn(AudioBundle {
-                            source: asset_server.load("/assets/sounds/explosion.wav"),
-                            ..default()
-                        });
+                        event_writer_sound.send(SoundEvent::Explosion);
                         commands.spawn((
                             SpriteSheetBundle {
                                 sprite: TextureAtlasSprite {
@@ -270,35 +592,44 @@ pub fn collision_bullet(
                                 timer: Timer::from_seconds(0.05, TimerMode::Repeating),
                                 counter: 2,
                             },
-                            Explosion { radius: 50.0 },
+                            Explosion {
+                                owner: Some(bullet.owner),
+                                ..Explosion::grenade()
+                            },
                             Collider,
                         ));
                     }
                     BulletType::BouncyBullet => {
-                        bullet.direction = match collision {
-                            Collision::Left | Collision::Right => DirectionHelper {
-                                direction_x: bullet.direction.direction_x.opposite(),
-                                direction_y: bullet.direction.direction_y.clone(),
-                            },
-                            _ => DirectionHelper {
-                                direction_x: bullet.direction.direction_x.clone(),
-                                direction_y: bullet.direction.direction_y.opposite(),
-                            },
-                        };
-                        bullet.bounces_left -= 1;
+                        // reflect relative to the surface so moving walls bat the ball away,
+                        // the wall moves per second and the ball per tick
+                        let normal = push.normalize_or_zero();
+                        let surface_velocity = maybe_kinematic
+                            .map(|kinematic| kinematic.point_velocity(bullet_body.center))
+                            .unwrap_or(Vec2::ZERO)
+                            * time.delta_seconds();
+                        let relative = bullet.velocity - surface_velocity;
+                        // only a ball moving into the surface bounces and uses one up
+                        if relative.dot(normal) < 0.0 {
+                            let speed = bullet.velocity.length();
+                            bullet.velocity = (reflect(relative, normal) + surface_velocity)
+                                .normalize_or_zero()
+                                * speed;
+                            bullet.bounces_left -= 1;
+                        }
+                        bullet_transform.translation += push.extend(0.0);
                         if maybe_player.is_none() {
+                            if maybe_destructible.is_some() {
+                                event_writer_wall.send(WallDamageEvent {
+                                    wall: collider_entity,
+                                    damage: 1,
+                                });
+                            }
                             if bullet.bounces_left < 1 {
                                 commands.entity(bullet_entity).despawn();
-                                commands.spawn(AudioBundle {
-                                    source: asset_server.load("/assets/sounds/hitwall.wav"),
-                                    ..default()
-                                });
+                                event_writer_sound.send(SoundEvent::HitWall);
                                 continue;
                             }
-                            commands.spawn(AudioBundle {
-                                source: asset_server.load("/assets/sounds/bouncywall.wav"),
-                                ..default()
-                            });
+                            event_writer_sound.send(SoundEvent::BouncyWall);
                             continue;
                         }
                         let player = &mut **maybe_player.as_mut().unwrap();
@@ -306,25 +637,31 @@ pub fn collision_bullet(
                             &player.direction.direction_x,
                             &player.direction.direction_y,
                         ));
-                        if player.invulnerable {
+                        if player.is_invulnerable() {
                             continue;
                         }
                         player.decrement_life();
+                        player.last_hit_by = Some(bullet.owner);
+                        event_writer_damage.send(PlayerDamageEvent {
+                            victim: player.player_number,
+                            attacker: Some(bullet.owner),
+                            bullet_type: Some(BulletType::BouncyBullet),
+                            damage: 1,
+                            stunned: false,
+                            lethal: player.lifes == 0,
+                        });
                         event_writer.send(UpdateUIEvent {
                             player_number: player.player_number as usize,
                         });
-                        commands.spawn(AudioBundle {
-                            source: asset_server.load("/assets/sounds/hit.wav"),
-                            ..default()
-                        });
+                        event_writer_sound.send(SoundEvent::Hit);
                         if player.lifes > 0 {
                             event_writer_player_hit.send_default();
                             commands.entity(bullet_entity).despawn();
                             player.stunned = false;
                             player.invulnerable = true;
                             commands.spawn((HitCooldownTimer {
-                                timer: Timer::new(Duration::from_secs(2), TimerMode::Once),
-                                associated_player: player.name.clone(),
+                                timer: Timer::new(rules.invulnerability(), TimerMode::Once),
+                                associated_player: player.player_number,
                                 timer_type: TimerType::Invulnerable,
                             },));
                         }
@@ -0,0 +1,410 @@
+use bevy::prelude::*;
+use bevy::utils::Duration;
+
+use crate::arena_gen::FreeCells;
+use crate::audio::SoundEvent;
+use crate::collision::{
+    collision_bullet, collision_explosion, collision_hazards, collision_player,
+    collision_player_bodies, collision_powerup,
+};
+use crate::constants::PLAYER_SIZE;
+use crate::game_objects::{get_direction_sprite, Bullet, Player, PowerUp, Totem};
+use crate::game_utils::{
+    AnimationTimer, Bindings, BulletType, Collider, Direction, DirectionHelper, HitCooldownTimer,
+    InvulnerableBlinkTimer, Name, PlayerDamageEvent, PlayerDeadEvent, PlayerHitEvent,
+    PlayerPowerUpEvent, PlayerSlots, PowerUpPickedEvent, ResetGameEvent, TimerType, UpdateUIEvent,
+    WallDamageEvent,
+};
+use crate::geometry::Obb;
+use crate::modes::deathmatch_selected;
+use crate::profiles::{profile_player, Profiles};
+use crate::rng::GameRng;
+use crate::rules::MatchRules;
+use crate::StapidSet;
+
+// players, bullets, power-ups and everything that happens when they touch
+pub struct CombatPlugin;
+
+impl Plugin for CombatPlugin {
+    fn build(&self, app: &mut App) {
+        app
+            // debug functionality
+            // .register_inspectable::<Player>()
+            // .register_inspectable::<Bullet>()
+            // .register_inspectable::<Wall>()
+            .init_resource::<PlayerSlots>()
+            .add_event::<ResetGameEvent>()
+            .add_event::<UpdateUIEvent>()
+            .add_event::<PlayerDeadEvent>()
+            .add_event::<PlayerHitEvent>()
+            .add_event::<PlayerPowerUpEvent>()
+            .add_event::<PlayerDamageEvent>()
+            .add_event::<PowerUpPickedEvent>()
+            .add_event::<WallDamageEvent>()
+            .add_event::<SoundEvent>()
+            .add_systems(
+                FixedUpdate,
+                (
+                    collision_player,
+                    collision_player_bodies,
+                    collision_hazards,
+                    collision_bullet,
+                    collision_powerup,
+                    collision_explosion,
+                )
+                    .chain()
+                    .in_set(StapidSet::Collision),
+            )
+            .add_systems(FixedUpdate, move_all_bullets.in_set(StapidSet::MoveBullets))
+            .add_systems(
+                Update,
+                (clear_totems, spawn_player, reset_powerup)
+                    .chain()
+                    .in_set(StapidSet::Reset),
+            )
+            .add_systems(
+                Update,
+                (spawn_totem.run_if(not(deathmatch_selected)), spawn_powerup)
+                    .chain()
+                    .in_set(StapidSet::Spawn),
+            )
+            .add_systems(
+                Update,
+                (player_invulnerable_blink, tick_timer, animate_sprite)
+                    .chain()
+                    .in_set(StapidSet::Effects),
+            );
+    }
+}
+
+pub fn clear_totems(
+    mut commands: Commands,
+    totems: Query<Entity, With<Totem>>,
+    mut event_reader: EventReader<ResetGameEvent>,
+) {
+    for _ in event_reader.read() {
+        for entity in &totems {
+            commands.entity(entity).despawn();
+        }
+    }
+}
+
+pub fn reset_powerup(
+    mut commands: Commands,
+    powerups: Query<Entity, With<PowerUp>>,
+    mut event_reader: EventReader<ResetGameEvent>,
+    mut event_writer: EventWriter<PlayerPowerUpEvent>,
+) {
+    for _ in event_reader.read() {
+        for entity in &powerups {
+            commands.entity(entity).despawn();
+            event_writer.send_default();
+        }
+    }
+}
+
+// every taken slot gets its player, odd numbers start on the left facing right
+pub fn spawn_player(
+    mut commands: Commands,
+    existing_players: Query<Entity, With<Player>>,
+    asset_server: Res<AssetServer>,
+    rules: Res<MatchRules>,
+    slots: Res<PlayerSlots>,
+    profiles: Res<Profiles>,
+    mut event_reader: EventReader<ResetGameEvent>,
+) {
+    let profile = profile_player(&slots).zip(profiles.current());
+    for _ in event_reader.read() {
+        for entity in &existing_players {
+            commands.entity(entity).despawn();
+        }
+        for player_number in 1..5 {
+            if !slots.taken(player_number) {
+                continue;
+            }
+            let bindings = Bindings::for_slot(player_number);
+            let name = format!("player{}", player_number);
+            let (display_name, color) = match profile {
+                Some((number, profile)) if number == player_number => {
+                    (profile.name.clone(), profile.tint())
+                }
+                _ => (name.clone(), Color::WHITE),
+            };
+            let facing = match player_number % 2 {
+                1 => Direction::Right,
+                _ => Direction::Left,
+            };
+            commands.spawn((
+                Player::new(
+                    player_number,
+                    name.clone(),
+                    bindings.shoot,
+                    bindings.shoot_special,
+                    bindings.shove,
+                    bindings.dash,
+                    bindings.up,
+                    bindings.down,
+                    bindings.right,
+                    bindings.left,
+                    DirectionHelper {
+                        direction_x: facing.clone(),
+                        direction_y: Direction::None,
+                    },
+                )
+                .with_display_name(display_name)
+                .with_rules(&rules),
+                SpriteBundle {
+                    sprite: Sprite {
+                        color,
+                        custom_size: Option::Some(Vec2 { x: 1.0, y: 1.0 }),
+                        ..default()
+                    },
+                    texture: asset_server.load(get_direction_sprite(&facing, &Direction::None)),
+                    transform: Transform {
+                        translation: Player::spawn_point(player_number).extend(2.0),
+                        scale: Vec3 {
+                            x: PLAYER_SIZE,
+                            y: PLAYER_SIZE,
+                            z: 0.0,
+                        },
+                        ..default()
+                    },
+                    ..default()
+                },
+                Collider,
+                facing,
+                Name::new(name),
+            ));
+        }
+    }
+}
+
+pub fn spawn_totem(
+    mut commands: Commands,
+    players: Query<(Entity, &Transform, &Player)>,
+    asset_server: ResMut<AssetServer>,
+    mut event_reader: EventReader<PlayerDeadEvent>,
+) {
+    for _ in event_reader.read() {
+        for (entity, transform, player) in players.iter() {
+            if player.lifes > 0 {
+                continue;
+            }
+            commands.entity(entity).despawn();
+            commands.spawn((
+                Totem {},
+                SpriteBundle {
+                    sprite: Sprite {
+                        custom_size: Option::Some(Vec2 { x: 1.0, y: 1.0 }),
+                        ..default()
+                    },
+                    texture: asset_server.load("/assets/images/dead.png"),
+                    transform: Transform {
+                        translation: Vec3 {
+                            x: transform.translation.x,
+                            y: transform.translation.y,
+                            z: 1.0,
+                        },
+                        scale: Vec3::new(PLAYER_SIZE, PLAYER_SIZE, 0.0),
+                        ..default()
+                    },
+                    ..default()
+                },
+            ));
+        }
+    }
+}
+
+pub fn spawn_powerup(
+    mut commands: Commands,
+    asset_server: ResMut<AssetServer>,
+    collision_query: Query<&Transform, With<Collider>>,
+    mut game_rng: ResMut<GameRng>,
+    free_cells: Res<FreeCells>,
+    mut event_reader: EventReader<PlayerPowerUpEvent>,
+) {
+    for _ in event_reader.read() {
+        let mut powerup_transform = Transform { ..default() };
+        loop {
+            let mut collided = false;
+            powerup_transform.translation =
+                PowerUp::generate_random_position(&free_cells.cells, &mut *game_rng);
+            powerup_transform.scale = Vec3 {
+                x: 40.0,
+                y: 40.0,
+                z: 0.0,
+            };
+            let powerup_body = Obb::from_transform(&powerup_transform);
+            for transform in &collision_query {
+                let collision = Obb::from_transform(transform).penetration(&powerup_body);
+                if collision.is_some() {
+                    collided = true;
+                    break;
+                }
+            }
+            if !collided {
+                break;
+            }
+        }
+        commands.spawn((
+            PowerUp {
+                pickup_type: BulletType::IceBullet,
+            },
+            SpriteBundle {
+                sprite: Sprite {
+                    custom_size: Option::Some(Vec2 { x: 1.0, y: 1.0 }),
+                    ..default()
+                },
+                texture: asset_server.load("/assets/images/coin.png"),
+                transform: powerup_transform,
+                ..default()
+            },
+            Collider,
+        ));
+    }
+}
+
+pub fn move_all_bullets(mut bullets: Query<(&Bullet, &mut Transform)>) {
+    for (bullet, mut transform) in &mut bullets {
+        transform.translation += bullet.velocity.extend(0.0);
+    }
+}
+
+pub fn tick_timer(
+    mut commands: Commands,
+    mut player_query: Query<(&mut Player, &mut Handle<Image>)>,
+    mut timer_query: Query<(Entity, &mut HitCooldownTimer)>,
+    mut event_writer: EventWriter<UpdateUIEvent>,
+    asset_server: Res<AssetServer>,
+    time: Res<Time>,
+) {
+    for (entity, mut hit_timer) in &mut timer_query {
+        hit_timer.timer.tick(time.delta());
+        for (mut player, mut player_sprite) in &mut player_query {
+            // the hud counts the dash cooldown down in tenths of a second
+            if let TimerType::DashCooldown = hit_timer.timer_type {
+                let remaining = (hit_timer.timer.remaining_secs() * 10.0).ceil() / 10.0;
+                if hit_timer.associated_player == player.player_number
+                    && remaining != player.dash_cooldown
+                {
+                    player.dash_cooldown = remaining;
+                    event_writer.send(UpdateUIEvent {
+                        player_number: player.player_number as usize,
+                    });
+                }
+            }
+            if hit_timer.timer.finished() && hit_timer.associated_player == player.player_number {
+                match hit_timer.timer_type {
+                    TimerType::Stun => {
+                        player.stunned = false;
+                        *player_sprite = asset_server.load(get_direction_sprite(
+                            &player.direction.direction_x,
+                            &player.direction.direction_y,
+                        ));
+                        commands.entity(entity).despawn();
+                    }
+                    TimerType::Invulnerable => {
+                        player.invulnerable = false;
+                        commands.entity(entity).despawn();
+                    }
+                    TimerType::Shoot => {
+                        player.shoot = true;
+                        commands.entity(entity).despawn();
+                    }
+                    TimerType::Shove => {
+                        player.shoving = false;
+                        commands.entity(entity).despawn();
+                    }
+                    TimerType::ShoveCooldown => {
+                        player.shove = true;
+                        commands.entity(entity).despawn();
+                    }
+                    TimerType::Dash => {
+                        player.dashing = false;
+                        commands.entity(entity).despawn();
+                    }
+                    TimerType::DashCooldown => {
+                        player.dash = true;
+                        commands.entity(entity).despawn();
+                    }
+                }
+            }
+        }
+    }
+}
+
+pub fn animate_sprite(
+    mut commands: Commands,
+    time: Res<Time>,
+    texture_atlases: Res<Assets<TextureAtlas>>,
+    mut query: Query<(
+        Entity,
+        &mut AnimationTimer,
+        &mut TextureAtlasSprite,
+        &Handle<TextureAtlas>,
+    )>,
+) {
+    for (entity, mut timer, mut sprite, texture_atlas_handle) in &mut query {
+        timer.timer.tick(time.delta());
+        if timer.timer.just_finished() {
+            if timer.counter < 1 {
+                commands.entity(entity).despawn();
+                return;
+            }
+
+            let texture_atlas = texture_atlases.get(texture_atlas_handle).unwrap();
+            sprite.index = (sprite.index + 1) % texture_atlas.textures.len();
+            timer.counter -= 1;
+        }
+    }
+}
+
+pub fn player_invulnerable_blink(
+    mut commands: Commands,
+    mut players: Query<(&mut Sprite, &Player)>,
+    mut event_reader: EventReader<PlayerHitEvent>,
+    mut timer_query: Query<(Entity, &mut InvulnerableBlinkTimer)>,
+    time: Res<Time>,
+) {
+    for (entity, mut hit_timer) in &mut timer_query {
+        hit_timer.timer.tick(time.delta());
+        for (mut sprite, player) in &mut players {
+            if hit_timer.timer.finished() && hit_timer.associated_player == player.player_number {
+                if !player.invulnerable {
+                    sprite.color.set_a(1.0);
+                    commands.entity(entity).despawn();
+                    break;
+                }
+                if hit_timer.color {
+                    sprite.color.set_a(1.0);
+                    commands.spawn((InvulnerableBlinkTimer {
+                        timer: Timer::new(Duration::from_millis(200), TimerMode::Once),
+                        color: false,
+                        associated_player: player.player_number,
+                    },));
+                } else {
+                    sprite.color.set_a(0.5);
+                    commands.spawn((InvulnerableBlinkTimer {
+                        timer: Timer::new(Duration::from_millis(200), TimerMode::Once),
+                        color: true,
+                        associated_player: player.player_number,
+                    },));
+                }
+                commands.entity(entity).despawn();
+            }
+        }
+    }
+    for _ in event_reader.read() {
+        for (mut sprite, player) in &mut players {
+            if !player.invulnerable {
+                continue;
+            }
+            sprite.color.set_a(0.5);
+            commands.spawn((InvulnerableBlinkTimer {
+                timer: Timer::new(Duration::from_millis(200), TimerMode::Once),
+                color: true,
+                associated_player: player.player_number,
+            },));
+        }
+    }
+}
@@ -5,6 +5,13 @@ pub const WALL_LEFT: f32 = -800.0;
 pub const WALL_RIGHT: f32 = 800.0;
 pub const PLAYER_PADDING: f32 = 10.0;
 pub const PLAYER_SIZE: f32 = 50.0;
+pub const PLAYER_SPAWN_X: f32 = 700.0;
+pub const PLAYER_SPAWN_Y: f32 = 350.0;
+pub const ICE_GRIP: f32 = 1.5;
+pub const SHOVE_SPEED: f32 = 900.0;
+pub const SHOVE_KNOCKBACK: f32 = 150.0;
+pub const SHOVE_REACH: f32 = 4.0;
+pub const DASH_SPEED: f32 = 1000.0;
 pub const LEFT_BOUND: f32 =
     WALL_LEFT + 60.0 + WALL_THICKNESS / 2.0 - PLAYER_SIZE / 2.0 - PLAYER_PADDING;
 pub const RIGHT_BOUND: f32 = WALL_RIGHT + WALL_THICKNESS / 2.0 - PLAYER_SIZE / 2.0 - PLAYER_PADDING;
@@ -1,11 +1,14 @@
-use crate::Collider;
-use bevy::prelude::*;
+use crate::game_utils::Collider;
+use bevy::{prelude::*, utils::Duration};
 
-use crate::constants::{WALL_BOTTOM, WALL_LEFT, WALL_RIGHT, WALL_THICKNESS, WALL_TOP};
-use crate::game_utils::{Bindings, BulletType, Direction, DirectionBlock, DirectionHelper};
+use crate::constants::{
+    PLAYER_SPAWN_X, PLAYER_SPAWN_Y, WALL_BOTTOM, WALL_LEFT, WALL_RIGHT, WALL_THICKNESS, WALL_TOP,
+};
+use crate::game_utils::{Bindings, BulletType, Direction, DirectionHelper};
+use crate::rules::MatchRules;
 use rand::prelude::*;
 
-#[derive(Component)]
+#[derive(Component, Clone)]
 pub struct Player {
     pub player_number: i32,
     pub size: i32,
@@ -14,15 +17,25 @@ pub struct Player {
     pub stunned: bool,
     pub powerup: bool,
     pub shoot: bool,
+    pub shove: bool,
+    pub shoving: bool,
+    pub dash: bool,
+    pub dashing: bool,
+    pub dash_cooldown: f32,
     pub speed: f32,
+    pub momentum: Vec2,
+    pub on_ice: bool,
+    // player number of whoever damaged this player last, gets the kill in deathmatch
+    pub last_hit_by: Option<i32>,
     pub direction: DirectionHelper,
-    pub direction_block: DirectionBlock,
     pub name: String,
+    // the profile's name when one plays this player, the name otherwise
+    pub display_name: String,
     pub bindings: Bindings,
     pub power_up_type: Option<BulletType>,
 }
 
-#[derive(Component)]
+#[derive(Component, Clone)]
 pub struct Bullet {
     pub bullet_type: BulletType,
     pub speed: f32,
@@ -30,16 +43,21 @@ pub struct Bullet {
     pub stuns: bool,
     pub bounces: bool,
     pub bounces_left: i32,
-    pub direction: DirectionHelper,
+    pub velocity: Vec2,
     pub color: Color,
+    pub owner: i32,
 }
 
-#[derive(Component)]
+#[derive(Component, Clone)]
 pub struct Explosion {
     pub radius: f32,
+    pub damage: i32,
+    pub falloff: bool,
+    pub knockback: f32,
+    pub owner: Option<i32>,
 }
 
-#[derive(Component)]
+#[derive(Component, Clone)]
 pub struct PowerUp {
     pub pickup_type: BulletType,
 }
@@ -51,11 +69,62 @@ pub struct WallBundle {
     pub collider: Collider,
 }
 
+#[derive(Component, Clone)]
+pub struct Destructible {
+    pub hp: i32,
+    pub max_hp: i32,
+    pub rebuild_after: Option<Duration>,
+}
+
+#[derive(Component, Clone)]
+pub struct WallCracks {
+    pub wall: Entity,
+}
+
+#[derive(Component, Clone)]
+pub enum Hazard {
+    Ice,
+    Spikes,
+    Teleporter { target: Vec2 },
+    Conveyor { push: Vec2 },
+}
+
+#[derive(Bundle)]
+pub struct HazardBundle {
+    pub hazard: Hazard,
+    pub sprite_bundle: SpriteBundle,
+}
+
+#[derive(Component, Clone)]
+pub struct MovingWall {
+    pub waypoints: Vec<Vec2>,
+    pub speed: f32,
+    pub next: usize,
+}
+
+#[derive(Component, Clone)]
+pub struct RotatingWall {
+    pub pivot: Vec2,
+    pub angular_speed: f32,
+}
+
+// how a moving or rotating wall moved during the last tick
+#[derive(Component, Clone, Default)]
+pub struct KinematicWall {
+    pub velocity: Vec2,
+    pub angular_velocity: f32,
+    pub pivot: Vec2,
+}
+
+// set after a teleport so the player does not bounce straight back
+#[derive(Component, Clone)]
+pub struct Teleported;
+
 // create totem pls
-#[derive(Component)]
+#[derive(Component, Clone)]
 pub struct Totem {}
 
-#[derive(Component)]
+#[derive(Component, Clone)]
 pub struct Wall {}
 
 #[derive(Component)]
@@ -71,6 +140,8 @@ impl Player {
         entered_name: String,
         entered_shootbind: KeyCode,
         entered_shoot_specialbind: KeyCode,
+        entered_shovebind: KeyCode,
+        entered_dashbind: KeyCode,
         entered_upbind: KeyCode,
         entered_downbind: KeyCode,
         entered_rightbind: KeyCode,
@@ -85,18 +156,23 @@ impl Player {
             stunned: false,
             powerup: false,
             shoot: true,
+            shove: true,
+            shoving: false,
+            dash: true,
+            dashing: false,
+            dash_cooldown: 0.0,
             speed: 2.5,
+            momentum: Vec2::ZERO,
+            on_ice: false,
+            last_hit_by: None,
             direction: entered_direction,
-            direction_block: DirectionBlock {
-                up: false,
-                down: false,
-                right: false,
-                left: false,
-            },
+            display_name: entered_name.clone(),
             name: entered_name,
             bindings: Bindings {
                 shoot: entered_shootbind,
                 shoot_special: entered_shoot_specialbind,
+                shove: entered_shovebind,
+                dash: entered_dashbind,
                 up: entered_upbind,
                 down: entered_downbind,
                 right: entered_rightbind,
@@ -106,10 +182,60 @@ impl Player {
         }
     }
 
+    // players 1 and 2 start at the top, 3 and 4 at the bottom, odd numbers on the left
+    pub fn spawn_point(player_number: i32) -> Vec2 {
+        let x = if player_number % 2 == 1 {
+            -PLAYER_SPAWN_X
+        } else {
+            PLAYER_SPAWN_X
+        };
+        let y = if player_number <= 2 {
+            PLAYER_SPAWN_Y
+        } else {
+            -PLAYER_SPAWN_Y
+        };
+        Vec2::new(x, y)
+    }
+
+    pub fn with_display_name(mut self, display_name: String) -> Player {
+        self.display_name = display_name;
+        self
+    }
+
+    pub fn with_rules(mut self, rules: &MatchRules) -> Player {
+        self.lifes = rules.lifes;
+        self.speed = rules.player_speed;
+        self
+    }
+
+    // a fresh copy with the same bindings, used to bring a dead player back in deathmatch
+    pub fn respawned(&self, rules: &MatchRules) -> Player {
+        Player::new(
+            self.player_number,
+            self.name.clone(),
+            self.bindings.shoot,
+            self.bindings.shoot_special,
+            self.bindings.shove,
+            self.bindings.dash,
+            self.bindings.up,
+            self.bindings.down,
+            self.bindings.right,
+            self.bindings.left,
+            self.direction.clone(),
+        )
+        .with_display_name(self.display_name.clone())
+        .with_rules(rules)
+    }
+
     pub fn decrement_life(&mut self) {
         self.lifes -= 1;
     }
 
+    // dashing gives a few frames of invulnerability on top of the one after getting hit
+    pub fn is_invulnerable(&self) -> bool {
+        self.invulnerable || self.dashing
+    }
+
     pub fn get_bullet_spawn_position(&self) -> (f32, f32) {
         let bullet_x = match self.direction.direction_x {
             Direction::Right => 43.0,
@@ -158,6 +284,11 @@ impl Bullet {
         }
     }
 
+    pub fn with_owner(mut self, player_number: i32) -> Bullet {
+        self.owner = player_number;
+        self
+    }
+
     pub fn normal_bullet(direction_entered: DirectionHelper) -> Bullet {
         Bullet {
             bullet_type: BulletType::NormalBullet,
@@ -166,8 +297,9 @@ impl Bullet {
             stuns: false,
             bounces: false,
             bounces_left: 0,
-            direction: direction_entered,
+            velocity: direction_entered.to_vec2() * 20.0,
             color: Color::rgb(1.0, 0.0, 0.0),
+            owner: 0,
         }
     }
 
@@ -179,8 +311,9 @@ impl Bullet {
             stuns: true,
             bounces: false,
             bounces_left: 0,
-            direction: direction_entered,
+            velocity: direction_entered.to_vec2() * 40.0,
             color: Color::rgb(0.0, 0.0, 1.0),
+            owner: 0,
         }
     }
 
@@ -192,8 +325,9 @@ impl Bullet {
             stuns: false,
             bounces: false,
             bounces_left: 0,
-            direction: direction_entered,
+            velocity: direction_entered.to_vec2() * 10.0,
             color: Color::rgb(1.0, 1.0, 0.0),
+            owner: 0,
         }
     }
 
@@ -205,15 +339,99 @@ impl Bullet {
             stuns: false,
             bounces: true,
             bounces_left: 4,
-            direction: direction_entered,
+            velocity: direction_entered.to_vec2() * 20.0,
             color: Color::rgb(0.0, 1.0, 0.0),
+            owner: 0,
+        }
+    }
+}
+
+impl Explosion {
+    pub fn grenade() -> Explosion {
+        Explosion {
+            radius: 50.0,
+            damage: 2,
+            falloff: true,
+            knockback: 120.0,
+            owner: None,
+        }
+    }
+
+    pub fn damage_at(&self, distance: f32) -> i32 {
+        if !self.falloff {
+            return self.damage;
         }
+        let strength = 1.0 - distance / self.radius;
+        ((self.damage as f32 * strength).ceil() as i32).max(1)
+    }
+}
+
+impl Destructible {
+    pub fn new(hp: i32, rebuild_after: Option<Duration>) -> Destructible {
+        Destructible {
+            hp,
+            max_hp: hp,
+            rebuild_after,
+        }
+    }
+
+    pub fn crack_sprite(&self) -> Option<&'static str> {
+        if self.hp <= 0 || self.hp == self.max_hp {
+            None
+        } else if self.hp * 2 > self.max_hp {
+            Some("/assets/images/walls/cracks_1.png")
+        } else {
+            Some("/assets/images/walls/cracks_2.png")
+        }
+    }
+}
+
+impl KinematicWall {
+    pub fn point_velocity(&self, point: Vec2) -> Vec2 {
+        self.velocity + self.angular_velocity * (point - self.pivot).perp()
+    }
+}
+
+impl HazardBundle {
+    pub fn new(hazard: Hazard, position: Vec2, size: Vec2) -> HazardBundle {
+        HazardBundle {
+            sprite_bundle: SpriteBundle {
+                transform: Transform {
+                    translation: position.extend(0.5),
+                    scale: size.extend(0.0),
+                    ..default()
+                },
+                sprite: Sprite {
+                    custom_size: Option::Some(Vec2 { x: 1.0, y: 1.0 }),
+                    color: match hazard {
+                        Hazard::Ice => Color::rgba(0.7, 0.9, 1.0, 0.6),
+                        Hazard::Spikes => Color::rgba(0.6, 0.1, 0.1, 0.8),
+                        Hazard::Teleporter { .. } => Color::rgba(0.6, 0.2, 0.9, 0.7),
+                        Hazard::Conveyor { .. } => Color::rgba(0.8, 0.7, 0.2, 0.6),
+                    },
+                    ..default()
+                },
+                ..default()
+            },
+            hazard,
+        }
+    }
+
+    pub fn teleporter_pair(first: Vec2, second: Vec2, size: Vec2) -> [HazardBundle; 2] {
+        [
+            Self::new(Hazard::Teleporter { target: second }, first, size),
+            Self::new(Hazard::Teleporter { target: first }, second, size),
+        ]
     }
 }
 
 impl PowerUp {
-    pub fn generate_random_position() -> Vec3 {
-        let mut rng = rand::thread_rng();
+    // the middle of one of the free cells of a generated arena, anywhere inside the outer walls
+    // on the drawn levels
+    pub fn generate_random_position(free_cells: &[Vec2], rng: &mut impl Rng) -> Vec3 {
+        if !free_cells.is_empty() {
+            return free_cells[rng.gen_range(0..free_cells.len())].extend(2.0);
+        }
         Vec3 {
             x: rng.gen_range((WALL_LEFT + 15.0)..=(WALL_RIGHT - 15.0)),
             y: rng.gen_range((WALL_TOP + 15.0)..=(WALL_BOTTOM - 15.0)),
@@ -289,67 +507,4 @@ impl WallBundle {
             collider: Collider,
         }
     }
-
-    pub fn new_random_wall() -> WallBundle {
-        let mut rng = rand::thread_rng();
-        let direction = Self::convert_int(rng.gen_range(0..=1));
-        match direction.unwrap() {
-            Direction::Up => WallBundle {
-                direction: Direction::Up,
-                sprite_bundle: SpriteBundle {
-                    transform: Transform {
-                        translation: Vec3 {
-                            x: rng.gen_range(-500..=500) as f32,
-                            y: rng.gen_range(-400..=400) as f32,
-                            z: (2.0),
-                        },
-                        scale: Vec3 {
-                            x: 500.0,
-                            y: WALL_THICKNESS,
-                            z: (0.0),
-                        },
-                        ..default()
-                    },
-                    sprite: Sprite {
-                        color: Color::rgb(1.0, 0.0, 0.0),
-                        ..default()
-                    },
-                    ..default()
-                },
-                collider: Collider,
-            },
-            _ => WallBundle {
-                direction: Direction::Right,
-                sprite_bundle: SpriteBundle {
-                    transform: Transform {
-                        translation: Vec3 {
-                            x: rng.gen_range(-500..=500) as f32,
-                            y: rng.gen_range(-250..=250) as f32,
-                            z: (2.0),
-                        },
-                        scale: Vec3 {
-                            x: WALL_THICKNESS,
-                            y: 500.0,
-                            z: (0.0),
-                        },
-                        ..default()
-                    },
-                    sprite: Sprite {
-                        color: Color::rgb(1.0, 0.0, 0.0),
-                        ..default()
-                    },
-                    ..default()
-                },
-                collider: Collider,
-            },
-        }
-    }
-
-    pub fn convert_int(number: i32) -> Option<Direction> {
-        match number {
-            0 => Some(Direction::Up),
-            1 => Some(Direction::Right),
-            _ => None,
-        }
-    }
 }
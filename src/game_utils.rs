@@ -1,26 +1,70 @@
 use bevy::prelude::*;
 use std::fmt;
 
+#[derive(States, Default, Debug, Clone, PartialEq, Eq, Hash)]
+pub enum GameState {
+    #[default]
+    Settings,
+    // hosting or joining a lan game, the match starts once the host says so
+    Lobby,
+    Playing,
+    // lifetime stats of the saved profiles
+    Stats,
+}
+
 #[derive(Default, Event)]
 pub struct ResetGameEvent {}
 
-#[derive(Default, Event)]
+#[derive(Default, Clone, Event)]
 pub struct PlayerHitEvent {}
 
-#[derive(Default, Event)]
-pub struct PlayerShootEvent {}
+#[derive(Clone, Event)]
+pub struct PlayerShootEvent {
+    pub player_number: i32,
+    pub bullet_type: BulletType,
+}
+
+// lifes a player lost or a freeze, attacker and bullet are None when the arena did it
+#[derive(Clone, Event)]
+pub struct PlayerDamageEvent {
+    pub victim: i32,
+    pub attacker: Option<i32>,
+    pub bullet_type: Option<BulletType>,
+    pub damage: i32,
+    pub stunned: bool,
+    // the hit that took the last life
+    pub lethal: bool,
+}
 
 #[derive(Default, Event)]
 pub struct PlayerPowerUpEvent {}
 
-#[derive(Default, Event)]
+#[derive(Clone, Event)]
+pub struct PowerUpPickedEvent {
+    pub player_number: i32,
+    pub bullet_type: BulletType,
+}
+
+#[derive(Default, Clone, Event)]
 pub struct UpdateUIEvent {
     pub player_number: usize,
 }
 
-#[derive(Default, Event)]
+#[derive(Default, Clone, Event)]
 pub struct PlayerDeadEvent {}
 
+// winner is None when the last players went down together with the same lifes
+#[derive(Event)]
+pub struct RoundOverEvent {
+    pub winner: Option<i32>,
+}
+
+#[derive(Event)]
+pub struct WallDamageEvent {
+    pub wall: Entity,
+    pub damage: i32,
+}
+
 #[derive(Component, Clone)]
 pub enum Direction {
     Up,
@@ -39,6 +83,14 @@ impl Direction {
             Direction::None => Direction::None,
         }
     }
+
+    pub fn sign(&self) -> f32 {
+        match self {
+            Direction::Up | Direction::Right => 1.0,
+            Direction::Down | Direction::Left => -1.0,
+            Direction::None => 0.0,
+        }
+    }
 }
 
 #[derive(Component, Clone)]
@@ -47,12 +99,10 @@ pub struct DirectionHelper {
     pub direction_x: Direction,
 }
 
-#[derive(Component, Clone)]
-pub struct DirectionBlock {
-    pub up: bool,
-    pub down: bool,
-    pub right: bool,
-    pub left: bool,
+impl DirectionHelper {
+    pub fn to_vec2(&self) -> Vec2 {
+        Vec2::new(self.direction_x.sign(), self.direction_y.sign())
+    }
 }
 
 #[derive(Component, Clone)]
@@ -68,50 +118,198 @@ pub enum TimerType {
     Invulnerable,
     Stun,
     Shoot,
+    Shove,
+    ShoveCooldown,
+    Dash,
+    DashCooldown,
 }
 
-#[derive(Component)]
-pub struct Name(String);
+#[derive(Component, Clone)]
+pub struct Name(pub String);
 impl Name {
     pub fn new(name: String) -> Self {
         Self(name)
     }
 }
 
-#[derive(Component)]
+#[derive(Component, Clone)]
 pub struct Collider;
 
-#[derive(Component)]
+#[derive(Component, Clone)]
 pub struct HitCooldownTimer {
     pub timer: Timer,
-    pub associated_player: String,
+    // player number
+    pub associated_player: i32,
     pub timer_type: TimerType,
 }
 
-#[derive(Component)]
+#[derive(Component, Clone)]
 pub struct AnimationTimer {
     pub timer: Timer,
     pub counter: i32,
 }
 
-#[derive(Component)]
+#[derive(Component, Clone)]
+pub struct WallRebuildTimer {
+    pub timer: Timer,
+}
+
+#[derive(Component, Clone)]
 pub struct InvulnerableBlinkTimer {
     pub timer: Timer,
     pub color: bool,
-    pub associated_player: String,
+    pub associated_player: i32,
 }
 
-#[derive(Component)]
+#[derive(Component, Clone)]
 pub struct Bindings {
     pub shoot: KeyCode,
     pub shoot_special: KeyCode,
+    pub shove: KeyCode,
+    pub dash: KeyCode,
     pub up: KeyCode,
     pub down: KeyCode,
     pub right: KeyCode,
     pub left: KeyCode,
 }
 
+impl Bindings {
+    // the keys each of the four player slots plays with
+    pub fn for_slot(player_number: i32) -> Bindings {
+        let keys = match player_number {
+            1 => [
+                KeyCode::ControlLeft,
+                KeyCode::ShiftLeft,
+                KeyCode::Q,
+                KeyCode::E,
+                KeyCode::W,
+                KeyCode::S,
+                KeyCode::D,
+                KeyCode::A,
+            ],
+            2 => [
+                KeyCode::ControlRight,
+                KeyCode::ShiftRight,
+                KeyCode::AltRight,
+                KeyCode::Numpad0,
+                KeyCode::Up,
+                KeyCode::Down,
+                KeyCode::Right,
+                KeyCode::Left,
+            ],
+            3 => [
+                KeyCode::Y,
+                KeyCode::U,
+                KeyCode::R,
+                KeyCode::V,
+                KeyCode::T,
+                KeyCode::G,
+                KeyCode::H,
+                KeyCode::F,
+            ],
+            _ => [
+                KeyCode::O,
+                KeyCode::P,
+                KeyCode::Semicolon,
+                KeyCode::M,
+                KeyCode::I,
+                KeyCode::K,
+                KeyCode::L,
+                KeyCode::J,
+            ],
+        };
+        let [shoot, shoot_special, shove, dash, up, down, right, left] = keys;
+        Bindings {
+            shoot,
+            shoot_special,
+            shove,
+            dash,
+            up,
+            down,
+            right,
+            left,
+        }
+    }
+
+    pub fn keys(&self) -> [KeyCode; 8] {
+        [
+            self.shoot,
+            self.shoot_special,
+            self.shove,
+            self.dash,
+            self.up,
+            self.down,
+            self.right,
+            self.left,
+        ]
+    }
+}
+
+// who plays in each of the four player slots, an open slot gets no player at all
+#[derive(Clone, Copy, PartialEq, Debug)]
+pub enum PlayerSlot {
+    Local,
+    Remote,
+    Open,
+}
+
+#[derive(Resource, Clone, PartialEq, Debug)]
+pub struct PlayerSlots(pub [PlayerSlot; 4]);
+
+impl Default for PlayerSlots {
+    fn default() -> Self {
+        PlayerSlots([PlayerSlot::Local; 4])
+    }
+}
+
+impl PlayerSlots {
+    // the first `players` slots play over the network, `local` is the one on this machine
+    pub fn networked(players: usize, local: usize) -> PlayerSlots {
+        let mut slots = [PlayerSlot::Open; 4];
+        for (index, slot) in slots.iter_mut().enumerate().take(players) {
+            *slot = match index + 1 == local {
+                true => PlayerSlot::Local,
+                false => PlayerSlot::Remote,
+            };
+        }
+        PlayerSlots(slots)
+    }
+
+    pub fn get(&self, player_number: i32) -> PlayerSlot {
+        self.0
+            .get((player_number - 1) as usize)
+            .copied()
+            .unwrap_or(PlayerSlot::Open)
+    }
+
+    pub fn taken(&self, player_number: i32) -> bool {
+        self.get(player_number) != PlayerSlot::Open
+    }
+
+    // the lowest player number nobody plays yet
+    pub fn free(&self) -> Option<i32> {
+        (1..=4).find(|player_number| !self.taken(*player_number))
+    }
+
+    pub fn count(&self) -> usize {
+        self.0
+            .iter()
+            .filter(|slot| **slot != PlayerSlot::Open)
+            .count()
+    }
+}
+
 impl BulletType {
+    // the power-up slot shows "None" for the normal bullet, stats need the weapon's name
+    pub fn name(&self) -> &'static str {
+        match self {
+            BulletType::NormalBullet => "Normal",
+            BulletType::IceBullet => "Ice",
+            BulletType::ExplosiveBullet => "Grenade",
+            BulletType::BouncyBullet => "Bouncy",
+        }
+    }
+
     pub fn convert_int(number: i32) -> Option<BulletType> {
         match number {
             0 => Some(BulletType::IceBullet),
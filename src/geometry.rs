@@ -0,0 +1,294 @@
+use bevy::prelude::*;
+
+// oriented box built from a sprite transform, the scale is the full size
+#[derive(Clone, Copy)]
+pub struct Obb {
+    pub center: Vec2,
+    pub half_size: Vec2,
+    pub axes: [Vec2; 2],
+}
+
+impl Obb {
+    pub fn from_transform(transform: &Transform) -> Obb {
+        Obb {
+            center: transform.translation.truncate(),
+            half_size: transform.scale.truncate().abs() / 2.0,
+            axes: [
+                (transform.rotation * Vec3::X)
+                    .truncate()
+                    .normalize_or_zero(),
+                (transform.rotation * Vec3::Y)
+                    .truncate()
+                    .normalize_or_zero(),
+            ],
+        }
+    }
+
+    pub fn from_aabb(center: Vec2, size: Vec2) -> Obb {
+        Obb {
+            center,
+            half_size: size.abs() / 2.0,
+            axes: [Vec2::X, Vec2::Y],
+        }
+    }
+
+    pub fn to_local(&self, point: Vec2) -> Vec2 {
+        let offset = point - self.center;
+        Vec2::new(offset.dot(self.axes[0]), offset.dot(self.axes[1]))
+    }
+
+    pub fn contains(&self, point: Vec2) -> bool {
+        let local = self.to_local(point);
+        local.x.abs() <= self.half_size.x && local.y.abs() <= self.half_size.y
+    }
+
+    pub fn distance_to(&self, point: Vec2) -> f32 {
+        let local = self.to_local(point);
+        local.distance(local.clamp(-self.half_size, self.half_size))
+    }
+
+    // slab test in local space, only counts hits strictly between start and end
+    pub fn segment_hits(&self, start: Vec2, end: Vec2) -> bool {
+        let start = self.to_local(start);
+        let delta = self.to_local(end) - start;
+        let mut t_enter: f32 = 0.0;
+        let mut t_exit: f32 = 1.0;
+        for (origin, direction, half) in [
+            (start.x, delta.x, self.half_size.x),
+            (start.y, delta.y, self.half_size.y),
+        ] {
+            if direction.abs() < f32::EPSILON {
+                if origin.abs() > half {
+                    return false;
+                }
+                continue;
+            }
+            let t1 = (-half - origin) / direction;
+            let t2 = (half - origin) / direction;
+            t_enter = t_enter.max(t1.min(t2));
+            t_exit = t_exit.min(t1.max(t2));
+            if t_enter > t_exit {
+                return false;
+            }
+        }
+        true
+    }
+
+    // separating axis test, returns how far `other` has to move to stop overlapping
+    pub fn penetration(&self, other: &Obb) -> Option<Vec2> {
+        let mut smallest = f32::MAX;
+        let mut push = Vec2::ZERO;
+        for axis in self.axes.iter().chain(other.axes.iter()) {
+            if *axis == Vec2::ZERO {
+                continue;
+            }
+            let (min_a, max_a) = self.project(*axis);
+            let (min_b, max_b) = other.project(*axis);
+            let overlap = max_a.min(max_b) - min_a.max(min_b);
+            if overlap <= 0.0 {
+                return None;
+            }
+            if overlap < smallest {
+                smallest = overlap;
+                let direction = if (other.center - self.center).dot(*axis) < 0.0 {
+                    -*axis
+                } else {
+                    *axis
+                };
+                push = direction * overlap;
+            }
+        }
+        Some(push)
+    }
+
+    fn project(&self, axis: Vec2) -> (f32, f32) {
+        let center = self.center.dot(axis);
+        let radius = self.half_size.x * self.axes[0].dot(axis).abs()
+            + self.half_size.y * self.axes[1].dot(axis).abs();
+        (center - radius, center + radius)
+    }
+}
+
+pub fn reflect(velocity: Vec2, normal: Vec2) -> Vec2 {
+    velocity - 2.0 * velocity.dot(normal) * normal
+}
+
+// anything closer than this counts as touching, not overlapping, so sliding along a wall works
+const CONTACT_EPSILON: f32 = 0.001;
+
+fn overlaps(center: Vec2, size: Vec2, obstacle: &Rect) -> bool {
+    let body = Rect::from_center_size(center, size);
+    body.max.x - obstacle.min.x > CONTACT_EPSILON
+        && obstacle.max.x - body.min.x > CONTACT_EPSILON
+        && body.max.y - obstacle.min.y > CONTACT_EPSILON
+        && obstacle.max.y - body.min.y > CONTACT_EPSILON
+}
+
+// moves one axis at a time and pushes the body back out of whatever it ran into on that axis,
+// returns the new center and which axes got cut short
+pub fn slide(position: Vec2, size: Vec2, delta: Vec2, obstacles: &[Rect]) -> (Vec2, BVec2) {
+    let half = size / 2.0;
+    let mut current = position;
+    let mut blocked = BVec2::FALSE;
+
+    let start = current;
+    current.x += delta.x;
+    for obstacle in obstacles {
+        // only walls we ran into this step, the ones we already overlapped are handled below
+        if !overlaps(current, size, obstacle) || overlaps(start, size, obstacle) {
+            continue;
+        }
+        if delta.x > 0.0 {
+            current.x = current.x.min(obstacle.min.x - half.x);
+        } else if delta.x < 0.0 {
+            current.x = current.x.max(obstacle.max.x + half.x);
+        }
+        blocked.x = true;
+    }
+
+    let start = current;
+    current.y += delta.y;
+    for obstacle in obstacles {
+        if !overlaps(current, size, obstacle) || overlaps(start, size, obstacle) {
+            continue;
+        }
+        if delta.y > 0.0 {
+            current.y = current.y.min(obstacle.min.y - half.y);
+        } else if delta.y < 0.0 {
+            current.y = current.y.max(obstacle.max.y + half.y);
+        }
+        blocked.y = true;
+    }
+
+    // something was already inside us, leave it the shortest way
+    for obstacle in obstacles {
+        if !overlaps(current, size, obstacle) {
+            continue;
+        }
+        let wall = Obb::from_aabb(obstacle.center(), obstacle.size());
+        if let Some(push) = wall.penetration(&Obb::from_aabb(current, size)) {
+            current += push;
+            blocked.x |= push.x != 0.0;
+            blocked.y |= push.y != 0.0;
+        }
+    }
+    (current, blocked)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const CELL: f32 = 50.0;
+    const BODY: Vec2 = Vec2::splat(CELL);
+
+    // '#' is a wall cell and 'P' the start cell, the first row is the top so y grows upwards
+    fn grid(rows: &[&str]) -> (Vec<Rect>, Vec2) {
+        let mut walls = Vec::new();
+        let mut start = Vec2::ZERO;
+        for (row, line) in rows.iter().enumerate() {
+            for (column, cell) in line.chars().enumerate() {
+                let center = Vec2::new(
+                    column as f32 * CELL + CELL / 2.0,
+                    (rows.len() - 1 - row) as f32 * CELL + CELL / 2.0,
+                );
+                match cell {
+                    '#' => walls.push(Rect::from_center_size(center, Vec2::splat(CELL))),
+                    'P' => start = center,
+                    _ => (),
+                }
+            }
+        }
+        (walls, start)
+    }
+
+    fn walk(walls: &[Rect], start: Vec2, step: Vec2, steps: usize) -> Vec2 {
+        (0..steps).fold(start, |position, _| slide(position, BODY, step, walls).0)
+    }
+
+    fn inside_any(walls: &[Rect], position: Vec2) -> bool {
+        walls.iter().any(|wall| overlaps(position, BODY, wall))
+    }
+
+    #[test]
+    fn slides_along_wall_on_diagonal() {
+        let (walls, start) = grid(&[
+            ".....", //
+            "P....", //
+            "#####",
+        ]);
+        let end = walk(&walls, start, Vec2::new(5.0, -5.0), 20);
+        assert_eq!(end.y, start.y);
+        assert_eq!(end.x, start.x + 100.0);
+    }
+
+    #[test]
+    fn stops_flush_against_wall() {
+        let (walls, start) = grid(&["P..#"]);
+        let (end, blocked) = slide(start, BODY, Vec2::new(120.0, 0.0), &walls);
+        assert_eq!(end.x, start.x + 100.0);
+        assert!(blocked.x && !blocked.y);
+    }
+
+    #[test]
+    fn never_ends_up_inside_walls() {
+        let (walls, start) = grid(&[
+            "#####", //
+            "#...#", //
+            "#.P.#", //
+            "#...#", //
+            "#####",
+        ]);
+        let mut position = start;
+        for step in [
+            Vec2::new(7.0, 3.0),
+            Vec2::new(-9.0, 11.0),
+            Vec2::new(-13.0, -4.0),
+            Vec2::new(2.0, -17.0),
+        ] {
+            for _ in 0..30 {
+                position = slide(position, BODY, step, &walls).0;
+                assert!(!inside_any(&walls, position), "inside at {:?}", position);
+            }
+        }
+    }
+
+    #[test]
+    fn slides_past_seams_between_wall_cells() {
+        let (walls, start) = grid(&[
+            "P......", //
+            "#######",
+        ]);
+        let end = walk(&walls, start, Vec2::new(10.0, -10.0), 30);
+        assert_eq!(end, Vec2::new(start.x + 300.0, start.y));
+    }
+
+    #[test]
+    fn does_not_get_stuck_on_outer_corner() {
+        let (walls, start) = grid(&[
+            "P....", //
+            ".#...", //
+            ".....",
+        ]);
+        let end = walk(&walls, start, Vec2::new(5.0, -5.0), 40);
+        assert_eq!(end, Vec2::new(start.x + 200.0, start.y - 105.0));
+    }
+
+    #[test]
+    fn stops_in_inner_corner() {
+        let (walls, start) = grid(&[
+            "###", //
+            "P.#", //
+        ]);
+        let (end, blocked) = slide(start, BODY, Vec2::new(80.0, 80.0), &walls);
+        assert_eq!(end, Vec2::new(start.x + 50.0, start.y));
+        assert!(blocked.x && blocked.y);
+    }
+
+    #[test]
+    fn gets_pushed_out_when_starting_inside() {
+        let (walls, start) = grid(&["P#"]);
+        let (end, _) = slide(start + Vec2::new(30.0, 0.0), BODY, Vec2::ZERO, &walls);
+        assert_eq!(end, start);
+    }
+}
@@ -0,0 +1,225 @@
+use bevy::prelude::*;
+
+use crate::collision::WallFilter;
+use crate::constants::{BOTTOM_BOUND, LEFT_BOUND, PLAYER_SIZE, RIGHT_BOUND, TOP_BOUND};
+use crate::game_objects::{get_direction_sprite, Player, PowerUp};
+use crate::game_utils::{Bindings, Direction, PlayerDeadEvent, ResetGameEvent};
+use crate::geometry::slide;
+use crate::rules::MatchRules;
+
+const GHOST_ALPHA: f32 = 0.35;
+// how close a ghost has to float to a power-up or player to interact with it
+const GHOST_REACH: f32 = 120.0;
+const NUDGE_DISTANCE: f32 = 60.0;
+const INTERACT_COOLDOWN_SECS: f32 = 1.5;
+const MARK_SECS: f32 = 3.0;
+const MARK_SIZE: f32 = 14.0;
+
+// what is left of an eliminated player, floats through walls and cannot be hit
+#[derive(Component, Clone)]
+pub struct Ghost {
+    pub player_number: i32,
+    pub bindings: Bindings,
+    pub facing: Vec2,
+    pub speed: f32,
+    pub cooldown: Timer,
+}
+
+// a marker a ghost put above a living player
+#[derive(Component, Clone)]
+pub struct TargetMark {
+    pub target: Entity,
+    pub timer: Timer,
+}
+
+// runs before spawn_totem so the dead players are still around to take the bindings from
+pub fn spawn_ghosts(
+    mut commands: Commands,
+    players: Query<(&Transform, &Player)>,
+    rules: Res<MatchRules>,
+    asset_server: Res<AssetServer>,
+    mut event_reader: EventReader<PlayerDeadEvent>,
+) {
+    if event_reader.read().last().is_none() {
+        return;
+    }
+    for (transform, player) in &players {
+        if player.lifes > 0 {
+            continue;
+        }
+        let mut cooldown = Timer::from_seconds(INTERACT_COOLDOWN_SECS, TimerMode::Once);
+        cooldown.tick(cooldown.duration());
+        commands.spawn((
+            SpriteBundle {
+                sprite: Sprite {
+                    color: Color::rgba(1.0, 1.0, 1.0, GHOST_ALPHA),
+                    custom_size: Option::Some(Vec2 { x: 1.0, y: 1.0 }),
+                    ..default()
+                },
+                texture: asset_server.load(get_direction_sprite(
+                    &player.direction.direction_x,
+                    &player.direction.direction_y,
+                )),
+                transform: Transform {
+                    translation: transform.translation.truncate().extend(4.0),
+                    scale: Vec3::new(PLAYER_SIZE, PLAYER_SIZE, 0.0),
+                    ..default()
+                },
+                ..default()
+            },
+            Ghost {
+                player_number: player.player_number,
+                bindings: player.bindings.clone(),
+                facing: player.direction.to_vec2(),
+                speed: rules.player_speed * 80.0,
+                cooldown,
+            },
+        ));
+    }
+}
+
+pub fn move_ghosts(
+    mut ghosts: Query<(&mut Ghost, &mut Transform, &mut Handle<Image>)>,
+    asset_server: Res<AssetServer>,
+    keys: Res<Input<KeyCode>>,
+    time: Res<Time>,
+) {
+    for (mut ghost, mut transform, mut sprite) in &mut ghosts {
+        let mut direction_x = Direction::None;
+        let mut direction_y = Direction::None;
+        if keys.pressed(ghost.bindings.up) {
+            direction_y = Direction::Up;
+        } else if keys.pressed(ghost.bindings.down) {
+            direction_y = Direction::Down;
+        }
+        if keys.pressed(ghost.bindings.right) {
+            direction_x = Direction::Right;
+        } else if keys.pressed(ghost.bindings.left) {
+            direction_x = Direction::Left;
+        }
+        let input = Vec2::new(direction_x.sign(), direction_y.sign());
+        if input == Vec2::ZERO {
+            continue;
+        }
+        *sprite = asset_server.load(get_direction_sprite(&direction_x, &direction_y));
+        ghost.facing = input;
+        let step = input * ghost.speed * time.delta_seconds();
+        transform.translation.x = (transform.translation.x + step.x).clamp(LEFT_BOUND, RIGHT_BOUND);
+        transform.translation.y = (transform.translation.y + step.y).clamp(TOP_BOUND, BOTTOM_BOUND);
+    }
+}
+
+// shoot nudges the closest power-up away, the special key marks the closest living player,
+// both share one cooldown so ghosts cannot take over the round
+#[allow(clippy::too_many_arguments)]
+#[allow(clippy::type_complexity)]
+pub fn ghost_interactions(
+    mut commands: Commands,
+    mut ghosts: Query<(&mut Ghost, &Transform)>,
+    mut powerups: Query<&mut Transform, (With<PowerUp>, Without<Ghost>)>,
+    players: Query<(Entity, &Transform), (With<Player>, Without<Ghost>, Without<PowerUp>)>,
+    walls: Query<&Transform, (WallFilter, Without<Ghost>)>,
+    keys: Res<Input<KeyCode>>,
+    time: Res<Time>,
+) {
+    let wall_rects: Vec<Rect> = walls
+        .iter()
+        .map(|transform| {
+            Rect::from_center_size(
+                transform.translation.truncate(),
+                transform.scale.truncate().abs(),
+            )
+        })
+        .collect();
+    for (mut ghost, ghost_transform) in &mut ghosts {
+        ghost.cooldown.tick(time.delta());
+        if !ghost.cooldown.finished() {
+            continue;
+        }
+        let position = ghost_transform.translation.truncate();
+        let in_reach = |transform: &Transform| {
+            let distance = transform.translation.truncate().distance(position);
+            (distance <= GHOST_REACH).then_some(distance)
+        };
+        if keys.just_pressed(ghost.bindings.shoot) {
+            let closest = powerups
+                .iter_mut()
+                .filter_map(|transform| in_reach(&transform).map(|distance| (transform, distance)))
+                .min_by(|(_, a), (_, b)| a.total_cmp(b));
+            if let Some((mut transform, _)) = closest {
+                let (target, _) = slide(
+                    transform.translation.truncate(),
+                    transform.scale.truncate(),
+                    ghost.facing.normalize_or_zero() * NUDGE_DISTANCE,
+                    &wall_rects,
+                );
+                transform.translation.x = target.x.clamp(LEFT_BOUND, RIGHT_BOUND);
+                transform.translation.y = target.y.clamp(TOP_BOUND, BOTTOM_BOUND);
+                ghost.cooldown.reset();
+            }
+        } else if keys.just_pressed(ghost.bindings.shoot_special) {
+            let closest = players
+                .iter()
+                .filter_map(|(entity, transform)| {
+                    in_reach(transform).map(|distance| (entity, distance))
+                })
+                .min_by(|(_, a), (_, b)| a.total_cmp(b));
+            if let Some((target, _)) = closest {
+                commands.spawn((
+                    SpriteBundle {
+                        sprite: Sprite {
+                            color: Color::rgba(1.0, 0.2, 0.2, 0.8),
+                            custom_size: Option::Some(Vec2 { x: 1.0, y: 1.0 }),
+                            ..default()
+                        },
+                        transform: Transform {
+                            translation: position.extend(4.0),
+                            scale: Vec3::new(MARK_SIZE, MARK_SIZE, 0.0),
+                            ..default()
+                        },
+                        ..default()
+                    },
+                    TargetMark {
+                        target,
+                        timer: Timer::from_seconds(MARK_SECS, TimerMode::Once),
+                    },
+                ));
+                ghost.cooldown.reset();
+            }
+        }
+    }
+}
+
+pub fn follow_marks(
+    mut commands: Commands,
+    mut marks: Query<(Entity, &mut TargetMark, &mut Transform)>,
+    targets: Query<&Transform, (With<Player>, Without<TargetMark>)>,
+    time: Res<Time>,
+) {
+    for (entity, mut mark, mut transform) in &mut marks {
+        let Ok(target) = targets.get(mark.target) else {
+            commands.entity(entity).despawn();
+            continue;
+        };
+        if mark.timer.tick(time.delta()).finished() {
+            commands.entity(entity).despawn();
+            continue;
+        }
+        transform.translation.x = target.translation.x;
+        transform.translation.y = target.translation.y + (target.scale.y + MARK_SIZE) / 2.0;
+    }
+}
+
+#[allow(clippy::type_complexity)]
+pub fn clear_ghosts(
+    mut commands: Commands,
+    leftovers: Query<Entity, Or<(With<Ghost>, With<TargetMark>)>>,
+    mut event_reader: EventReader<ResetGameEvent>,
+) {
+    if event_reader.read().last().is_none() {
+        return;
+    }
+    for entity in &leftovers {
+        commands.entity(entity).despawn();
+    }
+}
@@ -0,0 +1,319 @@
+use bevy::{
+    asset::{
+        io::{AssetReader, AssetReaderError, AssetSource, AssetSourceId, PathStream, Reader},
+        AssetPlugin,
+    },
+    prelude::*,
+    time::TimeUpdateStrategy,
+    utils::{BoxedFuture, Duration, HashMap, HashSet, Instant},
+};
+use rand::{rngs::StdRng, Rng, SeedableRng};
+use std::path::Path;
+
+use crate::audio::AudioFxPlugin;
+use crate::game_objects::{Bullet, Player};
+use crate::game_utils::{GameState, ResetGameEvent};
+use crate::killcam::KillCamPlugin;
+use crate::profiles::Profiles;
+use crate::results::ResultsOutput;
+use crate::round::RoundState;
+use crate::StapidGamePlugin;
+
+// one simulated frame, the clock is stepped by hand so matches run as fast as the cpu allows
+const FRAME: Duration = Duration::from_micros(16_667);
+// a match nobody wins within five simulated minutes counts as a timeout
+const MAX_FRAMES: u32 = 5 * 60 * 60;
+// how far off an axis or diagonal a bot still takes the shot
+const AIM_TOLERANCE: f32 = 20.0;
+const SHOVE_RANGE: f32 = 70.0;
+const DASH_CHANCE: f64 = 0.005;
+const WANDER_CHANCE: f64 = 0.02;
+const WANDER_FRAMES: u32 = 30;
+
+const WANDER_DIRECTIONS: [Vec2; 8] = [
+    Vec2::new(1.0, 0.0),
+    Vec2::new(1.0, 1.0),
+    Vec2::new(0.0, 1.0),
+    Vec2::new(-1.0, 1.0),
+    Vec2::new(-1.0, 0.0),
+    Vec2::new(-1.0, -1.0),
+    Vec2::new(0.0, -1.0),
+    Vec2::new(1.0, -1.0),
+];
+
+// the sprites, fonts and sounds only matter to somebody looking, so every load fails right away
+// instead of reading the assets folder, the handles stay valid and nothing waits on them
+struct NoAssets;
+
+// bots steer through the keyboard resource, so every gameplay system runs unchanged
+#[derive(Resource)]
+struct BotBrain {
+    rng: StdRng,
+    bots: HashMap<i32, BotState>,
+}
+
+// a bot that stopped moving picks a random direction for a while to get away from the wall
+#[derive(Default)]
+struct BotState {
+    last_position: Vec2,
+    wander: Vec2,
+    wander_frames: u32,
+}
+
+// every (player number, weapon) that was fired during the current match
+#[derive(Resource, Default)]
+struct ShotLog {
+    fired: HashSet<(i32, &'static str)>,
+}
+
+#[derive(Default)]
+struct WinCount {
+    wins: u32,
+    played: u32,
+}
+
+#[derive(Default)]
+struct SimStats {
+    matches: u32,
+    draws: u32,
+    timeouts: u32,
+    slots: [WinCount; 4],
+    weapons: HashMap<&'static str, WinCount>,
+}
+
+// `--headless <matches>` runs bot matches without a window and prints the results
+pub fn matches_from_args() -> Option<u32> {
+    let args: Vec<String> = std::env::args().collect();
+    let index = args.iter().position(|arg| arg == "--headless")?;
+    Some(
+        args.get(index + 1)
+            .and_then(|matches| matches.parse().ok())
+            .unwrap_or(100),
+    )
+}
+
+// the game without a window, a sound device or anybody watching, stepped `frame` at a time
+pub fn headless_app(frame: Duration) -> App {
+    let mut app = App::new();
+    app.register_asset_source(
+        AssetSourceId::Default,
+        AssetSource::build().with_reader(|| Box::new(NoAssets)),
+    )
+    .add_plugins((MinimalPlugins, AssetPlugin::default()))
+    .init_asset::<Image>()
+    .init_asset::<TextureAtlas>()
+    .init_asset::<Font>()
+    .init_resource::<Input<KeyCode>>()
+    .insert_resource(TimeUpdateStrategy::ManualDuration(frame))
+    .add_plugins(
+        StapidGamePlugin
+            .build()
+            .disable::<AudioFxPlugin>()
+            .disable::<KillCamPlugin>(),
+    );
+    app
+}
+
+pub fn run(matches: u32) {
+    let mut app = headless_app(FRAME);
+    app.insert_resource(BotBrain {
+        rng: StdRng::seed_from_u64(0),
+        bots: HashMap::default(),
+    })
+    .init_resource::<ShotLog>()
+    // the bots play for nobody's profile and their matches are nobody's results
+    .insert_resource(Profiles::default())
+    .insert_resource(ResultsOutput::Off)
+    .add_systems(PreUpdate, bot_input)
+    .add_systems(Last, log_shots);
+    app.finish();
+    app.cleanup();
+    app.world
+        .resource_mut::<NextState<GameState>>()
+        .set(GameState::Playing);
+
+    let started = Instant::now();
+    let mut stats = SimStats::default();
+    for _ in 0..matches {
+        let mut frames = 0;
+        let winner = loop {
+            app.update();
+            frames += 1;
+            let round = app.world.resource::<RoundState>();
+            if round.over {
+                break round.winner;
+            }
+            if frames >= MAX_FRAMES {
+                stats.timeouts += 1;
+                break None;
+            }
+        };
+        stats.record(winner, &app.world.resource::<ShotLog>().fired);
+        app.world.resource_mut::<ShotLog>().fired.clear();
+        app.world.send_event(ResetGameEvent {});
+    }
+    stats.print(started.elapsed());
+}
+
+impl AssetReader for NoAssets {
+    fn read<'a>(
+        &'a self,
+        path: &'a Path,
+    ) -> BoxedFuture<'a, Result<Box<Reader<'a>>, AssetReaderError>> {
+        Box::pin(async move { Err(AssetReaderError::NotFound(path.to_path_buf())) })
+    }
+
+    fn read_meta<'a>(
+        &'a self,
+        path: &'a Path,
+    ) -> BoxedFuture<'a, Result<Box<Reader<'a>>, AssetReaderError>> {
+        Box::pin(async move { Err(AssetReaderError::NotFound(path.to_path_buf())) })
+    }
+
+    fn read_directory<'a>(
+        &'a self,
+        path: &'a Path,
+    ) -> BoxedFuture<'a, Result<Box<PathStream>, AssetReaderError>> {
+        Box::pin(async move { Err(AssetReaderError::NotFound(path.to_path_buf())) })
+    }
+
+    fn is_directory<'a>(
+        &'a self,
+        _path: &'a Path,
+    ) -> BoxedFuture<'a, Result<bool, AssetReaderError>> {
+        Box::pin(async move { Ok(false) })
+    }
+}
+
+impl SimStats {
+    fn record(&mut self, winner: Option<i32>, fired: &HashSet<(i32, &'static str)>) {
+        self.matches += 1;
+        if winner.is_none() {
+            self.draws += 1;
+        }
+        for (index, slot) in self.slots.iter_mut().enumerate() {
+            slot.played += 1;
+            if winner == Some(index as i32 + 1) {
+                slot.wins += 1;
+            }
+        }
+        for (player_number, weapon) in fired {
+            let count = self.weapons.entry(*weapon).or_default();
+            count.played += 1;
+            if winner == Some(*player_number) {
+                count.wins += 1;
+            }
+        }
+    }
+
+    fn print(&self, elapsed: Duration) {
+        println!(
+            "{} matches in {:.1}s, {} without a winner ({} timed out)",
+            self.matches,
+            elapsed.as_secs_f32(),
+            self.draws,
+            self.timeouts
+        );
+        println!("win rate per spawn slot:");
+        for (index, slot) in self.slots.iter().enumerate() {
+            println!("  player {}: {}", index + 1, slot.rate());
+        }
+        println!("win rate per weapon, out of the matches a player fired it in:");
+        let mut weapons: Vec<_> = self.weapons.iter().collect();
+        weapons.sort_by_key(|(weapon, _)| **weapon);
+        for (weapon, count) in weapons {
+            println!("  {}: {}", weapon, count.rate());
+        }
+    }
+}
+
+impl WinCount {
+    fn rate(&self) -> String {
+        let percent = match self.played {
+            0 => 0.0,
+            played => self.wins as f32 * 100.0 / played as f32,
+        };
+        format!("{}/{} ({:.1}%)", self.wins, self.played, percent)
+    }
+}
+
+// walk towards the closest enemy, shoot once lined up on an axis or diagonal
+fn bot_input(
+    mut keys: ResMut<Input<KeyCode>>,
+    mut brain: ResMut<BotBrain>,
+    players: Query<(&Transform, &Player)>,
+) {
+    keys.reset_all();
+    let alive: Vec<(Vec2, i32)> = players
+        .iter()
+        .filter(|(_, player)| player.lifes > 0)
+        .map(|(transform, player)| (transform.translation.truncate(), player.player_number))
+        .collect();
+    for (transform, player) in &players {
+        if player.lifes < 1 {
+            continue;
+        }
+        let position = transform.translation.truncate();
+        let Some(offset) = alive
+            .iter()
+            .filter(|(_, number)| *number != player.player_number)
+            .map(|(enemy, _)| *enemy - position)
+            .min_by(|a, b| a.length().total_cmp(&b.length()))
+        else {
+            continue;
+        };
+        let lined_up = offset.x.abs() < AIM_TOLERANCE
+            || offset.y.abs() < AIM_TOLERANCE
+            || (offset.x.abs() - offset.y.abs()).abs() < AIM_TOLERANCE;
+        let axis = |value: f32| match value.abs() < AIM_TOLERANCE {
+            true => 0.0,
+            false => value.signum(),
+        };
+        let towards = Vec2::new(axis(offset.x), axis(offset.y));
+        let BotBrain { rng, bots } = &mut *brain;
+        let bot = bots.entry(player.player_number).or_default();
+        let stuck = bot.last_position == position;
+        bot.last_position = position;
+        if bot.wander_frames == 0 && (stuck || rng.gen_bool(WANDER_CHANCE)) {
+            bot.wander = WANDER_DIRECTIONS[rng.gen_range(0..WANDER_DIRECTIONS.len())];
+            bot.wander_frames = WANDER_FRAMES;
+        }
+        let wandering = bot.wander_frames > 0;
+        let heading = match wandering {
+            true => {
+                bot.wander_frames -= 1;
+                bot.wander
+            }
+            false => towards,
+        };
+        let bindings = &player.bindings;
+        if heading.x > 0.0 {
+            keys.press(bindings.right);
+        } else if heading.x < 0.0 {
+            keys.press(bindings.left);
+        }
+        if heading.y > 0.0 {
+            keys.press(bindings.up);
+        } else if heading.y < 0.0 {
+            keys.press(bindings.down);
+        }
+        if lined_up && !wandering {
+            keys.press(bindings.shoot);
+            if player.powerup {
+                keys.press(bindings.shoot_special);
+            }
+        }
+        if offset.length() < SHOVE_RANGE {
+            keys.press(bindings.shove);
+        }
+        if rng.gen_bool(DASH_CHANCE) {
+            keys.press(bindings.dash);
+        }
+    }
+}
+
+fn log_shots(mut log: ResMut<ShotLog>, bullets: Query<&Bullet, Added<Bullet>>) {
+    for bullet in &bullets {
+        log.fired.insert((bullet.owner, bullet.bullet_type.name()));
+    }
+}
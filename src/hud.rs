@@ -0,0 +1,272 @@
+use bevy::prelude::*;
+
+use crate::arena::{ArenaConfig, ArenaKind};
+use crate::client::connected;
+use crate::game_objects::{Player, UINode, UIText};
+use crate::game_utils::{BulletType, PlayerDeadEvent, PlayerSlots, ResetGameEvent, UpdateUIEvent};
+use crate::killcam::kill_cam_running;
+use crate::modes::ModeScores;
+use crate::netcode::netplay;
+use crate::replay::replaying;
+use crate::round::{update_round_text, RoundText};
+use crate::rules::{GameMode, MatchRules};
+use crate::StapidSet;
+
+// camera, the player panels on the side and the reset button
+pub struct HudPlugin;
+
+impl Plugin for HudPlugin {
+    fn build(&self, app: &mut App) {
+        app.add_event::<ResetGameEvent>()
+            .add_event::<UpdateUIEvent>()
+            .add_event::<PlayerDeadEvent>()
+            .add_systems(Startup, spawn_camera)
+            .add_systems(Update, spawn_ui.in_set(StapidSet::Reset))
+            .add_systems(
+                Update,
+                (
+                    reset_clicked
+                        .run_if(not(replaying))
+                        .run_if(not(netplay))
+                        .run_if(not(connected)),
+                    update_ui,
+                    update_round_text.run_if(not(kill_cam_running)),
+                )
+                    .chain()
+                    .in_set(StapidSet::Hud),
+            );
+    }
+}
+
+pub fn spawn_ui(
+    mut commands: Commands,
+    asset_server: Res<AssetServer>,
+    arena: Res<ArenaConfig>,
+    rules: Res<MatchRules>,
+    slots: Res<PlayerSlots>,
+    mut event_reader: EventReader<ResetGameEvent>,
+    existing_node: Query<Entity, With<UINode>>,
+) {
+    for _ in event_reader.read() {
+        for entity in &existing_node {
+            commands.entity(entity).despawn_recursive();
+        }
+        commands
+            .spawn((
+                NodeBundle {
+                    style: Style {
+                        // size: Size::new(Val::Px(150.0), Val::Percent(100.0)),
+                        border: UiRect::all(Val::Px(2.0)),
+                        flex_direction: FlexDirection::Column,
+                        flex_wrap: FlexWrap::NoWrap,
+                        overflow: Overflow::DEFAULT,
+                        ..default()
+                    },
+                    background_color: Color::rgba(0.0, 0.0, 0.0, 0.0).into(),
+                    ..default()
+                },
+                UINode {},
+            ))
+            .with_children(|parent| {
+                for n in 1..5 {
+                    let panel = match slots.taken(n) {
+                        true => format!(
+                            "Player {}\nLifes: {}\nSpecial:\nNone\nDash: Ready\n{}\n",
+                            n,
+                            rules.lifes,
+                            rules
+                                .mode
+                                .hud_line(0.0)
+                                .map(|line| format!("{}\n", line))
+                                .unwrap_or_default()
+                        ),
+                        false => format!("Player {}\nOpen\n", n),
+                    };
+                    parent
+                        .spawn(ImageBundle {
+                            style: Style {
+                                // size: Size::new(Val::Px(144.0), Val::Px(108.0)),
+                                padding: UiRect {
+                                    left: Val::Px(20.0),
+                                    right: Val::Px(20.0),
+                                    top: Val::Px(10.0),
+                                    bottom: Val::Px(10.0),
+                                },
+                                ..default()
+                            },
+                            image: asset_server.load("/assets/images/hud_64_48.png").into(),
+                            ..default()
+                        })
+                        .with_children(|subparent| {
+                            subparent.spawn((
+                                TextBundle::from_section(
+                                    panel,
+                                    TextStyle {
+                                        font: asset_server.load("/assets/fonts/PixeloidSans.ttf"),
+                                        font_size: 20.0,
+                                        color: Color::WHITE,
+                                    },
+                                )
+                                .with_style(Style {
+                                    margin: UiRect::all(Val::Px(1.0)),
+                                    ..default()
+                                }),
+                                UIText {},
+                            ));
+                        });
+                }
+                if rules.mode != GameMode::Elimination {
+                    parent.spawn(
+                        TextBundle::from_section(
+                            format!("Mode:\n{}", rules.mode.name()),
+                            TextStyle {
+                                font: asset_server.load("/assets/fonts/PixeloidSans.ttf"),
+                                font_size: 20.0,
+                                color: Color::WHITE,
+                            },
+                        )
+                        .with_style(Style {
+                            margin: UiRect::all(Val::Px(8.0)),
+                            ..default()
+                        }),
+                    );
+                }
+                parent.spawn((
+                    TextBundle::from_section(
+                        "",
+                        TextStyle {
+                            font: asset_server.load("/assets/fonts/PixeloidSans.ttf"),
+                            font_size: 20.0,
+                            color: Color::WHITE,
+                        },
+                    )
+                    .with_style(Style {
+                        margin: UiRect::all(Val::Px(8.0)),
+                        ..default()
+                    }),
+                    RoundText,
+                ));
+                if arena.kind == ArenaKind::Generated {
+                    parent.spawn(
+                        TextBundle::from_section(
+                            format!("Seed:\n{}", arena.seed),
+                            TextStyle {
+                                font: asset_server.load("/assets/fonts/PixeloidSans.ttf"),
+                                font_size: 20.0,
+                                color: Color::WHITE,
+                            },
+                        )
+                        .with_style(Style {
+                            margin: UiRect::all(Val::Px(8.0)),
+                            ..default()
+                        }),
+                    );
+                }
+                parent
+                    .spawn(ButtonBundle {
+                        style: Style {
+                            // size: Size::new(Val::Px(128.0), Val::Px(64.0)),
+                            margin: UiRect::all(Val::Px(8.0)),
+                            padding: UiRect {
+                                left: Val::Px(17.0),
+                                right: Val::Px(17.0),
+                                top: Val::Px(15.0),
+                                bottom: Val::Px(15.0),
+                            },
+                            justify_content: JustifyContent::Center,
+                            ..default()
+                        },
+                        image: asset_server.load("/assets/images/hud_64_32.png").into(),
+                        ..default()
+                    })
+                    .with_children(|subparent| {
+                        subparent.spawn(TextBundle::from_section(
+                            "Reset",
+                            TextStyle {
+                                font: asset_server.load("/assets/fonts/PixeloidSans.ttf"),
+                                font_size: 30.0,
+                                color: Color::WHITE,
+                            },
+                        ));
+                    });
+            });
+    }
+}
+
+pub fn update_ui(
+    mut text_query: Query<&mut Text, With<UIText>>,
+    player_query: Query<&Player>,
+    asset_server: Res<AssetServer>,
+    rules: Res<MatchRules>,
+    scores: Res<ModeScores>,
+    mut event_reader_hit: EventReader<UpdateUIEvent>,
+    mut player_dead_event_writer: EventWriter<PlayerDeadEvent>,
+) {
+    for event in event_reader_hit.read() {
+        let mut text_nodes = text_query.iter_mut();
+        for player in &player_query {
+            if player.player_number as usize != event.player_number {
+                continue;
+            }
+            let mut maybe_node = text_nodes.nth(event.player_number - 1);
+            let text_node = &mut **maybe_node.as_mut().unwrap();
+            if player.lifes < 1 {
+                *text_node = Text::from_section(
+                    format!("Player {}\nSpectating\n", player.player_number),
+                    TextStyle {
+                        font: asset_server.load("/assets/fonts/PixeloidSans.ttf"),
+                        font_size: 20.0,
+                        color: Color::WHITE,
+                    },
+                );
+                player_dead_event_writer.send_default();
+                break;
+            }
+            let mut powerup = BulletType::NormalBullet;
+            if player.power_up_type.is_some() {
+                powerup = player.power_up_type.clone().unwrap();
+            }
+            let dash = if player.dash {
+                String::from("Ready")
+            } else {
+                format!("{:.1}s", player.dash_cooldown)
+            };
+            let mode_line = rules
+                .mode
+                .hud_line(scores.get(player.player_number))
+                .map(|line| format!("{}\n", line))
+                .unwrap_or_default();
+            *text_node = Text::from_section(
+                format!(
+                    "Player {}\nLifes: {}\nSpecial:\n{}\nDash: {}\n{}\n",
+                    player.player_number, player.lifes, powerup, dash, mode_line
+                ),
+                TextStyle {
+                    font: asset_server.load("/assets/fonts/PixeloidSans.ttf"),
+                    font_size: 20.0,
+                    color: Color::WHITE,
+                },
+            );
+            break;
+        }
+    }
+}
+
+pub fn reset_clicked(
+    interaction_query: Query<&Interaction, (Changed<Interaction>, With<Button>)>,
+    mut event_writer: EventWriter<ResetGameEvent>,
+) {
+    for interaction in &interaction_query {
+        #[allow(clippy::single_match)]
+        match *interaction {
+            Interaction::Pressed => {
+                event_writer.send_default();
+            }
+            _ => (),
+        }
+    }
+}
+
+pub fn spawn_camera(mut commands: Commands) {
+    commands.spawn(Camera2dBundle::default());
+}
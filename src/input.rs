@@ -0,0 +1,277 @@
+use bevy::prelude::*;
+use bevy::utils::Duration;
+
+use crate::audio::SoundEvent;
+use crate::collision::WallFilter;
+use crate::constants::{
+    BOTTOM_BOUND, DASH_SPEED, ICE_GRIP, LEFT_BOUND, RIGHT_BOUND, SHOVE_SPEED, TOP_BOUND,
+};
+use crate::game_objects::{get_direction_sprite, Bullet, KinematicWall, Player};
+use crate::game_utils::{
+    BulletType, Direction, HitCooldownTimer, PlayerShootEvent, TimerType, UpdateUIEvent,
+};
+use crate::geometry::slide;
+use crate::rules::MatchRules;
+use crate::StapidSet;
+
+// keyboard controls of the living players: moving, shooting, shoving and dashing
+pub struct InputPlugin;
+
+impl Plugin for InputPlugin {
+    fn build(&self, app: &mut App) {
+        app.add_event::<UpdateUIEvent>()
+            .add_event::<PlayerShootEvent>()
+            .add_event::<SoundEvent>()
+            .add_systems(
+                Update,
+                (move_all_players, player_shoot, player_shove, player_dash)
+                    .chain()
+                    .in_set(StapidSet::Input),
+            );
+    }
+}
+
+pub fn move_all_players(
+    mut players: Query<(Entity, &mut Player, &mut Transform, &mut Handle<Image>)>,
+    walls: Query<&Transform, (WallFilter, Without<KinematicWall>)>,
+    timer: Res<Time>,
+    keys: Res<Input<KeyCode>>,
+    asset_server: Res<AssetServer>,
+) {
+    let wall_rects: Vec<Rect> = walls
+        .iter()
+        .map(|transform| {
+            Rect::from_center_size(
+                transform.translation.truncate(),
+                transform.scale.truncate().abs(),
+            )
+        })
+        .collect();
+    let mut player_rects: Vec<(Entity, Rect)> = players
+        .iter()
+        .map(|(entity, _, transform, _)| {
+            (
+                entity,
+                Rect::from_center_size(
+                    transform.translation.truncate(),
+                    transform.scale.truncate(),
+                ),
+            )
+        })
+        .collect();
+    for (entity, mut player, mut transform, mut player_sprite) in &mut players {
+        let mut direction_x = Direction::None;
+        let mut direction_y = Direction::None;
+        if !player.stunned {
+            if keys.pressed(player.bindings.up) {
+                direction_y = Direction::Up;
+            } else if keys.pressed(player.bindings.down) {
+                direction_y = Direction::Down;
+            }
+            if keys.pressed(player.bindings.right) {
+                direction_x = Direction::Right;
+            } else if keys.pressed(player.bindings.left) {
+                direction_x = Direction::Left;
+            }
+        }
+        let input = Vec2::new(direction_x.sign(), direction_y.sign());
+        if input != Vec2::ZERO {
+            *player_sprite = asset_server.load(get_direction_sprite(&direction_x, &direction_y));
+            player.direction.direction_x = direction_x;
+            player.direction.direction_y = direction_y;
+        }
+        let target = input * 80. * player.speed;
+        // on ice the player only slowly gains or loses speed and keeps sliding
+        player.momentum = if player.shoving {
+            player.direction.to_vec2().normalize_or_zero() * SHOVE_SPEED
+        } else if player.dashing && !player.stunned {
+            player.direction.to_vec2().normalize_or_zero() * DASH_SPEED
+        } else if player.on_ice {
+            player
+                .momentum
+                .lerp(target, (ICE_GRIP * timer.delta_seconds()).min(1.0))
+        } else {
+            target
+        };
+        let obstacles: Vec<Rect> = player_rects
+            .iter()
+            .filter(|(other, _)| *other != entity)
+            .map(|(_, rect)| *rect)
+            .chain(wall_rects.iter().copied())
+            .collect();
+        let size = transform.scale.truncate();
+        let (new_position, blocked) = slide(
+            transform.translation.truncate(),
+            size,
+            player.momentum * timer.delta_seconds(),
+            &obstacles,
+        );
+        if blocked.x {
+            player.momentum.x = 0.0;
+        }
+        if blocked.y {
+            player.momentum.y = 0.0;
+        }
+        transform.translation.x = new_position.x.clamp(LEFT_BOUND, RIGHT_BOUND);
+        transform.translation.y = new_position.y.clamp(TOP_BOUND, BOTTOM_BOUND);
+        if let Some((_, rect)) = player_rects.iter_mut().find(|(other, _)| *other == entity) {
+            *rect = Rect::from_center_size(transform.translation.truncate(), size);
+        }
+    }
+}
+
+#[allow(clippy::too_many_arguments)]
+pub fn player_shoot(
+    mut commands: Commands,
+    mut players: Query<(&mut Player, &Transform)>,
+    mut event_writer: EventWriter<UpdateUIEvent>,
+    mut event_writer_shoot: EventWriter<PlayerShootEvent>,
+    mut event_writer_sound: EventWriter<SoundEvent>,
+    asset_server: ResMut<AssetServer>,
+    keys: Res<Input<KeyCode>>,
+    rules: Res<MatchRules>,
+) {
+    for (mut player, transform) in &mut players {
+        if keys.just_pressed(player.bindings.shoot) && !player.stunned && player.shoot {
+            player.shoot = false;
+            let (bullet_x, bullet_y) = player.get_bullet_spawn_position();
+            commands.spawn((
+                Bullet::normal_bullet(player.direction.clone()).with_owner(player.player_number),
+                SpriteBundle {
+                    sprite: Sprite {
+                        custom_size: Option::Some(Vec2 { x: 1.0, y: 1.0 }),
+                        ..default()
+                    },
+                    texture: asset_server.load("/assets/images/bullet.png"),
+                    transform: Transform {
+                        translation: transform.translation
+                            + Vec3 {
+                                x: bullet_x,
+                                y: bullet_y,
+                                z: 2.0,
+                            },
+                        scale: Vec3 {
+                            x: 30.0,
+                            y: 30.0,
+                            z: 0.0,
+                        },
+                        ..default()
+                    },
+                    ..default()
+                },
+            ));
+            commands.spawn((HitCooldownTimer {
+                timer: Timer::new(rules.shot_cooldown(), TimerMode::Once),
+                associated_player: player.player_number,
+                timer_type: TimerType::Shoot,
+            },));
+            event_writer_shoot.send(PlayerShootEvent {
+                player_number: player.player_number,
+                bullet_type: BulletType::NormalBullet,
+            });
+            event_writer_sound.send(SoundEvent::Shot);
+        }
+        if keys.just_pressed(player.bindings.shoot_special) && !player.stunned && player.powerup {
+            let (bullet_x, bullet_y) = player.get_bullet_spawn_position();
+            commands.spawn((
+                Bullet::bullet_from_enum(player.power_up_type.as_ref(), &player.direction)
+                    .with_owner(player.player_number),
+                SpriteBundle {
+                    sprite: Sprite {
+                        custom_size: Option::Some(Vec2 { x: 1.0, y: 1.0 }),
+                        ..default()
+                    },
+                    texture: match player.power_up_type.clone().unwrap() {
+                        BulletType::IceBullet => {
+                            asset_server.load("/assets/images/freezing_bullet.png")
+                        }
+                        BulletType::ExplosiveBullet => {
+                            asset_server.load("/assets/images/granate.png")
+                        }
+                        _ => asset_server.load("/assets/images/bouncy_ball.png"),
+                    },
+                    transform: Transform {
+                        translation: transform.translation
+                            + Vec3 {
+                                x: bullet_x,
+                                y: bullet_y,
+                                z: 2.0,
+                            },
+                        scale: Vec3 {
+                            x: 30.0,
+                            y: 30.0,
+                            z: 0.0,
+                        },
+                        ..default()
+                    },
+                    ..default()
+                },
+            ));
+            player.powerup = false;
+            if let Some(bullet_type) = player.power_up_type.take() {
+                event_writer_shoot.send(PlayerShootEvent {
+                    player_number: player.player_number,
+                    bullet_type,
+                });
+            }
+            event_writer.send(UpdateUIEvent {
+                player_number: player.player_number as usize,
+            });
+            event_writer_sound.send(SoundEvent::Shot);
+        }
+    }
+}
+
+pub fn player_shove(
+    mut commands: Commands,
+    mut players: Query<&mut Player>,
+    mut event_writer_sound: EventWriter<SoundEvent>,
+    keys: Res<Input<KeyCode>>,
+) {
+    for mut player in &mut players {
+        if !keys.just_pressed(player.bindings.shove) || player.stunned || !player.shove {
+            continue;
+        }
+        player.shove = false;
+        player.shoving = true;
+        commands.spawn((HitCooldownTimer {
+            timer: Timer::new(Duration::from_millis(150), TimerMode::Once),
+            associated_player: player.player_number,
+            timer_type: TimerType::Shove,
+        },));
+        commands.spawn((HitCooldownTimer {
+            timer: Timer::new(Duration::from_millis(1000), TimerMode::Once),
+            associated_player: player.player_number,
+            timer_type: TimerType::ShoveCooldown,
+        },));
+        event_writer_sound.send(SoundEvent::Shot);
+    }
+}
+
+pub fn player_dash(
+    mut commands: Commands,
+    mut players: Query<&mut Player>,
+    mut event_writer: EventWriter<UpdateUIEvent>,
+    keys: Res<Input<KeyCode>>,
+) {
+    for mut player in &mut players {
+        if !keys.just_pressed(player.bindings.dash) || player.stunned || !player.dash {
+            continue;
+        }
+        player.dash = false;
+        player.dashing = true;
+        commands.spawn((HitCooldownTimer {
+            timer: Timer::new(Duration::from_millis(180), TimerMode::Once),
+            associated_player: player.player_number,
+            timer_type: TimerType::Dash,
+        },));
+        commands.spawn((HitCooldownTimer {
+            timer: Timer::new(Duration::from_secs(2), TimerMode::Once),
+            associated_player: player.player_number,
+            timer_type: TimerType::DashCooldown,
+        },));
+        event_writer.send(UpdateUIEvent {
+            player_number: player.player_number as usize,
+        });
+    }
+}
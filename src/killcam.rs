@@ -0,0 +1,263 @@
+use bevy::{prelude::*, utils::HashMap};
+use std::collections::VecDeque;
+
+use crate::constants::{WALL_BOTTOM, WALL_LEFT, WALL_RIGHT, WALL_THICKNESS, WALL_TOP};
+use crate::game_objects::{Bullet, Explosion, Player};
+use crate::game_utils::{PlayerDeadEvent, ResetGameEvent, RoundOverEvent};
+use crate::StapidSet;
+
+// how much of the round is kept, and which part of it around the killing blow gets shown
+const BUFFER_SECS: f32 = 3.0;
+const BEFORE_KILL_SECS: f32 = 1.5;
+const AFTER_KILL_SECS: f32 = 0.5;
+const SLOW_MOTION: f32 = 0.25;
+// the kill-cam is drawn over the arena, the live entities stay where they are underneath
+const OVERLAY_Z: f32 = 10.0;
+
+// records the last seconds of every round and plays the final elimination back in slow
+// motion before the round-over text shows up
+pub struct KillCamPlugin;
+
+#[derive(Resource, Default)]
+pub struct KillCam {
+    frames: VecDeque<KillCamFrame>,
+    // when the round-ending death happened, stays set until the playback is done
+    kill_secs: Option<f32>,
+    playback: Option<KillCamPlayback>,
+}
+
+struct KillCamFrame {
+    secs: f32,
+    snapshots: Vec<Snapshot>,
+}
+
+struct Snapshot {
+    entity: Entity,
+    transform: Transform,
+    sprite: SnapshotSprite,
+    visible: bool,
+}
+
+enum SnapshotSprite {
+    Image(Sprite, Handle<Image>),
+    Atlas(TextureAtlasSprite, Handle<TextureAtlas>),
+}
+
+struct KillCamPlayback {
+    secs: f32,
+    end_secs: f32,
+    // recorded entity to the overlay sprite standing in for it
+    sprites: HashMap<Entity, Entity>,
+}
+
+#[derive(Component)]
+pub struct KillCamOverlay;
+
+impl Plugin for KillCamPlugin {
+    fn build(&self, app: &mut App) {
+        app.init_resource::<KillCam>()
+            .add_systems(Update, reset_kill_cam.in_set(StapidSet::Reset))
+            .add_systems(
+                Update,
+                (record_frame, watch_final_kill, play_kill_cam)
+                    .chain()
+                    .in_set(StapidSet::KillCam),
+            );
+    }
+}
+
+pub fn kill_cam_running(kill_cam: Option<Res<KillCam>>) -> bool {
+    kill_cam.is_some_and(|kill_cam| kill_cam.kill_secs.is_some())
+}
+
+pub fn reset_kill_cam(
+    mut commands: Commands,
+    mut kill_cam: ResMut<KillCam>,
+    overlay: Query<Entity, With<KillCamOverlay>>,
+    mut event_reader: EventReader<ResetGameEvent>,
+) {
+    if event_reader.read().last().is_none() {
+        return;
+    }
+    for entity in &overlay {
+        commands.entity(entity).despawn();
+    }
+    *kill_cam = KillCam::default();
+}
+
+#[allow(clippy::type_complexity)]
+pub fn record_frame(
+    mut kill_cam: ResMut<KillCam>,
+    query: Query<
+        (
+            Entity,
+            &Transform,
+            &Visibility,
+            Option<(&Sprite, &Handle<Image>)>,
+            Option<(&TextureAtlasSprite, &Handle<TextureAtlas>)>,
+        ),
+        Or<(With<Player>, With<Bullet>, With<Explosion>)>,
+    >,
+    time: Res<Time>,
+) {
+    if kill_cam.playback.is_some() {
+        return;
+    }
+    let secs = time.elapsed_seconds();
+    let snapshots = query
+        .iter()
+        .filter_map(|(entity, transform, visibility, image, atlas)| {
+            let sprite = match (image, atlas) {
+                (Some((sprite, texture)), _) => {
+                    SnapshotSprite::Image(sprite.clone(), texture.clone())
+                }
+                (_, Some((sprite, atlas))) => SnapshotSprite::Atlas(sprite.clone(), atlas.clone()),
+                _ => return None,
+            };
+            Some(Snapshot {
+                entity,
+                transform: *transform,
+                sprite,
+                visible: visibility != Visibility::Hidden,
+            })
+        })
+        .collect();
+    kill_cam.frames.push_back(KillCamFrame { secs, snapshots });
+    while kill_cam
+        .frames
+        .front()
+        .is_some_and(|frame| frame.secs < secs - BUFFER_SECS)
+    {
+        kill_cam.frames.pop_front();
+    }
+}
+
+// a round that ends on a death gets the kill-cam, one that runs out of time does not
+pub fn watch_final_kill(
+    mut kill_cam: ResMut<KillCam>,
+    mut event_reader_dead: EventReader<PlayerDeadEvent>,
+    mut event_reader_round: EventReader<RoundOverEvent>,
+    time: Res<Time>,
+) {
+    let died = event_reader_dead.read().last().is_some();
+    if event_reader_round.read().last().is_none() || !died || kill_cam.kill_secs.is_some() {
+        return;
+    }
+    kill_cam.kill_secs = Some(time.elapsed_seconds());
+}
+
+pub fn play_kill_cam(
+    mut commands: Commands,
+    mut kill_cam: ResMut<KillCam>,
+    overlay: Query<Entity, With<KillCamOverlay>>,
+    asset_server: Res<AssetServer>,
+    time: Res<Time>,
+) {
+    let Some(kill_secs) = kill_cam.kill_secs else {
+        return;
+    };
+    let kill_cam = &mut *kill_cam;
+    let Some(playback) = kill_cam.playback.as_mut() else {
+        if time.elapsed_seconds() >= kill_secs + AFTER_KILL_SECS {
+            spawn_backdrop(&mut commands, &asset_server);
+            let first_secs = kill_cam
+                .frames
+                .front()
+                .map_or(kill_secs, |frame| frame.secs);
+            kill_cam.playback = Some(KillCamPlayback {
+                secs: first_secs.max(kill_secs - BEFORE_KILL_SECS),
+                end_secs: time.elapsed_seconds(),
+                sprites: HashMap::new(),
+            });
+        }
+        return;
+    };
+
+    playback.secs += time.delta_seconds() * SLOW_MOTION;
+    if playback.secs >= playback.end_secs {
+        for entity in &overlay {
+            commands.entity(entity).despawn();
+        }
+        *kill_cam = KillCam::default();
+        return;
+    }
+    let index = kill_cam
+        .frames
+        .partition_point(|frame| frame.secs <= playback.secs)
+        .saturating_sub(1);
+    let Some(frame) = kill_cam.frames.get(index) else {
+        return;
+    };
+    for sprite in playback.sprites.values() {
+        commands.entity(*sprite).insert(Visibility::Hidden);
+    }
+    for snapshot in &frame.snapshots {
+        let sprite =
+            *playback
+                .sprites
+                .entry(snapshot.entity)
+                .or_insert_with(|| match snapshot.sprite {
+                    SnapshotSprite::Image(..) => commands
+                        .spawn((SpriteBundle::default(), KillCamOverlay))
+                        .id(),
+                    SnapshotSprite::Atlas(..) => commands
+                        .spawn((SpriteSheetBundle::default(), KillCamOverlay))
+                        .id(),
+                });
+        let mut transform = snapshot.transform;
+        transform.translation.z += OVERLAY_Z + 1.0;
+        let visibility = match snapshot.visible {
+            true => Visibility::Inherited,
+            false => Visibility::Hidden,
+        };
+        let mut entity = commands.entity(sprite);
+        entity.insert((transform, visibility));
+        match &snapshot.sprite {
+            SnapshotSprite::Image(image, texture) => {
+                entity.insert((image.clone(), texture.clone()));
+            }
+            SnapshotSprite::Atlas(atlas_sprite, atlas) => {
+                entity.insert((atlas_sprite.clone(), atlas.clone()));
+            }
+        }
+    }
+}
+
+// darkens the live arena and labels the playback
+fn spawn_backdrop(commands: &mut Commands, asset_server: &AssetServer) {
+    commands.spawn((
+        SpriteBundle {
+            sprite: Sprite {
+                color: Color::rgba(0.0, 0.0, 0.0, 0.85),
+                custom_size: Option::Some(Vec2 { x: 1.0, y: 1.0 }),
+                ..default()
+            },
+            transform: Transform {
+                translation: Vec3::new(0.0, 0.0, OVERLAY_Z),
+                scale: Vec3::new(
+                    WALL_RIGHT - WALL_LEFT + WALL_THICKNESS,
+                    WALL_BOTTOM - WALL_TOP + WALL_THICKNESS,
+                    0.0,
+                ),
+                ..default()
+            },
+            ..default()
+        },
+        KillCamOverlay,
+    ));
+    commands.spawn((
+        Text2dBundle {
+            text: Text::from_section(
+                "Kill cam",
+                TextStyle {
+                    font: asset_server.load("/assets/fonts/PixeloidSans.ttf"),
+                    font_size: 30.0,
+                    color: Color::rgb(0.9, 0.2, 0.2),
+                },
+            ),
+            transform: Transform::from_xyz(0.0, WALL_BOTTOM - 40.0, OVERLAY_Z + 5.0),
+            ..default()
+        },
+        KillCamOverlay,
+    ));
+}
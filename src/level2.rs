@@ -0,0 +1,22 @@
+use crate::arena::{ArenaKind, SpawnPoints};
+use crate::arena_layout::{ArenaFiles, ArenaLayout};
+use crate::level1::spawn_floor;
+use bevy::prelude::*;
+
+// the middle between the spinners and the two corners the sliders never reach
+const EXTRA_SPAWNS: [Vec2; 3] = [
+    Vec2::new(0.0, 0.0),
+    Vec2::new(300.0, 350.0),
+    Vec2::new(-300.0, -350.0),
+];
+
+// the spinners and sliders come from assets/arenas/level2.ron
+pub fn spawn_level_2(
+    mut commands: Commands,
+    asset_server: Res<AssetServer>,
+    files: Res<ArenaFiles>,
+) {
+    ArenaLayout::load(&files, ArenaKind::Level2).spawn_walls(&mut commands, &asset_server);
+    commands.insert_resource(SpawnPoints::with_extra(&EXTRA_SPAWNS));
+    spawn_floor(&mut commands, &asset_server);
+}
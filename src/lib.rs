@@ -0,0 +1,92 @@
+use bevy::{app::PluginGroupBuilder, prelude::*};
+
+pub mod arena;
+pub mod arena_gen;
+pub mod arena_layout;
+pub mod audio;
+pub mod client;
+pub mod collision;
+pub mod combat;
+pub mod constants;
+pub mod game_objects;
+pub mod game_utils;
+pub mod geometry;
+pub mod ghost;
+pub mod headless;
+pub mod hud;
+pub mod input;
+pub mod killcam;
+pub mod level1;
+pub mod level2;
+pub mod lobby;
+pub mod modes;
+pub mod netcode;
+pub mod profiles;
+pub mod replay;
+pub mod results;
+pub mod rng;
+pub mod rollback;
+pub mod round;
+pub mod round_stats;
+pub mod rules;
+pub mod server;
+pub mod settings;
+pub mod walls;
+
+use crate::arena::ArenaPlugin;
+use crate::audio::AudioFxPlugin;
+use crate::client::ClientPlugin;
+use crate::combat::CombatPlugin;
+use crate::hud::HudPlugin;
+use crate::input::InputPlugin;
+use crate::killcam::KillCamPlugin;
+use crate::lobby::LobbyPlugin;
+use crate::netcode::NetPlugin;
+use crate::profiles::ProfilePlugin;
+use crate::replay::ReplayPlugin;
+use crate::results::ResultsPlugin;
+use crate::round::RoundPlugin;
+use crate::round_stats::RoundStatsPlugin;
+
+// RoundPlugin chains these up, FixedUpdate runs the first four and Update the rest
+#[derive(SystemSet, Debug, Clone, PartialEq, Eq, Hash)]
+pub enum StapidSet {
+    MoveWalls,
+    Collision,
+    WallDamage,
+    MoveBullets,
+    // everything reacting to a ResetGameEvent
+    Reset,
+    Spawn,
+    Input,
+    // timers, blinking and animations
+    Effects,
+    Hud,
+    Round,
+    // slow motion playback of the final elimination
+    KillCam,
+}
+
+// all of the gameplay without window or rendering, headless runs disable AudioFxPlugin
+// and KillCamPlugin
+pub struct StapidGamePlugin;
+
+impl PluginGroup for StapidGamePlugin {
+    fn build(self) -> PluginGroupBuilder {
+        PluginGroupBuilder::start::<Self>()
+            .add(RoundPlugin)
+            .add(ArenaPlugin)
+            .add(CombatPlugin)
+            .add(InputPlugin)
+            .add(HudPlugin)
+            .add(ReplayPlugin)
+            .add(KillCamPlugin)
+            .add(RoundStatsPlugin)
+            .add(NetPlugin)
+            .add(LobbyPlugin)
+            .add(ClientPlugin)
+            .add(ProfilePlugin)
+            .add(ResultsPlugin)
+            .add(AudioFxPlugin)
+    }
+}
@@ -0,0 +1,819 @@
+use bevy::{
+    prelude::*,
+    utils::{Duration, Instant},
+    window::ReceivedCharacter,
+};
+use serde::{Deserialize, Serialize};
+use std::{
+    io::ErrorKind,
+    net::{Ipv4Addr, SocketAddr, UdpSocket},
+};
+
+use crate::arena::ArenaConfig;
+use crate::game_utils::{GameState, PlayerSlot, PlayerSlots};
+use crate::netcode::{start_session, Message, NetConfig};
+use crate::profiles::{Profiles, COLORS};
+use crate::settings::spawn_button;
+
+// everyone looking for a game listens for adverts here, a host tries its own port first and
+// takes any free one when another game on the same machine already has it
+pub const DISCOVERY_PORT: u16 = 7070;
+pub const HOST_PORT: u16 = 7000;
+const LOBBY: u8 = 2;
+const ADVERT_EVERY: Duration = Duration::from_secs(1);
+const JOIN_EVERY: Duration = Duration::from_secs(1);
+const START_EVERY: Duration = Duration::from_millis(250);
+// a game nobody advertised for this long is gone, so is a player who stopped asking
+const FORGET_AFTER: Duration = Duration::from_secs(3);
+const SILENT_AFTER: Duration = Duration::from_secs(5);
+
+// finds games on the lan or connects to one directly, the host hands out the free player
+// slots and starts the network session once everybody is there
+pub struct LobbyPlugin;
+
+#[derive(Serialize, Deserialize, Clone, PartialEq, Debug)]
+pub enum LobbyMessage {
+    // broadcast by the host every second while it waits for players
+    Advert {
+        arena: String,
+        players: usize,
+    },
+    // repeated while waiting, a host that stops hearing it frees the slot again
+    Join,
+    Welcome {
+        player: usize,
+    },
+    Full,
+    Leave,
+    // every player's address in player order, repeated until the player's session says hello
+    Start {
+        player: usize,
+        addresses: Vec<SocketAddr>,
+    },
+}
+
+#[derive(Event, Clone)]
+pub enum LobbyEvent {
+    Host(u16),
+    Join(SocketAddr),
+    Start,
+    Leave,
+}
+
+#[derive(Resource, Default)]
+pub struct Lobby {
+    // games heard of on the lan, in the order they showed up
+    pub games: Vec<FoundGame>,
+    // typed in for a direct connect
+    pub address: String,
+    pub status: String,
+    role: LobbyRole,
+    discovery: Option<UdpSocket>,
+    discovery_failed: bool,
+}
+
+#[derive(Clone)]
+pub struct FoundGame {
+    pub address: SocketAddr,
+    pub arena: String,
+    pub players: usize,
+    seen: Instant,
+}
+
+#[derive(Default)]
+enum LobbyRole {
+    #[default]
+    Browsing,
+    Hosting(Host),
+    Joining(Client),
+    // the socket went to the network session, which starts the match
+    Started,
+}
+
+struct Host {
+    socket: UdpSocket,
+    clients: Vec<LobbyClient>,
+    starting: bool,
+    last_advert: Option<Instant>,
+    last_start: Option<Instant>,
+}
+
+struct LobbyClient {
+    address: SocketAddr,
+    player: usize,
+    heard: Instant,
+    started: bool,
+}
+
+struct Client {
+    socket: UdpSocket,
+    host: SocketAddr,
+    player: Option<usize>,
+    last_join: Option<Instant>,
+}
+
+#[derive(Component)]
+pub struct LobbyScreen;
+
+#[derive(Component)]
+pub struct LobbyGameList;
+
+#[derive(Component)]
+pub enum LobbyText {
+    Status,
+    Address,
+    Slots,
+    Profile,
+}
+
+#[derive(Component)]
+pub struct LobbyButton {
+    pub action: LobbyAction,
+}
+
+#[derive(Clone, Copy)]
+pub enum LobbyAction {
+    Host,
+    Join(SocketAddr),
+    JoinTyped,
+    Start,
+    Back,
+    // cycle the profile this machine plays with, its color and its keys
+    Profile,
+    Color,
+    Keys,
+}
+
+impl Plugin for LobbyPlugin {
+    fn build(&self, app: &mut App) {
+        app.init_resource::<Lobby>()
+            .add_event::<LobbyEvent>()
+            .add_event::<ReceivedCharacter>()
+            .add_systems(OnEnter(GameState::Lobby), spawn_lobby_screen)
+            .add_systems(
+                Update,
+                (
+                    lobby_clicked,
+                    type_address,
+                    handle_lobby_events,
+                    run_lobby,
+                    update_lobby_screen,
+                )
+                    .chain()
+                    .run_if(in_state(GameState::Lobby)),
+            )
+            .add_systems(OnExit(GameState::Lobby), close_lobby);
+    }
+}
+
+impl LobbyMessage {
+    pub fn encode(&self) -> Vec<u8> {
+        let mut packet = vec![LOBBY];
+        packet.extend_from_slice(ron::to_string(self).unwrap_or_default().as_bytes());
+        packet
+    }
+
+    pub fn decode(packet: &[u8]) -> Option<LobbyMessage> {
+        let (tag, text) = packet.split_first()?;
+        if *tag != LOBBY {
+            return None;
+        }
+        ron::from_str(std::str::from_utf8(text).ok()?).ok()
+    }
+}
+
+impl FoundGame {
+    pub fn describe(&self) -> String {
+        format!(
+            "{}  {}/4 players  {}",
+            self.arena, self.players, self.address
+        )
+    }
+}
+
+impl Lobby {
+    // where this machine hosts, the port is the one to give to players joining directly
+    pub fn hosting_address(&self) -> Option<SocketAddr> {
+        match &self.role {
+            LobbyRole::Hosting(host) => host.socket.local_addr().ok(),
+            _ => None,
+        }
+    }
+
+    pub fn joined_as(&self) -> Option<usize> {
+        match &self.role {
+            LobbyRole::Joining(client) => client.player,
+            _ => None,
+        }
+    }
+
+    // tells the other side before going back to browsing, a started session is left alone
+    fn leave(&mut self, slots: &mut PlayerSlots) {
+        match std::mem::take(&mut self.role) {
+            LobbyRole::Hosting(host) => {
+                for client in &host.clients {
+                    send(&host.socket, client.address, &LobbyMessage::Leave);
+                }
+                *slots = PlayerSlots::default();
+            }
+            LobbyRole::Joining(client) => {
+                send(&client.socket, client.host, &LobbyMessage::Leave);
+            }
+            LobbyRole::Started => self.role = LobbyRole::Started,
+            LobbyRole::Browsing => (),
+        }
+    }
+}
+
+fn send(socket: &UdpSocket, address: SocketAddr, message: &LobbyMessage) {
+    // a lost packet gets sent again, one that can not go out at all is not worth a warning
+    let _ = socket.send_to(&message.encode(), address);
+}
+
+fn receive(socket: &UdpSocket) -> Vec<(SocketAddr, Vec<u8>)> {
+    let mut received = Vec::new();
+    let mut buffer = [0; 2048];
+    loop {
+        match socket.recv_from(&mut buffer) {
+            Ok((size, address)) => received.push((address, buffer[..size].to_vec())),
+            Err(error) if error.kind() == ErrorKind::WouldBlock => break,
+            Err(_) => continue,
+        }
+    }
+    received
+}
+
+fn bind(port: u16) -> std::io::Result<UdpSocket> {
+    let socket = UdpSocket::bind((Ipv4Addr::UNSPECIFIED, port))?;
+    socket.set_nonblocking(true)?;
+    Ok(socket)
+}
+
+pub fn spawn_lobby_screen(mut commands: Commands, asset_server: Res<AssetServer>) {
+    let font = asset_server.load("/assets/fonts/PixeloidSans.ttf");
+    let text_style = |font_size: f32| TextStyle {
+        font: font.clone(),
+        font_size,
+        color: Color::WHITE,
+    };
+    commands
+        .spawn((
+            NodeBundle {
+                style: Style {
+                    width: Val::Percent(100.0),
+                    height: Val::Percent(100.0),
+                    flex_direction: FlexDirection::Column,
+                    align_items: AlignItems::Center,
+                    justify_content: JustifyContent::Center,
+                    ..default()
+                },
+                background_color: Color::rgba(0.0, 0.0, 0.0, 0.85).into(),
+                z_index: ZIndex::Global(10),
+                ..default()
+            },
+            LobbyScreen,
+        ))
+        .with_children(|parent| {
+            parent.spawn(TextBundle::from_section("LAN game", text_style(40.0)));
+            parent.spawn((
+                TextBundle::from_section("", text_style(20.0)),
+                LobbyText::Status,
+            ));
+            parent.spawn((
+                NodeBundle {
+                    style: Style {
+                        flex_direction: FlexDirection::Column,
+                        align_items: AlignItems::Center,
+                        ..default()
+                    },
+                    ..default()
+                },
+                LobbyGameList,
+            ));
+            parent
+                .spawn(NodeBundle {
+                    style: Style {
+                        align_items: AlignItems::Center,
+                        ..default()
+                    },
+                    ..default()
+                })
+                .with_children(|row| {
+                    row.spawn((
+                        TextBundle::from_section("", text_style(20.0)).with_style(Style {
+                            width: Val::Px(400.0),
+                            ..default()
+                        }),
+                        LobbyText::Address,
+                    ));
+                    spawn_button(
+                        row,
+                        &asset_server,
+                        "Join",
+                        text_style(20.0),
+                        LobbyButton {
+                            action: LobbyAction::JoinTyped,
+                        },
+                    );
+                });
+            parent.spawn((
+                TextBundle::from_section("", text_style(20.0)),
+                LobbyText::Slots,
+            ));
+            parent
+                .spawn(NodeBundle {
+                    style: Style {
+                        align_items: AlignItems::Center,
+                        ..default()
+                    },
+                    ..default()
+                })
+                .with_children(|row| {
+                    row.spawn((
+                        TextBundle::from_section("", text_style(20.0)).with_style(Style {
+                            width: Val::Px(500.0),
+                            ..default()
+                        }),
+                        LobbyText::Profile,
+                    ));
+                    for (label, action) in [
+                        ("Profile", LobbyAction::Profile),
+                        ("Color", LobbyAction::Color),
+                        ("Keys", LobbyAction::Keys),
+                    ] {
+                        spawn_button(
+                            row,
+                            &asset_server,
+                            label,
+                            text_style(20.0),
+                            LobbyButton { action },
+                        );
+                    }
+                });
+            parent.spawn(NodeBundle::default()).with_children(|row| {
+                for (label, action) in [
+                    ("Host", LobbyAction::Host),
+                    ("Start", LobbyAction::Start),
+                    ("Back", LobbyAction::Back),
+                ] {
+                    spawn_button(
+                        row,
+                        &asset_server,
+                        label,
+                        text_style(30.0),
+                        LobbyButton { action },
+                    );
+                }
+            });
+        });
+}
+
+pub fn lobby_clicked(
+    interaction_query: Query<(&Interaction, &LobbyButton), Changed<Interaction>>,
+    mut lobby: ResMut<Lobby>,
+    mut profiles: ResMut<Profiles>,
+    mut event_writer: EventWriter<LobbyEvent>,
+    mut next_state: ResMut<NextState<GameState>>,
+) {
+    for (interaction, button) in &interaction_query {
+        if *interaction != Interaction::Pressed {
+            continue;
+        }
+        match button.action {
+            LobbyAction::Host => event_writer.send(LobbyEvent::Host(HOST_PORT)),
+            LobbyAction::Join(address) => event_writer.send(LobbyEvent::Join(address)),
+            LobbyAction::JoinTyped => join_typed(&mut lobby, &mut event_writer),
+            LobbyAction::Start => event_writer.send(LobbyEvent::Start),
+            // close_lobby says goodbye on the way out
+            LobbyAction::Back => next_state.set(GameState::Settings),
+            LobbyAction::Profile => {
+                profiles.select_next();
+                profiles.save();
+            }
+            LobbyAction::Color | LobbyAction::Keys => {
+                if let Some(profile) = profiles.current_mut() {
+                    match button.action {
+                        LobbyAction::Color => profile.color = (profile.color + 1) % COLORS.len(),
+                        _ => profile.bindings = profile.bindings % 4 + 1,
+                    }
+                    profiles.save();
+                }
+            }
+        }
+    }
+}
+
+fn join_typed(lobby: &mut Lobby, event_writer: &mut EventWriter<LobbyEvent>) {
+    match lobby.address.trim().parse() {
+        Ok(address) => event_writer.send(LobbyEvent::Join(address)),
+        Err(_) => lobby.status = String::from("Type the host as ip:port, 192.168.0.10:7000"),
+    }
+}
+
+pub fn type_address(
+    mut lobby: ResMut<Lobby>,
+    mut characters: EventReader<ReceivedCharacter>,
+    mut event_writer: EventWriter<LobbyEvent>,
+    keys: Res<Input<KeyCode>>,
+) {
+    for character in characters.read() {
+        if character.char.is_ascii_alphanumeric() || ".:[]".contains(character.char) {
+            lobby.address.push(character.char);
+        }
+    }
+    if keys.just_pressed(KeyCode::Back) {
+        lobby.address.pop();
+    }
+    if keys.just_pressed(KeyCode::Return) {
+        join_typed(&mut lobby, &mut event_writer);
+    }
+}
+
+pub fn handle_lobby_events(
+    mut lobby: ResMut<Lobby>,
+    mut slots: ResMut<PlayerSlots>,
+    mut event_reader: EventReader<LobbyEvent>,
+) {
+    for event in event_reader.read() {
+        match event {
+            LobbyEvent::Host(port) => {
+                lobby.leave(&mut slots);
+                let socket = match bind(*port).or_else(|_| bind(0)).and_then(|socket| {
+                    socket.set_broadcast(true)?;
+                    Ok(socket)
+                }) {
+                    Ok(socket) => socket,
+                    Err(error) => {
+                        lobby.status = format!("Could not host: {}", error);
+                        continue;
+                    }
+                };
+                let port = socket.local_addr().map_or(0, |address| address.port());
+                lobby.status = format!("Hosting on port {}, waiting for players", port);
+                lobby.role = LobbyRole::Hosting(Host {
+                    socket,
+                    clients: Vec::new(),
+                    starting: false,
+                    last_advert: None,
+                    last_start: None,
+                });
+                *slots = PlayerSlots::networked(1, 1);
+            }
+            LobbyEvent::Join(address) => {
+                lobby.leave(&mut slots);
+                let socket = match bind(0) {
+                    Ok(socket) => socket,
+                    Err(error) => {
+                        lobby.status = format!("Could not join: {}", error);
+                        continue;
+                    }
+                };
+                lobby.status = format!("Asking {} for a slot", address);
+                lobby.role = LobbyRole::Joining(Client {
+                    socket,
+                    host: *address,
+                    player: None,
+                    last_join: None,
+                });
+            }
+            LobbyEvent::Start => {
+                let LobbyRole::Hosting(host) = &mut lobby.role else {
+                    continue;
+                };
+                if host.clients.is_empty() {
+                    lobby.status = String::from("Nobody joined yet");
+                    continue;
+                }
+                // the session wants players one after the other, whoever left made a gap
+                host.clients.sort_by_key(|client| client.player);
+                for (index, client) in host.clients.iter_mut().enumerate() {
+                    client.player = index + 2;
+                }
+                host.starting = true;
+                lobby.status = String::from("Starting");
+            }
+            LobbyEvent::Leave => {
+                lobby.leave(&mut slots);
+                lobby.status = String::new();
+            }
+        }
+    }
+}
+
+pub fn run_lobby(
+    mut commands: Commands,
+    mut lobby: ResMut<Lobby>,
+    mut slots: ResMut<PlayerSlots>,
+    arena: Res<ArenaConfig>,
+) {
+    let lobby = &mut *lobby;
+    let now = Instant::now();
+    lobby.role = match std::mem::take(&mut lobby.role) {
+        LobbyRole::Browsing => {
+            discover_games(lobby, now);
+            LobbyRole::Browsing
+        }
+        LobbyRole::Hosting(host) => run_host(
+            host,
+            &mut commands,
+            lobby,
+            &mut slots,
+            arena.kind.name(),
+            now,
+        ),
+        LobbyRole::Joining(client) => run_client(client, &mut commands, lobby, now),
+        LobbyRole::Started => LobbyRole::Started,
+    };
+}
+
+fn discover_games(lobby: &mut Lobby, now: Instant) {
+    if lobby.discovery.is_none() && !lobby.discovery_failed {
+        match bind(DISCOVERY_PORT) {
+            Ok(socket) => lobby.discovery = Some(socket),
+            Err(error) => {
+                warn!("not looking for lan games: {}", error);
+                lobby.discovery_failed = true;
+                lobby.status = String::from("Can not look for games here, join one directly");
+            }
+        }
+    }
+    if let Some(discovery) = &lobby.discovery {
+        for (address, packet) in receive(discovery) {
+            let Some(LobbyMessage::Advert { arena, players }) = LobbyMessage::decode(&packet)
+            else {
+                continue;
+            };
+            let game = FoundGame {
+                address,
+                arena,
+                players,
+                seen: now,
+            };
+            match lobby.games.iter_mut().find(|game| game.address == address) {
+                Some(found) => *found = game,
+                None => lobby.games.push(game),
+            }
+        }
+    }
+    lobby
+        .games
+        .retain(|game| now.duration_since(game.seen) < FORGET_AFTER);
+}
+
+fn run_host(
+    mut host: Host,
+    commands: &mut Commands,
+    lobby: &mut Lobby,
+    slots: &mut PlayerSlots,
+    arena: &str,
+    now: Instant,
+) -> LobbyRole {
+    let due = |last: Option<Instant>, every: Duration| last.is_none_or(|last| now - last >= every);
+    if !host.starting && due(host.last_advert, ADVERT_EVERY) {
+        host.last_advert = Some(now);
+        let advert = LobbyMessage::Advert {
+            arena: arena.to_string(),
+            players: slots.count(),
+        };
+        send(
+            &host.socket,
+            SocketAddr::from((Ipv4Addr::BROADCAST, DISCOVERY_PORT)),
+            &advert,
+        );
+    }
+    for (address, packet) in receive(&host.socket) {
+        // a player whose session already says hello got the start
+        if let Some(Message::Hello { player, .. }) = Message::decode(&packet) {
+            if let Some(client) = host
+                .clients
+                .iter_mut()
+                .find(|client| host.starting && client.player == player as usize)
+            {
+                client.started = true;
+            }
+            continue;
+        }
+        let known = host
+            .clients
+            .iter()
+            .position(|client| client.address == address);
+        match (LobbyMessage::decode(&packet), known) {
+            (Some(LobbyMessage::Join), Some(index)) => {
+                let client = &mut host.clients[index];
+                client.heard = now;
+                if !host.starting {
+                    let welcome = LobbyMessage::Welcome {
+                        player: client.player,
+                    };
+                    send(&host.socket, address, &welcome);
+                }
+            }
+            (Some(LobbyMessage::Join), None) => match slots.free().filter(|_| !host.starting) {
+                Some(player) => {
+                    slots.0[player as usize - 1] = PlayerSlot::Remote;
+                    host.clients.push(LobbyClient {
+                        address,
+                        player: player as usize,
+                        heard: now,
+                        started: false,
+                    });
+                    info!("{} joined as player {}", address, player);
+                    let welcome = LobbyMessage::Welcome {
+                        player: player as usize,
+                    };
+                    send(&host.socket, address, &welcome);
+                }
+                None => send(&host.socket, address, &LobbyMessage::Full),
+            },
+            (Some(LobbyMessage::Leave), Some(index)) if !host.starting => {
+                let client = host.clients.remove(index);
+                slots.0[client.player - 1] = PlayerSlot::Open;
+                info!("player {} left", client.player);
+            }
+            _ => (),
+        }
+    }
+
+    if !host.starting {
+        host.clients.retain(|client| {
+            let silent = now - client.heard >= SILENT_AFTER;
+            if silent {
+                slots.0[client.player - 1] = PlayerSlot::Open;
+                info!(
+                    "player {} stopped answering and lost the slot",
+                    client.player
+                );
+            }
+            !silent
+        });
+        lobby.status = format!(
+            "Hosting on port {}, {} of 4 players",
+            host.socket.local_addr().map_or(0, |address| address.port()),
+            slots.count()
+        );
+        return LobbyRole::Hosting(host);
+    }
+
+    let mut addresses = vec![host.socket.local_addr().unwrap_or(host.clients[0].address)];
+    addresses.extend(host.clients.iter().map(|client| client.address));
+    if host.clients.iter().all(|client| client.started) {
+        let config = NetConfig {
+            player: 1,
+            addresses,
+            latency: Duration::ZERO,
+            loss: 0.0,
+        };
+        let socket = host.socket;
+        commands.add(move |world: &mut World| start_session(world, config, socket));
+        return LobbyRole::Started;
+    }
+    if due(host.last_start, START_EVERY) {
+        host.last_start = Some(now);
+        for client in host.clients.iter().filter(|client| !client.started) {
+            let start = LobbyMessage::Start {
+                player: client.player,
+                addresses: addresses.clone(),
+            };
+            send(&host.socket, client.address, &start);
+        }
+    }
+    LobbyRole::Hosting(host)
+}
+
+fn run_client(
+    mut client: Client,
+    commands: &mut Commands,
+    lobby: &mut Lobby,
+    now: Instant,
+) -> LobbyRole {
+    if client.last_join.is_none_or(|last| now - last >= JOIN_EVERY) {
+        client.last_join = Some(now);
+        send(&client.socket, client.host, &LobbyMessage::Join);
+    }
+    for (address, packet) in receive(&client.socket) {
+        if address != client.host {
+            continue;
+        }
+        match LobbyMessage::decode(&packet) {
+            Some(LobbyMessage::Welcome { player }) => {
+                if client.player.is_none() {
+                    lobby.status = format!(
+                        "Joined {} as player {}, waiting for the host to start",
+                        client.host, player
+                    );
+                }
+                client.player = Some(player);
+            }
+            Some(LobbyMessage::Full) => {
+                lobby.status = format!("The game at {} is full", client.host);
+                return LobbyRole::Browsing;
+            }
+            Some(LobbyMessage::Leave) => {
+                lobby.status = String::from("The host closed the game");
+                return LobbyRole::Browsing;
+            }
+            Some(LobbyMessage::Start {
+                player,
+                mut addresses,
+            }) => {
+                // the host only knows itself by the address it listens on
+                if let Some(host) = addresses.first_mut() {
+                    *host = client.host;
+                }
+                let config = NetConfig {
+                    player,
+                    addresses,
+                    latency: Duration::ZERO,
+                    loss: 0.0,
+                };
+                let Some(config) = config.validated() else {
+                    lobby.status = String::from("The host sent a broken start");
+                    return LobbyRole::Browsing;
+                };
+                lobby.status = String::from("Starting");
+                let socket = client.socket;
+                commands.add(move |world: &mut World| start_session(world, config, socket));
+                return LobbyRole::Started;
+            }
+            _ => (),
+        }
+    }
+    LobbyRole::Joining(client)
+}
+
+#[allow(clippy::too_many_arguments)]
+pub fn update_lobby_screen(
+    mut commands: Commands,
+    lobby: Res<Lobby>,
+    slots: Res<PlayerSlots>,
+    profiles: Res<Profiles>,
+    mut text_query: Query<(&mut Text, &LobbyText)>,
+    list: Query<Entity, With<LobbyGameList>>,
+    asset_server: Res<AssetServer>,
+    mut listed: Local<Vec<String>>,
+) {
+    for (mut text, lobby_text) in &mut text_query {
+        let value = match lobby_text {
+            LobbyText::Status => lobby.status.clone(),
+            LobbyText::Address => format!("Address: {}_", lobby.address),
+            LobbyText::Slots => match lobby.role {
+                LobbyRole::Hosting(_) => (1..5)
+                    .map(|player_number| match slots.get(player_number) {
+                        PlayerSlot::Local => format!("Player {}: you", player_number),
+                        PlayerSlot::Remote => format!("Player {}: joined", player_number),
+                        PlayerSlot::Open => format!("Player {}: open", player_number),
+                    })
+                    .collect::<Vec<String>>()
+                    .join("\n"),
+                _ => String::new(),
+            },
+            LobbyText::Profile => match profiles.current() {
+                Some(profile) => profile.describe(),
+                None => String::from("Profile: none, nothing is counted"),
+            },
+        };
+        if text.sections[0].value != value {
+            text.sections[0].value = value;
+        }
+    }
+
+    let games: Vec<String> = lobby.games.iter().map(FoundGame::describe).collect();
+    if *listed == games {
+        return;
+    }
+    let Ok(list) = list.get_single() else {
+        return;
+    };
+    commands.entity(list).despawn_descendants();
+    commands.entity(list).with_children(|parent| {
+        for game in &lobby.games {
+            spawn_button(
+                parent,
+                &asset_server,
+                &game.describe(),
+                TextStyle {
+                    font: asset_server.load("/assets/fonts/PixeloidSans.ttf"),
+                    font_size: 20.0,
+                    color: Color::WHITE,
+                },
+                LobbyButton {
+                    action: LobbyAction::Join(game.address),
+                },
+            );
+        }
+    });
+    *listed = games;
+}
+
+pub fn close_lobby(
+    mut commands: Commands,
+    mut lobby: ResMut<Lobby>,
+    mut slots: ResMut<PlayerSlots>,
+    screens: Query<Entity, With<LobbyScreen>>,
+) {
+    for entity in &screens {
+        commands.entity(entity).despawn_recursive();
+    }
+    lobby.leave(&mut slots);
+    *lobby = Lobby::default();
+}
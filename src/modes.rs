@@ -0,0 +1,343 @@
+use bevy::{prelude::*, utils::HashMap};
+
+use crate::arena::SpawnPoints;
+use crate::constants::PLAYER_SIZE;
+use crate::game_objects::{get_direction_sprite, Player, Totem};
+use crate::game_utils::{
+    Collider, HitCooldownTimer, Name, PlayerDeadEvent, PlayerHitEvent, ResetGameEvent,
+    RoundOverEvent, TimerType, UpdateUIEvent,
+};
+use crate::geometry::Obb;
+use crate::round::RoundState;
+use crate::rules::{GameMode, MatchRules};
+
+const HILL_SIZE: f32 = 160.0;
+const TOTEM_SIZE: f32 = 40.0;
+// how close to the own spawn point a carried totem counts as captured
+const CAPTURE_RADIUS: f32 = 80.0;
+const RESPAWN_SECS: f32 = 3.0;
+
+#[derive(Resource, Clone, Default)]
+pub struct ModeScores {
+    pub points: HashMap<i32, f32>,
+}
+
+impl ModeScores {
+    pub fn get(&self, player_number: i32) -> f32 {
+        self.points.get(&player_number).copied().unwrap_or(0.0)
+    }
+
+    // the single player with the highest score, None on a tie or when nobody scored
+    pub fn leader(&self) -> Option<i32> {
+        let best = self.points.values().copied().fold(0.0, f32::max);
+        let mut leaders = self
+            .points
+            .iter()
+            .filter(|(_, score)| **score == best && best > 0.0);
+        match (leaders.next(), leaders.next()) {
+            (Some((player_number, _)), None) => Some(*player_number),
+            _ => None,
+        }
+    }
+}
+
+#[derive(Component, Clone)]
+pub struct HillZone;
+
+// carrier_lifes remembers the lifes at pickup, losing one drops the totem
+#[derive(Component, Clone)]
+pub struct CaptureTotem {
+    pub carrier: Option<Entity>,
+    pub carrier_lifes: i32,
+}
+
+// a dead deathmatch player waiting to come back, the player entity itself is gone meanwhile
+#[derive(Component, Clone)]
+pub struct PendingRespawn {
+    pub player: Player,
+    pub timer: Timer,
+}
+
+pub fn king_of_the_hill_selected(rules: Res<MatchRules>) -> bool {
+    rules.mode == GameMode::KingOfTheHill
+}
+
+pub fn capture_the_totem_selected(rules: Res<MatchRules>) -> bool {
+    rules.mode == GameMode::CaptureTheTotem
+}
+
+pub fn deathmatch_selected(rules: Res<MatchRules>) -> bool {
+    rules.mode == GameMode::Deathmatch
+}
+
+// the capture totem is a Totem as well, so clear_totems already takes care of the old one
+#[allow(clippy::type_complexity)]
+pub fn reset_modes(
+    mut commands: Commands,
+    mut scores: ResMut<ModeScores>,
+    leftovers: Query<Entity, Or<(With<HillZone>, With<PendingRespawn>)>>,
+    rules: Res<MatchRules>,
+    asset_server: Res<AssetServer>,
+    mut event_reader: EventReader<ResetGameEvent>,
+) {
+    if event_reader.read().last().is_none() {
+        return;
+    }
+    scores.points.clear();
+    for entity in &leftovers {
+        commands.entity(entity).despawn();
+    }
+    match rules.mode {
+        GameMode::Elimination | GameMode::Deathmatch => (),
+        GameMode::KingOfTheHill => {
+            commands.spawn((
+                SpriteBundle {
+                    sprite: Sprite {
+                        color: Color::rgba(1.0, 0.84, 0.0, 0.35),
+                        custom_size: Option::Some(Vec2 { x: 1.0, y: 1.0 }),
+                        ..default()
+                    },
+                    transform: Transform {
+                        translation: Vec3::new(0.0, 0.0, 0.6),
+                        scale: Vec3::new(HILL_SIZE, HILL_SIZE, 0.0),
+                        ..default()
+                    },
+                    ..default()
+                },
+                HillZone,
+            ));
+        }
+        GameMode::CaptureTheTotem => {
+            commands.spawn((
+                SpriteBundle {
+                    sprite: Sprite {
+                        color: Color::GOLD,
+                        custom_size: Option::Some(Vec2 { x: 1.0, y: 1.0 }),
+                        ..default()
+                    },
+                    texture: asset_server.load("/assets/images/dead.png"),
+                    transform: Transform {
+                        translation: Vec3::new(0.0, 0.0, 3.0),
+                        scale: Vec3::new(TOTEM_SIZE, TOTEM_SIZE, 0.0),
+                        ..default()
+                    },
+                    ..default()
+                },
+                Totem {},
+                CaptureTotem {
+                    carrier: None,
+                    carrier_lifes: 0,
+                },
+            ));
+        }
+    }
+}
+
+// only a player standing in the zone alone scores, a contested hill counts for nobody
+#[allow(clippy::too_many_arguments)]
+pub fn king_of_the_hill(
+    mut scores: ResMut<ModeScores>,
+    mut round: ResMut<RoundState>,
+    players: Query<(&Transform, &Player)>,
+    hills: Query<&Transform, With<HillZone>>,
+    rules: Res<MatchRules>,
+    time: Res<Time>,
+    mut event_writer: EventWriter<UpdateUIEvent>,
+    mut event_writer_round: EventWriter<RoundOverEvent>,
+) {
+    if round.over {
+        return;
+    }
+    for hill_transform in &hills {
+        let hill = Obb::from_transform(hill_transform);
+        let mut kings = players.iter().filter(|(transform, player)| {
+            player.lifes > 0 && hill.contains(transform.translation.truncate())
+        });
+        let (Some((_, king)), None) = (kings.next(), kings.next()) else {
+            continue;
+        };
+        let score = scores.points.entry(king.player_number).or_insert(0.0);
+        let before = score.floor();
+        *score += time.delta_seconds();
+        if score.floor() != before {
+            event_writer.send(UpdateUIEvent {
+                player_number: king.player_number as usize,
+            });
+        }
+        if rules
+            .mode
+            .target_score()
+            .is_some_and(|target| *score >= target)
+        {
+            event_writer_round.send(round.finish(Some(king.player_number)));
+            return;
+        }
+    }
+}
+
+pub fn capture_the_totem(
+    mut scores: ResMut<ModeScores>,
+    mut round: ResMut<RoundState>,
+    players: Query<(Entity, &Transform, &Player), Without<CaptureTotem>>,
+    mut totems: Query<(&mut Transform, &mut CaptureTotem)>,
+    rules: Res<MatchRules>,
+    mut event_writer: EventWriter<UpdateUIEvent>,
+    mut event_writer_round: EventWriter<RoundOverEvent>,
+) {
+    if round.over {
+        return;
+    }
+    for (mut totem_transform, mut totem) in &mut totems {
+        let Some(carrier) = totem.carrier else {
+            let totem_body = Obb::from_transform(&totem_transform);
+            let picked_up = players.iter().find(|(_, transform, player)| {
+                player.lifes > 0
+                    && !player.stunned
+                    && Obb::from_transform(transform)
+                        .penetration(&totem_body)
+                        .is_some()
+            });
+            if let Some((entity, _, player)) = picked_up {
+                totem.carrier = Some(entity);
+                totem.carrier_lifes = player.lifes;
+            }
+            continue;
+        };
+        // a hit or a dead carrier leaves the totem where it was last carried
+        let Ok((_, transform, player)) = players.get(carrier) else {
+            totem.carrier = None;
+            continue;
+        };
+        if player.lifes < totem.carrier_lifes || player.lifes <= 0 {
+            totem.carrier = None;
+            continue;
+        }
+        let position = transform.translation.truncate();
+        totem_transform.translation.x = position.x;
+        totem_transform.translation.y = position.y + transform.scale.y / 2.0;
+        if position.distance(Player::spawn_point(player.player_number)) > CAPTURE_RADIUS {
+            continue;
+        }
+        let score = scores.points.entry(player.player_number).or_insert(0.0);
+        *score += 1.0;
+        event_writer.send(UpdateUIEvent {
+            player_number: player.player_number as usize,
+        });
+        if rules
+            .mode
+            .target_score()
+            .is_some_and(|target| *score >= target)
+        {
+            event_writer_round.send(round.finish(Some(player.player_number)));
+            return;
+        }
+        totem.carrier = None;
+        totem_transform.translation.x = 0.0;
+        totem_transform.translation.y = 0.0;
+    }
+}
+
+// takes the place of spawn_totem in deathmatch, whoever hit the dead player last gets the kill
+#[allow(clippy::too_many_arguments)]
+pub fn deathmatch_deaths(
+    mut commands: Commands,
+    mut scores: ResMut<ModeScores>,
+    mut round: ResMut<RoundState>,
+    players: Query<(Entity, &Player)>,
+    rules: Res<MatchRules>,
+    mut event_reader: EventReader<PlayerDeadEvent>,
+    mut event_writer: EventWriter<UpdateUIEvent>,
+    mut event_writer_round: EventWriter<RoundOverEvent>,
+) {
+    if event_reader.read().last().is_none() {
+        return;
+    }
+    for (entity, player) in &players {
+        if player.lifes > 0 {
+            continue;
+        }
+        commands.entity(entity).despawn();
+        commands.spawn(PendingRespawn {
+            player: player.respawned(&rules),
+            timer: Timer::from_seconds(RESPAWN_SECS, TimerMode::Once),
+        });
+        let Some(killer) = player.last_hit_by else {
+            continue;
+        };
+        if killer == player.player_number || round.over {
+            continue;
+        }
+        let kills = scores.points.entry(killer).or_insert(0.0);
+        *kills += 1.0;
+        event_writer.send(UpdateUIEvent {
+            player_number: killer as usize,
+        });
+        if rules
+            .mode
+            .target_score()
+            .is_some_and(|target| *kills >= target)
+        {
+            event_writer_round.send(round.finish(Some(killer)));
+        }
+    }
+}
+
+// respawned players come back blinking with the usual invulnerability as spawn protection
+#[allow(clippy::too_many_arguments)]
+pub fn respawn_players(
+    mut commands: Commands,
+    mut pending: Query<(Entity, &mut PendingRespawn)>,
+    players: Query<&Transform, With<Player>>,
+    spawn_points: Res<SpawnPoints>,
+    rules: Res<MatchRules>,
+    asset_server: Res<AssetServer>,
+    time: Res<Time>,
+    mut event_writer: EventWriter<UpdateUIEvent>,
+    mut event_writer_player_hit: EventWriter<PlayerHitEvent>,
+) {
+    let mut enemies: Vec<Vec2> = players
+        .iter()
+        .map(|transform| transform.translation.truncate())
+        .collect();
+    for (entity, mut respawn) in &mut pending {
+        if !respawn.timer.tick(time.delta()).finished() {
+            continue;
+        }
+        commands.entity(entity).despawn();
+        let spawn = spawn_points.furthest_from(&enemies);
+        enemies.push(spawn);
+        let mut player = respawn.player.respawned(&rules);
+        player.invulnerable = true;
+        commands.spawn((HitCooldownTimer {
+            timer: Timer::new(rules.invulnerability(), TimerMode::Once),
+            associated_player: player.player_number,
+            timer_type: TimerType::Invulnerable,
+        },));
+        event_writer.send(UpdateUIEvent {
+            player_number: player.player_number as usize,
+        });
+        event_writer_player_hit.send_default();
+        commands.spawn((
+            SpriteBundle {
+                sprite: Sprite {
+                    custom_size: Option::Some(Vec2 { x: 1.0, y: 1.0 }),
+                    ..default()
+                },
+                texture: asset_server.load(get_direction_sprite(
+                    &player.direction.direction_x,
+                    &player.direction.direction_y,
+                )),
+                transform: Transform {
+                    translation: spawn.extend(2.0),
+                    scale: Vec3::new(PLAYER_SIZE, PLAYER_SIZE, 0.0),
+                    ..default()
+                },
+                ..default()
+            },
+            Collider,
+            player.direction.direction_x.clone(),
+            Name::new(player.name.clone()),
+            player,
+        ));
+    }
+}
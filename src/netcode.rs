@@ -0,0 +1,909 @@
+use bevy::{
+    app::RunFixedUpdateLoop,
+    input::InputSystem,
+    prelude::*,
+    time::{TimeSystem, TimeUpdateStrategy},
+    utils::{Duration, Instant},
+};
+use rand::{rngs::StdRng, Rng, SeedableRng};
+use serde::{Deserialize, Serialize};
+use std::{
+    collections::{BTreeMap, VecDeque},
+    io::ErrorKind,
+    net::{SocketAddr, UdpSocket},
+};
+
+use crate::arena::{ArenaConfig, ArenaKind};
+use crate::game_objects::{Bullet, Player};
+use crate::game_utils::{Bindings, GameState, PlayerSlots, ResetGameEvent};
+use crate::profiles::Profiles;
+use crate::replay::{apply_input, read_input, TICK};
+use crate::rng::GameRng;
+use crate::rollback::{RollbackRegistry, WorldSnapshot};
+use crate::round::RoundState;
+use crate::rules::MatchRules;
+use crate::StapidSet;
+
+// how many ticks the simulation may run ahead of the slowest peer before it waits
+const MAX_PREDICTION: u32 = 8;
+// local input is scheduled this many ticks ahead, it hides most of the latency on a lan
+const INPUT_DELAY: u32 = 2;
+// the most inputs a single packet carries, enough to cover a few lost ones in a row
+const MAX_RESEND: u32 = 32;
+const MAX_BUDGET: Duration = Duration::from_micros(4 * 16_667);
+const RESTART_SECS: f32 = 3.0;
+const SILENT_SECS: u64 = 5;
+const CHECKSUMS_KEPT: usize = 600;
+const FNV_OFFSET: u64 = 0xcbf2_9ce4_8422_2325;
+const FNV_PRIME: u64 = 0x0100_0000_01b3;
+
+// lobby packets share the socket and start with a byte of their own, decode skips them
+const HELLO: u8 = 0;
+const INPUTS: u8 = 1;
+
+// `--net <player> <address,address,..>` plays over udp with one peer per player, every peer
+// simulates the whole match and rolls back whenever a remote input differs from its guess
+pub struct NetPlugin;
+
+#[derive(Resource, Clone)]
+pub struct NetConfig {
+    // one based, the player this peer controls
+    pub player: usize,
+    // every player's address in player order, this peer's own included
+    pub addresses: Vec<SocketAddr>,
+    // synthetic conditions for testing, applied to everything this peer sends
+    pub latency: Duration,
+    pub loss: f64,
+}
+
+// what player one picks and everyone else plays with
+#[derive(Serialize, Deserialize, Clone)]
+pub struct NetSetup {
+    pub seed: u64,
+    pub rules: MatchRules,
+    pub arena: String,
+    pub arena_seed: Option<u64>,
+}
+
+#[derive(PartialEq)]
+pub enum Message {
+    Hello {
+        player: u8,
+        ready: bool,
+        setup: Option<String>,
+    },
+    Inputs {
+        player: u8,
+        // how many of the receiver's inputs the sender has
+        ack: u32,
+        start: u32,
+        inputs: Vec<u16>,
+        // the sender's checksum of its latest tick that can no longer change
+        checksum: (u32, u64),
+    },
+}
+
+#[derive(PartialEq, Clone, Copy)]
+pub enum NetPhase {
+    Connecting,
+    // the frame the match starts on, it sets up the round without moving the clock
+    Starting,
+    Running,
+}
+
+struct NetLink {
+    socket: UdpSocket,
+    latency: Duration,
+    loss: f64,
+    rng: StdRng,
+    outgoing: VecDeque<(Instant, SocketAddr, Vec<u8>)>,
+}
+
+#[derive(Default)]
+struct Peer {
+    heard: bool,
+    ready: bool,
+    // how many of our inputs the peer confirmed
+    acked: u32,
+    last_packet: Option<Instant>,
+    silent: bool,
+}
+
+#[derive(Resource)]
+pub struct NetSession {
+    pub player: usize,
+    pub players: usize,
+    pub phase: NetPhase,
+    // the tick that gets simulated next
+    pub tick: u32,
+    // true on the frames that simulate a tick
+    pub advancing: bool,
+    // state at the start of each tick, the latest ones and only as far back as a rollback goes
+    pub checksums: BTreeMap<u32, u64>,
+    pub rollbacks: u32,
+    pub desyncs: u32,
+    addresses: Vec<SocketAddr>,
+    link: NetLink,
+    peers: Vec<Peer>,
+    setup: Option<NetSetup>,
+    // every input known so far per player, from tick zero on without gaps
+    inputs: Vec<Vec<u16>>,
+    // what each tick was simulated with, guesses included
+    used: Vec<[u16; 4]>,
+    rollback_to: Option<u32>,
+    snapshots: VecDeque<(u32, WorldSnapshot)>,
+    resimulating: bool,
+    budget: Duration,
+    last_frame: Option<Instant>,
+    // the keyboard as the player left it, the applied inputs are undone at the end of the frame
+    raw_keys: Option<Input<KeyCode>>,
+    // keys pressed on frames that simulate no tick, they go out with the next one
+    pressed: u16,
+}
+
+// counts down from the end of a round to the next one, rolled back with the rest
+#[derive(Resource, Clone, Default)]
+pub struct NetRestart {
+    timer: Option<Timer>,
+}
+
+impl Plugin for NetPlugin {
+    fn build(&self, app: &mut App) {
+        if let Some(config) = NetConfig::from_args() {
+            app.insert_resource(config);
+        }
+        app.add_systems(
+            PreStartup,
+            setup_session.run_if(resource_exists::<NetConfig>()),
+        )
+        .add_systems(
+            First,
+            pace_session
+                .before(TimeSystem)
+                .run_if(resource_exists::<NetSession>()),
+        )
+        .add_systems(
+            PreUpdate,
+            run_session
+                .after(InputSystem)
+                .run_if(resource_exists::<NetSession>()),
+        )
+        .add_systems(
+            Last,
+            end_session_tick.run_if(resource_exists::<NetSession>()),
+        )
+        .configure_sets(
+            Update,
+            (
+                StapidSet::Reset,
+                StapidSet::Spawn,
+                StapidSet::Input,
+                StapidSet::Effects,
+                StapidSet::Hud,
+                StapidSet::Round,
+                StapidSet::KillCam,
+            )
+                .run_if(net_advancing),
+        );
+    }
+}
+
+impl NetConfig {
+    // `--net 2 192.168.0.10:7000,192.168.0.11:7000` for the second of two players, `--net-latency
+    // <ms>` and `--net-loss <percent>` make a local test behave like a bad connection
+    pub fn from_args() -> Option<NetConfig> {
+        let args: Vec<String> = std::env::args().collect();
+        let index = args.iter().position(|arg| arg == "--net")?;
+        let player = args.get(index + 1)?.parse().ok()?;
+        let addresses = args
+            .get(index + 2)?
+            .split(',')
+            .map(|address| address.parse().ok())
+            .collect::<Option<Vec<SocketAddr>>>()?;
+        let value_of = |flag: &str| {
+            args.iter()
+                .position(|arg| arg == flag)
+                .and_then(|index| args.get(index + 1))
+                .and_then(|value| value.parse::<f64>().ok())
+        };
+        NetConfig {
+            player,
+            addresses,
+            latency: Duration::from_secs_f64(value_of("--net-latency").unwrap_or(0.0) / 1000.0),
+            loss: value_of("--net-loss").unwrap_or(0.0) / 100.0,
+        }
+        .validated()
+    }
+
+    pub fn validated(self) -> Option<NetConfig> {
+        if !(2..=4).contains(&self.addresses.len()) {
+            error!("network play needs two to four addresses");
+            return None;
+        }
+        if !(1..=self.addresses.len()).contains(&self.player) {
+            error!("player {} has no address", self.player);
+            return None;
+        }
+        Some(self)
+    }
+}
+
+impl Message {
+    pub fn encode(&self) -> Vec<u8> {
+        match self {
+            Message::Hello {
+                player,
+                ready,
+                setup,
+            } => {
+                let mut packet = vec![HELLO, *player, *ready as u8];
+                if let Some(setup) = setup {
+                    packet.extend_from_slice(setup.as_bytes());
+                }
+                packet
+            }
+            Message::Inputs {
+                player,
+                ack,
+                start,
+                inputs,
+                checksum,
+            } => {
+                let mut packet = vec![INPUTS, *player];
+                packet.extend_from_slice(&ack.to_le_bytes());
+                packet.extend_from_slice(&start.to_le_bytes());
+                packet.push(inputs.len() as u8);
+                for input in inputs {
+                    packet.extend_from_slice(&input.to_le_bytes());
+                }
+                packet.extend_from_slice(&checksum.0.to_le_bytes());
+                packet.extend_from_slice(&checksum.1.to_le_bytes());
+                packet
+            }
+        }
+    }
+
+    pub fn decode(packet: &[u8]) -> Option<Message> {
+        match *packet.first()? {
+            HELLO => {
+                let setup = match packet.get(3..) {
+                    Some(bytes) if !bytes.is_empty() => {
+                        Some(String::from_utf8(bytes.to_vec()).ok()?)
+                    }
+                    _ => None,
+                };
+                Some(Message::Hello {
+                    player: *packet.get(1)?,
+                    ready: *packet.get(2)? != 0,
+                    setup,
+                })
+            }
+            INPUTS => {
+                let u32_at =
+                    |at: usize| Some(u32::from_le_bytes(packet.get(at..at + 4)?.try_into().ok()?));
+                let count = *packet.get(10)? as usize;
+                let inputs = (0..count)
+                    .map(|index| {
+                        let at = 11 + index * 2;
+                        Some(u16::from_le_bytes(packet.get(at..at + 2)?.try_into().ok()?))
+                    })
+                    .collect::<Option<Vec<u16>>>()?;
+                let at = 11 + count * 2;
+                let checksum = u64::from_le_bytes(packet.get(at + 4..at + 12)?.try_into().ok()?);
+                Some(Message::Inputs {
+                    player: *packet.get(1)?,
+                    ack: u32_at(2)?,
+                    start: u32_at(6)?,
+                    inputs,
+                    checksum: (u32_at(at)?, checksum),
+                })
+            }
+            _ => None,
+        }
+    }
+}
+
+impl NetLink {
+    fn send(&mut self, address: SocketAddr, message: &Message) {
+        if self.loss > 0.0 && self.rng.gen_bool(self.loss.min(1.0)) {
+            return;
+        }
+        let due = Instant::now() + self.latency;
+        self.outgoing.push_back((due, address, message.encode()));
+    }
+
+    fn flush(&mut self) {
+        let now = Instant::now();
+        while self.outgoing.front().is_some_and(|(due, _, _)| *due <= now) {
+            let Some((_, address, packet)) = self.outgoing.pop_front() else {
+                break;
+            };
+            // a peer that is not up yet refuses the packet, it gets sent again anyway
+            let _ = self.socket.send_to(&packet, address);
+        }
+    }
+
+    fn receive(&mut self) -> Vec<(SocketAddr, Message)> {
+        let mut received = Vec::new();
+        let mut buffer = [0; 2048];
+        loop {
+            match self.socket.recv_from(&mut buffer) {
+                Ok((size, address)) => {
+                    if let Some(message) = Message::decode(&buffer[..size]) {
+                        received.push((address, message));
+                    }
+                }
+                Err(error) if error.kind() == ErrorKind::WouldBlock => break,
+                // on some systems an earlier refused packet shows up here, nothing to do about it
+                Err(_) => continue,
+            }
+        }
+        received
+    }
+}
+
+impl NetSession {
+    // the first tick whose input is still missing from at least one player
+    pub fn confirmed(&self) -> u32 {
+        self.inputs
+            .iter()
+            .map(|inputs| inputs.len() as u32)
+            .min()
+            .unwrap_or(0)
+    }
+
+    // a missing input is guessed as the last known one with the keys held but not pressed again
+    fn input(&self, slot: usize, tick: u32) -> u16 {
+        let Some(inputs) = self.inputs.get(slot) else {
+            return 0;
+        };
+        match inputs.get(tick as usize) {
+            Some(input) => *input,
+            None => inputs.last().map_or(0, |input| input & 0x00ff),
+        }
+    }
+
+    fn send_inputs(&mut self) {
+        let local = self.player - 1;
+        let tick = self.final_tick();
+        let checksum = self
+            .checksums
+            .range(..=tick)
+            .next_back()
+            .map_or((0, 0), |(tick, checksum)| (*tick, *checksum));
+        for slot in 0..self.players {
+            if slot == local {
+                continue;
+            }
+            let sent = &self.inputs[local];
+            let start = self.peers[slot].acked.min(sent.len() as u32);
+            let end = (start + MAX_RESEND).min(sent.len() as u32);
+            let message = Message::Inputs {
+                player: self.player as u8,
+                ack: self.inputs[slot].len() as u32,
+                start,
+                inputs: sent[start as usize..end as usize].to_vec(),
+                checksum,
+            };
+            let address = self.addresses[slot];
+            self.link.send(address, &message);
+        }
+    }
+
+    fn send_hello(&mut self) {
+        let ready = self
+            .peers
+            .iter()
+            .enumerate()
+            .all(|(slot, peer)| peer.heard || slot == self.player - 1);
+        let setup = match self.player {
+            1 => self
+                .setup
+                .as_ref()
+                .and_then(|setup| ron::to_string(setup).ok()),
+            _ => None,
+        };
+        for slot in 0..self.players {
+            if slot == self.player - 1 {
+                continue;
+            }
+            let message = Message::Hello {
+                player: self.player as u8,
+                ready,
+                setup: setup.clone(),
+            };
+            let address = self.addresses[slot];
+            self.link.send(address, &message);
+        }
+    }
+
+    fn handle(&mut self, message: Message) {
+        let player = match &message {
+            Message::Hello { player, .. } | Message::Inputs { player, .. } => *player as usize,
+        };
+        if player == self.player || !(1..=self.players).contains(&player) {
+            return;
+        }
+        let slot = player - 1;
+        let peer = &mut self.peers[slot];
+        peer.heard = true;
+        peer.last_packet = Some(Instant::now());
+        if peer.silent {
+            peer.silent = false;
+            info!("player {} is back", player);
+        }
+        match message {
+            Message::Hello { ready, setup, .. } => {
+                peer.ready |= ready;
+                if let Some(setup) = setup.filter(|_| player == 1 && self.setup.is_none()) {
+                    match ron::from_str(&setup) {
+                        Ok(setup) => self.setup = Some(setup),
+                        Err(error) => warn!("could not read the match setup: {}", error),
+                    }
+                }
+            }
+            Message::Inputs {
+                ack,
+                start,
+                inputs,
+                checksum,
+                ..
+            } => {
+                // inputs only ever come after the hello, a peer sending them is ready
+                peer.ready = true;
+                peer.acked = peer.acked.max(ack);
+                for (tick, input) in (start..).zip(inputs) {
+                    if tick != self.inputs[slot].len() as u32 {
+                        continue;
+                    }
+                    self.inputs[slot].push(input);
+                    let guessed = self.used.get(tick as usize).map(|used| used[slot]);
+                    if guessed.is_some_and(|guessed| guessed != input) {
+                        self.rollback_to = Some(self.rollback_to.map_or(tick, |to| to.min(tick)));
+                    }
+                }
+                let (tick, remote) = checksum;
+                if tick > 0
+                    && tick <= self.final_tick()
+                    && self
+                        .checksums
+                        .get(&tick)
+                        .is_some_and(|local| *local != remote)
+                {
+                    self.desyncs += 1;
+                    warn!("out of sync with player {} at tick {}", player, tick);
+                }
+            }
+        }
+    }
+
+    // ticks up to this one were simulated with every player's real input
+    pub fn final_tick(&self) -> u32 {
+        let tick = self.confirmed().min(self.tick);
+        self.rollback_to.map_or(tick, |to| tick.min(to))
+    }
+}
+
+pub fn netplay(session: Option<Res<NetSession>>) -> bool {
+    session.is_some()
+}
+
+pub fn net_advancing(session: Option<Res<NetSession>>) -> bool {
+    session.is_none_or(|session| session.advancing)
+}
+
+pub fn setup_session(world: &mut World) {
+    let config = world.resource::<NetConfig>().clone();
+    let address = config.addresses[config.player - 1];
+    let socket = match UdpSocket::bind(address).and_then(|socket| {
+        socket.set_nonblocking(true)?;
+        Ok(socket)
+    }) {
+        Ok(socket) => socket,
+        Err(error) => {
+            error!("could not listen on {}: {}", address, error);
+            return;
+        }
+    };
+    start_session(world, config, socket);
+    // see setup_replay, the first clock update would swallow the first tick
+    world
+        .resource_mut::<Time<Real>>()
+        .update_with_duration(Duration::ZERO);
+}
+
+// the lobby hands over the socket its players already talk to each other on
+pub fn start_session(world: &mut World, config: NetConfig, socket: UdpSocket) {
+    let setup = (config.player == 1).then(|| {
+        let arena = world.resource::<ArenaConfig>();
+        NetSetup {
+            seed: world
+                .resource::<GameRng>()
+                .fixed_seed
+                .unwrap_or_else(|| rand::thread_rng().gen()),
+            rules: world.resource::<MatchRules>().clone(),
+            arena: arena.kind.name().to_string(),
+            arena_seed: arena.fixed_seed,
+        }
+    });
+    let players = config.addresses.len();
+    info!(
+        "player {} of {} listening on {}, waiting for the others",
+        config.player,
+        players,
+        socket
+            .local_addr()
+            .map_or_else(|_| String::from("?"), |address| address.to_string())
+    );
+    world.insert_resource(PlayerSlots::networked(players, config.player));
+    world.insert_resource(NetSession {
+        player: config.player,
+        players,
+        phase: NetPhase::Connecting,
+        tick: 0,
+        advancing: false,
+        checksums: BTreeMap::new(),
+        rollbacks: 0,
+        desyncs: 0,
+        addresses: config.addresses,
+        link: NetLink {
+            socket,
+            latency: config.latency,
+            loss: config.loss,
+            rng: StdRng::from_entropy(),
+            outgoing: VecDeque::new(),
+        },
+        peers: (0..players).map(|_| Peer::default()).collect(),
+        setup,
+        // nobody can press anything during the input delay at the start
+        inputs: vec![vec![0; INPUT_DELAY as usize]; players],
+        used: Vec::new(),
+        rollback_to: None,
+        snapshots: VecDeque::new(),
+        resimulating: false,
+        budget: TICK,
+        last_frame: None,
+        raw_keys: None,
+        pressed: 0,
+    });
+    let registry = RollbackRegistry::gameplay().resource::<NetRestart>();
+    registry.init(world);
+    world.insert_resource(registry);
+    world.init_resource::<NetRestart>();
+    world.insert_resource(TimeUpdateStrategy::ManualDuration(Duration::ZERO));
+}
+
+// talks to the peers and decides whether this frame simulates a tick, the clock only moves
+// on those frames
+#[allow(clippy::too_many_arguments)]
+pub fn pace_session(
+    mut session: ResMut<NetSession>,
+    mut strategy: ResMut<TimeUpdateStrategy>,
+    mut rules: ResMut<MatchRules>,
+    mut arena: ResMut<ArenaConfig>,
+    mut game_rng: ResMut<GameRng>,
+    mut next_state: ResMut<NextState<GameState>>,
+) {
+    if session.resimulating {
+        return;
+    }
+    for (_, message) in session.link.receive() {
+        session.handle(message);
+    }
+    let now = Instant::now();
+    let elapsed = session.last_frame.map_or(Duration::ZERO, |last| now - last);
+    session.last_frame = Some(now);
+    session.advancing = false;
+    match session.phase {
+        NetPhase::Connecting => {
+            session.send_hello();
+            let local = session.player - 1;
+            let everyone_ready = session
+                .peers
+                .iter()
+                .enumerate()
+                .all(|(slot, peer)| slot == local || (peer.heard && peer.ready));
+            if let Some(setup) = session.setup.clone().filter(|_| everyone_ready) {
+                info!("all {} players are here, starting", session.players);
+                *rules = setup.rules;
+                *arena = ArenaConfig {
+                    kind: ArenaKind::from_name(&setup.arena).unwrap_or(ArenaKind::Level1),
+                    fixed_seed: setup.arena_seed,
+                    seed: setup.arena_seed.unwrap_or(0),
+                };
+                *game_rng = GameRng::new(Some(setup.seed));
+                next_state.set(GameState::Playing);
+                session.phase = NetPhase::Starting;
+                session.advancing = true;
+            }
+        }
+        NetPhase::Starting => (),
+        NetPhase::Running => {
+            for (slot, peer) in session.peers.iter_mut().enumerate() {
+                let silent = peer
+                    .last_packet
+                    .is_some_and(|last| now - last > Duration::from_secs(SILENT_SECS));
+                if silent && !peer.silent {
+                    peer.silent = true;
+                    warn!("player {} stopped answering, waiting for them", slot + 1);
+                }
+            }
+            session.budget = (session.budget + elapsed).min(MAX_BUDGET);
+            let ahead = session.tick >= session.confirmed() + MAX_PREDICTION;
+            if session.budget >= TICK && !ahead {
+                session.budget -= TICK;
+                session.advancing = true;
+            } else {
+                // nothing new to send in this frame, but the peers still need the acks
+                session.send_inputs();
+            }
+            // the setup only comes with a hello, whoever has not started yet still needs it
+            if session.peers.iter().any(|peer| peer.acked == 0) {
+                session.send_hello();
+            }
+        }
+    }
+    *strategy = TimeUpdateStrategy::ManualDuration(match session.advancing {
+        true if session.phase == NetPhase::Running => TICK,
+        _ => Duration::ZERO,
+    });
+    session.link.flush();
+}
+
+// the local input goes out, a misguessed remote input rolls the world back and simulates it
+// again, then every player's input for this tick is put on the keyboard
+pub fn run_session(world: &mut World) {
+    let raw_keys = world.resource::<Input<KeyCode>>().clone();
+    let input = read_input(&world.resource::<Profiles>().local_bindings(), &raw_keys);
+    let mut session = world.resource_mut::<NetSession>();
+    session.pressed |= input & 0xff00;
+    if !session.advancing {
+        return;
+    }
+    let phase = session.phase;
+    let local = input | std::mem::take(&mut session.pressed);
+    session.raw_keys = Some(raw_keys);
+    if phase == NetPhase::Starting {
+        apply_inputs(world, [0; 4]);
+        return;
+    }
+
+    world.resource_scope(|world, registry: Mut<RollbackRegistry>| {
+        let mut session = world.resource_mut::<NetSession>();
+        let slot = session.player - 1;
+        session.inputs[slot].push(local);
+        if let Some(from) = session.rollback_to.take() {
+            resimulate(world, &registry, from);
+        }
+        let mut session = world.resource_mut::<NetSession>();
+        session.send_inputs();
+        session.link.flush();
+        start_tick(world, &registry);
+    });
+}
+
+// runs the ticks from `from` up to the current one again with what is known by now
+fn resimulate(world: &mut World, registry: &RollbackRegistry, from: u32) {
+    let mut session = world.resource_mut::<NetSession>();
+    let tick = session.tick;
+    if from >= tick {
+        return;
+    }
+    let Some(snapshot) = session
+        .snapshots
+        .iter()
+        .position(|(snapshot_tick, _)| *snapshot_tick == from)
+        .and_then(|index| session.snapshots.remove(index))
+    else {
+        warn!("no snapshot of tick {} left to roll back to", from);
+        return;
+    };
+    session.rollbacks += 1;
+    session.resimulating = true;
+    registry.load(world, &snapshot.1);
+    world
+        .resource_mut::<NetSession>()
+        .snapshots
+        .push_front(snapshot);
+    for resimulated in from..tick {
+        world.resource_mut::<NetSession>().tick = resimulated;
+        world.run_schedule(First);
+        start_tick(world, registry);
+        world.run_schedule(RunFixedUpdateLoop);
+        world.run_schedule(Update);
+        end_tick(world, registry);
+    }
+    world.run_schedule(First);
+    world.resource_mut::<NetSession>().resimulating = false;
+}
+
+fn start_tick(world: &mut World, registry: &RollbackRegistry) {
+    registry.start_tick(world);
+    let session = world.resource::<NetSession>();
+    let tick = session.tick;
+    let inputs = [0, 1, 2, 3].map(|slot| session.input(slot, tick));
+    let mut session = world.resource_mut::<NetSession>();
+    match session.used.get_mut(tick as usize) {
+        Some(used) => *used = inputs,
+        None => session.used.push(inputs),
+    }
+    apply_inputs(world, inputs);
+    restart_finished_round(world);
+}
+
+fn end_tick(world: &mut World, registry: &RollbackRegistry) {
+    registry.end_tick(world);
+    let snapshot = registry.save(world);
+    let checksum = checksum(world);
+    let mut session = world.resource_mut::<NetSession>();
+    session.tick += 1;
+    let tick = session.tick;
+    session
+        .snapshots
+        .retain(|(snapshot_tick, _)| *snapshot_tick != tick);
+    session.snapshots.push_back((tick, snapshot));
+    session.checksums.insert(tick, checksum);
+}
+
+fn apply_inputs(world: &mut World, inputs: [u16; 4]) {
+    let mut keys = world.resource_mut::<Input<KeyCode>>();
+    for (slot, input) in inputs.into_iter().enumerate() {
+        apply_input(&Bindings::for_slot(slot as i32 + 1), input, &mut keys);
+    }
+}
+
+// nobody may click the next round into existence, it starts a few seconds after the last one
+// ended with a seed every peer rolls the same way
+fn restart_finished_round(world: &mut World) {
+    let over = world.resource::<RoundState>().over;
+    let mut restart = world.resource_mut::<NetRestart>();
+    if !over {
+        restart.timer = None;
+        return;
+    }
+    let timer = restart
+        .timer
+        .get_or_insert_with(|| Timer::from_seconds(RESTART_SECS, TimerMode::Once));
+    if !timer.tick(TICK).just_finished() {
+        return;
+    }
+    let mut game_rng = world.resource_mut::<GameRng>();
+    let seed = game_rng.gen();
+    game_rng.fixed_seed = Some(seed);
+    world.send_event(ResetGameEvent {});
+}
+
+// the state at the start of the next tick is kept for a rollback and the keyboard goes back to
+// what the player actually pressed
+pub fn end_session_tick(world: &mut World) {
+    let session = world.resource::<NetSession>();
+    let (advancing, phase) = (session.advancing, session.phase);
+    if let Some(raw_keys) = world.resource_mut::<NetSession>().raw_keys.take() {
+        *world.resource_mut::<Input<KeyCode>>() = raw_keys;
+    }
+    if !advancing {
+        return;
+    }
+    world.resource_scope(|world, registry: Mut<RollbackRegistry>| match phase {
+        NetPhase::Connecting => (),
+        NetPhase::Starting => {
+            registry.end_tick(world);
+            let snapshot = registry.save(world);
+            let mut session = world.resource_mut::<NetSession>();
+            session.snapshots.push_back((0, snapshot));
+            session.phase = NetPhase::Running;
+        }
+        NetPhase::Running => end_tick(world, &registry),
+    });
+    let mut session = world.resource_mut::<NetSession>();
+    let confirmed = session.confirmed();
+    session.snapshots.retain(|(tick, _)| *tick >= confirmed);
+    while session.checksums.len() > CHECKSUMS_KEPT {
+        session.checksums.pop_first();
+    }
+}
+
+// what the peers compare to notice they drifted apart
+pub fn checksum(world: &mut World) -> u64 {
+    let mut players: Vec<(i32, i32, [u32; 2])> = world
+        .query::<(&Player, &Transform)>()
+        .iter(world)
+        .map(|(player, transform)| {
+            (
+                player.player_number,
+                player.lifes,
+                [
+                    transform.translation.x.to_bits(),
+                    transform.translation.y.to_bits(),
+                ],
+            )
+        })
+        .collect();
+    players.sort();
+    let mut bullets: Vec<[u32; 2]> = world
+        .query_filtered::<&Transform, With<Bullet>>()
+        .iter(world)
+        .map(|transform| {
+            [
+                transform.translation.x.to_bits(),
+                transform.translation.y.to_bits(),
+            ]
+        })
+        .collect();
+    bullets.sort();
+    let mut hash = FNV_OFFSET;
+    hash = fnv1a(hash, &(players.len() as u32).to_le_bytes());
+    for (player_number, lifes, [x, y]) in players {
+        for value in [player_number as u32, lifes as u32, x, y] {
+            hash = fnv1a(hash, &value.to_le_bytes());
+        }
+    }
+    hash = fnv1a(hash, &(bullets.len() as u32).to_le_bytes());
+    for [x, y] in bullets {
+        hash = fnv1a(hash, &x.to_le_bytes());
+        hash = fnv1a(hash, &y.to_le_bytes());
+    }
+    fnv1a(hash, &world.resource::<GameRng>().seed.to_le_bytes())
+}
+
+// FNV-1a, the std hasher may change between rust versions and peers built with different
+// ones would see a desync on every tick
+fn fnv1a(hash: u64, bytes: &[u8]) -> u64 {
+    bytes.iter().fold(hash, |hash, byte| {
+        (hash ^ *byte as u64).wrapping_mul(FNV_PRIME)
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn messages_survive_the_wire() {
+        let messages = [
+            Message::Hello {
+                player: 1,
+                ready: true,
+                setup: Some(String::from("(seed: 4)")),
+            },
+            Message::Hello {
+                player: 3,
+                ready: false,
+                setup: None,
+            },
+            Message::Inputs {
+                player: 2,
+                ack: 40,
+                start: 12,
+                inputs: vec![0, 0x0101, 0xffff],
+                checksum: (11, u64::MAX - 7),
+            },
+        ];
+        for message in messages {
+            assert!(Message::decode(&message.encode()) == Some(message));
+        }
+    }
+
+    #[test]
+    fn truncated_packets_are_dropped() {
+        let packet = Message::Inputs {
+            player: 2,
+            ack: 1,
+            start: 0,
+            inputs: vec![3, 4],
+            checksum: (0, 0),
+        }
+        .encode();
+        for size in 0..packet.len() {
+            assert!(Message::decode(&packet[..size]).is_none());
+        }
+    }
+
+    #[test]
+    fn checksums_hash_the_same_everywhere() {
+        // the published FNV-1a values
+        assert_eq!(fnv1a(FNV_OFFSET, b""), 0xcbf2_9ce4_8422_2325);
+        assert_eq!(fnv1a(FNV_OFFSET, b"a"), 0xaf63_dc4c_8601_ec8c);
+        assert_eq!(fnv1a(FNV_OFFSET, b"foobar"), 0x8594_4171_f739_67e8);
+    }
+}
@@ -0,0 +1,465 @@
+use bevy::{app::AppExit, prelude::*};
+use serde::{Deserialize, Serialize};
+use std::{collections::BTreeMap, path::PathBuf};
+
+use crate::client::connected;
+use crate::game_objects::Player;
+use crate::game_utils::{
+    Bindings, GameState, PlayerShootEvent, PlayerSlot, PlayerSlots, ResetGameEvent,
+};
+use crate::replay::replaying;
+use crate::results::MatchLog;
+use crate::settings::spawn_button;
+use crate::StapidSet;
+
+const PROFILES_FILE: &str = "stapid_profiles.ron";
+
+// tints for the player sprite, the blinking only touches the alpha so they stay visible
+pub const COLORS: [(&str, Color); 6] = [
+    ("White", Color::WHITE),
+    ("Red", Color::rgb(1.0, 0.55, 0.55)),
+    ("Blue", Color::rgb(0.55, 0.7, 1.0)),
+    ("Green", Color::rgb(0.6, 1.0, 0.6)),
+    ("Yellow", Color::rgb(1.0, 1.0, 0.55)),
+    ("Purple", Color::rgb(0.85, 0.6, 1.0)),
+];
+
+// whoever sits at this machine, the other players stay anonymous
+pub struct ProfilePlugin;
+
+// everything this profile's player did since the profile was made
+#[derive(Serialize, Deserialize, Clone, Default, PartialEq, Debug)]
+#[serde(default)]
+pub struct LifetimeStats {
+    pub matches_played: u32,
+    pub matches_won: u32,
+    pub kills: u32,
+    pub deaths: u32,
+    pub shots_fired: u32,
+    // lifes the shots took off other players
+    pub hits: u32,
+    pub shots_by_bullet: BTreeMap<String, u32>,
+    pub powerups_collected: u32,
+}
+
+#[derive(Serialize, Deserialize, Clone, PartialEq, Debug)]
+#[serde(default)]
+pub struct Profile {
+    pub name: String,
+    // index into COLORS
+    pub color: usize,
+    // the key set of this player number steers in network games, where nobody else shares
+    // the keyboard
+    pub bindings: i32,
+    pub stats: LifetimeStats,
+}
+
+#[derive(Resource, Serialize, Deserialize, Clone, Default)]
+#[serde(default)]
+pub struct Profiles {
+    pub profiles: Vec<Profile>,
+    pub selected: Option<usize>,
+    // nothing is written without a file, headless runs and the server play for nobody
+    #[serde(skip)]
+    pub path: Option<PathBuf>,
+}
+
+// what the profile's player looked like last tick, for telling what happened since, rolled
+// back with the rest of the gameplay
+#[derive(Resource, Clone, Default)]
+pub struct StatsTracker {
+    lifes: BTreeMap<i32, i32>,
+    powerup: bool,
+    // what the profile's player did this match, it goes into the profile once the match is over
+    pub stats: LifetimeStats,
+}
+
+#[derive(Component)]
+pub struct StatsScreen;
+
+#[derive(Component)]
+pub struct StatsBackButton;
+
+impl Plugin for ProfilePlugin {
+    fn build(&self, app: &mut App) {
+        app.insert_resource(Profiles::from_args())
+            .init_resource::<StatsTracker>()
+            .add_event::<PlayerShootEvent>()
+            .add_systems(OnEnter(GameState::Playing), start_match_stats)
+            .add_systems(
+                Update,
+                clear_stats_tracker
+                    .run_if(on_event::<ResetGameEvent>())
+                    .in_set(StapidSet::Reset),
+            )
+            // before the round systems despawn whoever died in this frame
+            .add_systems(
+                Update,
+                track_lifetime_stats
+                    .after(StapidSet::Hud)
+                    .before(StapidSet::Round)
+                    .run_if(in_state(GameState::Playing))
+                    .run_if(not(replaying))
+                    .run_if(not(connected)),
+            )
+            .add_systems(Last, record_match_stats)
+            .add_systems(OnEnter(GameState::Stats), spawn_stats_screen)
+            .add_systems(Update, stats_clicked.run_if(in_state(GameState::Stats)))
+            .add_systems(OnExit(GameState::Stats), despawn_stats_screen);
+    }
+}
+
+impl Default for Profile {
+    fn default() -> Self {
+        Profile {
+            name: String::from("player"),
+            color: 0,
+            bindings: 1,
+            stats: LifetimeStats::default(),
+        }
+    }
+}
+
+impl LifetimeStats {
+    pub fn accuracy(&self) -> f32 {
+        match self.shots_fired {
+            0 => 0.0,
+            shots => (self.hits as f32 * 100.0 / shots as f32).min(100.0),
+        }
+    }
+
+    // the most fired one, ties go to the first name
+    pub fn favorite_bullet(&self) -> Option<&str> {
+        self.shots_by_bullet
+            .iter()
+            .rev()
+            .max_by_key(|(_, shots)| **shots)
+            .map(|(bullet, _)| bullet.as_str())
+    }
+
+    pub fn add(&mut self, other: &LifetimeStats) {
+        self.matches_played += other.matches_played;
+        self.matches_won += other.matches_won;
+        self.kills += other.kills;
+        self.deaths += other.deaths;
+        self.shots_fired += other.shots_fired;
+        self.hits += other.hits;
+        for (bullet, shots) in &other.shots_by_bullet {
+            *self.shots_by_bullet.entry(bullet.clone()).or_insert(0) += shots;
+        }
+        self.powerups_collected += other.powerups_collected;
+    }
+}
+
+impl Profile {
+    pub fn tint(&self) -> Color {
+        COLORS[self.color % COLORS.len()].1
+    }
+
+    pub fn describe(&self) -> String {
+        format!(
+            "Profile: {}, {}, keys of player {}",
+            self.name,
+            COLORS[self.color % COLORS.len()].0,
+            self.bindings
+        )
+    }
+}
+
+impl Profiles {
+    // `--profiles <path>` reads another file, `--profile <name>` picks that profile and makes
+    // it when it is new
+    pub fn from_args() -> Profiles {
+        let args: Vec<String> = std::env::args().collect();
+        let value_of = |flag: &str| {
+            args.iter()
+                .position(|arg| arg == flag)
+                .and_then(|index| args.get(index + 1))
+        };
+        let path = value_of("--profiles")
+            .map(PathBuf::from)
+            .unwrap_or_else(|| PathBuf::from(PROFILES_FILE));
+        let mut profiles = Profiles::load(path);
+        if let Some(name) = value_of("--profile") {
+            match profiles.select_or_create(name) {
+                Ok(()) => profiles.save(),
+                Err(error) => warn!("not playing with a profile: {}", error),
+            }
+        }
+        profiles
+    }
+
+    // a missing file is an empty list, a broken one is reported and left alone until the next
+    // save
+    pub fn load(path: PathBuf) -> Profiles {
+        let profiles = match std::fs::read_to_string(&path) {
+            Ok(content) => match ron::from_str(&content) {
+                Ok(profiles) => profiles,
+                Err(error) => {
+                    warn!("ignoring profiles in {}: {}", path.display(), error);
+                    Profiles::default()
+                }
+            },
+            Err(_) => Profiles::default(),
+        };
+        Profiles {
+            path: Some(path),
+            ..profiles
+        }
+    }
+
+    pub fn save(&self) {
+        let Some(path) = &self.path else {
+            return;
+        };
+        let result = ron::ser::to_string_pretty(self, ron::ser::PrettyConfig::default())
+            .map_err(|error| error.to_string())
+            .and_then(|content| std::fs::write(path, content).map_err(|error| error.to_string()));
+        if let Err(error) = result {
+            warn!("could not save profiles to {}: {}", path.display(), error);
+        }
+    }
+
+    // a name that only differs in case or spaces picks the existing profile, an empty one or
+    // the name of a player without a profile is refused
+    pub fn select_or_create(&mut self, name: &str) -> Result<(), String> {
+        let name = name.trim();
+        if name.is_empty() {
+            return Err(String::from("the profile name is empty"));
+        }
+        if (1..=4).any(|number| name.eq_ignore_ascii_case(&format!("player{}", number))) {
+            return Err(format!("{} is already the name of a player", name));
+        }
+        let index = match self
+            .profiles
+            .iter()
+            .position(|profile| profile.name.eq_ignore_ascii_case(name))
+        {
+            Some(index) => index,
+            None => {
+                self.profiles.push(Profile {
+                    name: name.to_string(),
+                    color: self.profiles.len() % COLORS.len(),
+                    ..default()
+                });
+                self.profiles.len() - 1
+            }
+        };
+        self.selected = Some(index);
+        Ok(())
+    }
+
+    pub fn current(&self) -> Option<&Profile> {
+        self.profiles.get(self.selected?)
+    }
+
+    pub fn current_mut(&mut self) -> Option<&mut Profile> {
+        self.profiles.get_mut(self.selected?)
+    }
+
+    // goes through the profiles and then to none
+    pub fn select_next(&mut self) {
+        self.selected = match self.selected {
+            None if self.profiles.is_empty() => None,
+            None => Some(0),
+            Some(index) if index + 1 < self.profiles.len() => Some(index + 1),
+            Some(_) => None,
+        };
+    }
+
+    // the keys this machine's player steers with in a network game
+    pub fn local_bindings(&self) -> Bindings {
+        Bindings::for_slot(self.current().map_or(1, |profile| profile.bindings))
+    }
+}
+
+// the profile plays the first local player, the only one in a network game and the one on
+// the player 1 keys otherwise
+pub fn profile_player(slots: &PlayerSlots) -> Option<i32> {
+    (1..5).find(|player_number| slots.get(*player_number) == PlayerSlot::Local)
+}
+
+pub fn start_match_stats(mut tracker: ResMut<StatsTracker>) {
+    *tracker = StatsTracker::default();
+}
+
+// the counts go on over the whole match
+pub fn clear_stats_tracker(mut tracker: ResMut<StatsTracker>) {
+    *tracker = StatsTracker {
+        stats: std::mem::take(&mut tracker.stats),
+        ..default()
+    };
+}
+
+// watches the lifes instead of the collisions and only counts into the tracker, a rollback
+// puts the tracker back too and the ticks it simulates again are counted as they turn out
+pub fn track_lifetime_stats(
+    profiles: Res<Profiles>,
+    mut tracker: ResMut<StatsTracker>,
+    slots: Res<PlayerSlots>,
+    players: Query<&Player>,
+    mut shots: EventReader<PlayerShootEvent>,
+) {
+    let Some(own) = profile_player(&slots) else {
+        shots.clear();
+        return;
+    };
+    if profiles.current().is_none() {
+        shots.clear();
+        return;
+    }
+    let tracker = &mut *tracker;
+    let stats = &mut tracker.stats;
+    // a bullet can be gone again before a frame saw it, the shot still happened
+    for shot in shots.read().filter(|shot| shot.player_number == own) {
+        stats.shots_fired += 1;
+        *stats
+            .shots_by_bullet
+            .entry(shot.bullet_type.name().to_string())
+            .or_insert(0) += 1;
+    }
+    for player in &players {
+        let before = tracker
+            .lifes
+            .insert(player.player_number, player.lifes)
+            .unwrap_or(player.lifes);
+        if player.lifes >= before {
+            continue;
+        }
+        let by_own = player.last_hit_by == Some(own) && player.player_number != own;
+        if by_own {
+            stats.hits += (before - player.lifes) as u32;
+        }
+        if before > 0 && player.lifes < 1 {
+            match player.player_number == own {
+                true => stats.deaths += 1,
+                false if by_own => stats.kills += 1,
+                false => (),
+            }
+        }
+    }
+    if let Some(player) = players.iter().find(|player| player.player_number == own) {
+        if player.powerup && !tracker.powerup {
+            stats.powerups_collected += 1;
+        }
+        tracker.powerup = player.powerup;
+    }
+}
+
+// a match counts once it is left or the game closes, won by whoever leads the standings, and
+// only if a round of it was finished
+pub fn record_match_stats(
+    mut profiles: ResMut<Profiles>,
+    tracker: Res<StatsTracker>,
+    slots: Res<PlayerSlots>,
+    log: Res<MatchLog>,
+    state: Res<State<GameState>>,
+    mut exit: EventReader<AppExit>,
+    mut recorded: Local<Option<u64>>,
+) {
+    let finished = *state.get() != GameState::Playing || exit.read().last().is_some();
+    if !finished || log.rounds.is_empty() || *recorded == Some(log.started) {
+        return;
+    }
+    *recorded = Some(log.started);
+    let Some(own) = profile_player(&slots) else {
+        return;
+    };
+    let Some(profile) = profiles.current_mut() else {
+        return;
+    };
+    profile.stats.add(&LifetimeStats {
+        matches_played: 1,
+        matches_won: (log.winner(&slots) == Some(own)) as u32,
+        ..tracker.stats.clone()
+    });
+    profiles.save();
+}
+
+pub fn spawn_stats_screen(
+    mut commands: Commands,
+    asset_server: Res<AssetServer>,
+    profiles: Res<Profiles>,
+) {
+    let font = asset_server.load("/assets/fonts/PixeloidSans.ttf");
+    let text_style = |font_size: f32| TextStyle {
+        font: font.clone(),
+        font_size,
+        color: Color::WHITE,
+    };
+    let mut rows = vec![format!(
+        "{:<14}{:>8}{:>6}{:>7}{:>8}{:>10}{:>10}{:>11}",
+        "Profile", "Played", "Won", "Kills", "Deaths", "Accuracy", "Favorite", "Power-ups"
+    )];
+    for profile in &profiles.profiles {
+        let stats = &profile.stats;
+        rows.push(format!(
+            "{:<14}{:>8}{:>6}{:>7}{:>8}{:>9.1}%{:>10}{:>11}",
+            profile.name,
+            stats.matches_played,
+            stats.matches_won,
+            stats.kills,
+            stats.deaths,
+            stats.accuracy(),
+            stats.favorite_bullet().unwrap_or("-"),
+            stats.powerups_collected
+        ));
+    }
+    if profiles.profiles.is_empty() {
+        rows.push(String::from(
+            "No profiles yet, start the game with --profile <name>",
+        ));
+    }
+    commands
+        .spawn((
+            NodeBundle {
+                style: Style {
+                    width: Val::Percent(100.0),
+                    height: Val::Percent(100.0),
+                    flex_direction: FlexDirection::Column,
+                    align_items: AlignItems::Center,
+                    justify_content: JustifyContent::Center,
+                    ..default()
+                },
+                background_color: Color::rgba(0.0, 0.0, 0.0, 0.85).into(),
+                z_index: ZIndex::Global(10),
+                ..default()
+            },
+            StatsScreen,
+        ))
+        .with_children(|parent| {
+            parent.spawn(TextBundle::from_section("Lifetime stats", text_style(40.0)));
+            parent.spawn(
+                TextBundle::from_section(rows.join("\n"), text_style(20.0)).with_style(Style {
+                    margin: UiRect::all(Val::Px(16.0)),
+                    ..default()
+                }),
+            );
+            spawn_button(
+                parent,
+                &asset_server,
+                "Back",
+                text_style(30.0),
+                StatsBackButton,
+            );
+        });
+}
+
+pub fn stats_clicked(
+    interaction_query: Query<&Interaction, (Changed<Interaction>, With<StatsBackButton>)>,
+    mut next_state: ResMut<NextState<GameState>>,
+    keys: Res<Input<KeyCode>>,
+) {
+    let back = interaction_query
+        .iter()
+        .any(|interaction| *interaction == Interaction::Pressed);
+    if back || keys.just_pressed(KeyCode::Escape) {
+        next_state.set(GameState::Settings);
+    }
+}
+
+pub fn despawn_stats_screen(mut commands: Commands, screens: Query<Entity, With<StatsScreen>>) {
+    for entity in &screens {
+        commands.entity(entity).despawn_recursive();
+    }
+}
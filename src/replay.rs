@@ -0,0 +1,407 @@
+use bevy::{
+    app::AppExit,
+    input::InputSystem,
+    prelude::*,
+    time::{TimeSystem, TimeUpdateStrategy},
+    utils::{Duration, Instant},
+    window::{PresentMode, PrimaryWindow},
+};
+use serde::{Deserialize, Serialize};
+use std::path::PathBuf;
+
+use crate::arena::{ArenaConfig, ArenaKind};
+use crate::game_utils::{Bindings, GameState, ResetGameEvent, RoundOverEvent};
+use crate::rng::{reseed_rng, GameRng};
+use crate::rules::MatchRules;
+use crate::StapidSet;
+
+// recorded and replayed matches advance by exactly this much every frame
+pub const TICK: Duration = Duration::from_micros(16_667);
+// how far playback may fall behind before it stops trying to catch up
+const MAX_BUDGET: Duration = Duration::from_micros(4 * 16_667);
+const SPEEDS: [(KeyCode, f32); 3] = [
+    (KeyCode::Key1, 1.0),
+    (KeyCode::Key2, 2.0),
+    (KeyCode::Key4, 4.0),
+];
+
+// `--record <file>` writes the match to a replay, `--replay <file>` plays one back
+pub struct ReplayPlugin;
+
+#[derive(Resource, Clone, PartialEq)]
+pub enum ReplayMode {
+    Off,
+    Record(PathBuf),
+    Play(PathBuf),
+}
+
+// everything needed to play a match again: the setup, the seed of every round and what
+// each player held down on every tick
+#[derive(Serialize, Deserialize, Default)]
+pub struct Replay {
+    pub arena: String,
+    pub arena_seed: Option<u64>,
+    pub rules: MatchRules,
+    // tick a round started on and the seed it rolled, the first one is the match start
+    pub rounds: Vec<(u32, u64)>,
+    // runs of identical ticks: how many of them and the input of players one to four
+    pub inputs: Vec<(u32, [u16; 4])>,
+}
+
+#[derive(Resource)]
+pub struct ReplayRecorder {
+    pub path: PathBuf,
+    pub replay: Replay,
+    pub tick: u32,
+}
+
+#[derive(Resource)]
+pub struct ReplayPlayback {
+    pub ticks: Vec<[u16; 4]>,
+    pub rounds: Vec<(u32, u64)>,
+    pub tick: usize,
+    pub paused: bool,
+    pub speed: f32,
+    pub step: bool,
+    // true on the frames that play a tick, every other frame the game stands still
+    pub advancing: bool,
+    budget: Duration,
+    last_frame: Option<Instant>,
+}
+
+impl Plugin for ReplayPlugin {
+    fn build(&self, app: &mut App) {
+        app.insert_resource(ReplayMode::from_args())
+            .add_systems(PreStartup, setup_replay)
+            .add_systems(
+                OnEnter(GameState::Playing),
+                (restart_fixed_clock, start_recording),
+            )
+            .add_systems(
+                First,
+                pace_replay
+                    .before(TimeSystem)
+                    .run_if(resource_exists::<ReplayPlayback>()),
+            )
+            .add_systems(
+                PreUpdate,
+                (replay_controls, play_tick)
+                    .chain()
+                    .after(InputSystem)
+                    .run_if(resource_exists::<ReplayPlayback>()),
+            )
+            .add_systems(
+                Update,
+                record_tick
+                    .after(reseed_rng)
+                    .in_set(StapidSet::Reset)
+                    .run_if(resource_exists::<ReplayRecorder>()),
+            )
+            .add_systems(
+                Last,
+                save_recording.run_if(resource_exists::<ReplayRecorder>()),
+            )
+            .configure_sets(
+                Update,
+                (
+                    StapidSet::Reset,
+                    StapidSet::Spawn,
+                    StapidSet::Input,
+                    StapidSet::Effects,
+                    StapidSet::Hud,
+                    StapidSet::Round,
+                    StapidSet::KillCam,
+                )
+                    .run_if(replay_advancing),
+            );
+    }
+}
+
+impl ReplayMode {
+    pub fn from_args() -> ReplayMode {
+        let args: Vec<String> = std::env::args().collect();
+        let value_of = |flag: &str| {
+            args.iter()
+                .position(|arg| arg == flag)
+                .and_then(|index| args.get(index + 1))
+                .map(PathBuf::from)
+        };
+        if let Some(path) = value_of("--replay") {
+            return ReplayMode::Play(path);
+        }
+        match value_of("--record") {
+            Some(path) => ReplayMode::Record(path),
+            None => ReplayMode::Off,
+        }
+    }
+}
+
+impl Replay {
+    pub fn push(&mut self, input: [u16; 4]) {
+        match self.inputs.last_mut() {
+            Some((count, last)) if *last == input => *count += 1,
+            _ => self.inputs.push((1, input)),
+        }
+    }
+
+    pub fn ticks(&self) -> Vec<[u16; 4]> {
+        self.inputs
+            .iter()
+            .flat_map(|(count, input)| std::iter::repeat_n(*input, *count as usize))
+            .collect()
+    }
+
+    pub fn load(path: &PathBuf) -> Result<Replay, String> {
+        let content = std::fs::read_to_string(path).map_err(|error| error.to_string())?;
+        ron::from_str(&content).map_err(|error| error.to_string())
+    }
+
+    pub fn save(&self, path: &PathBuf) -> Result<(), String> {
+        if let Some(parent) = path
+            .parent()
+            .filter(|parent| !parent.as_os_str().is_empty())
+        {
+            std::fs::create_dir_all(parent).map_err(|error| error.to_string())?;
+        }
+        let content = ron::to_string(self).map_err(|error| error.to_string())?;
+        std::fs::write(path, content).map_err(|error| error.to_string())
+    }
+}
+
+// the low byte holds the keys that are down, the high byte the ones pressed this tick,
+// both in the order of Bindings::keys
+pub fn read_input(bindings: &Bindings, keys: &Input<KeyCode>) -> u16 {
+    let mut input = 0;
+    for (bit, key) in bindings.keys().into_iter().enumerate() {
+        if keys.pressed(key) {
+            input |= 1 << bit;
+        }
+        if keys.just_pressed(key) {
+            input |= 1 << (bit + 8);
+        }
+    }
+    input
+}
+
+pub fn apply_input(bindings: &Bindings, input: u16, keys: &mut Input<KeyCode>) {
+    for (bit, key) in bindings.keys().into_iter().enumerate() {
+        keys.reset(key);
+        let held = input & (1 << bit) != 0;
+        if input & (1 << (bit + 8)) != 0 {
+            keys.press(key);
+            if !held {
+                keys.release(key);
+            }
+        } else if held {
+            keys.press(key);
+            keys.clear_just_pressed(key);
+        }
+    }
+}
+
+pub fn replaying(playback: Option<Res<ReplayPlayback>>) -> bool {
+    playback.is_some()
+}
+
+pub fn replay_advancing(playback: Option<Res<ReplayPlayback>>) -> bool {
+    playback.is_none_or(|playback| playback.advancing)
+}
+
+#[allow(clippy::too_many_arguments)]
+pub fn setup_replay(
+    mut commands: Commands,
+    mut mode: ResMut<ReplayMode>,
+    mut rules: ResMut<MatchRules>,
+    mut arena: ResMut<ArenaConfig>,
+    mut game_rng: ResMut<GameRng>,
+    mut real_time: ResMut<Time<Real>>,
+    mut next_state: ResMut<NextState<GameState>>,
+    mut windows: Query<&mut Window, With<PrimaryWindow>>,
+) {
+    if *mode != ReplayMode::Off {
+        // the very first clock update reports no time passing, use it up now so the
+        // first tick moves like every other one no matter on which frame the match starts
+        real_time.update_with_duration(Duration::ZERO);
+    }
+    match mode.clone() {
+        ReplayMode::Off => (),
+        ReplayMode::Record(path) => {
+            commands.insert_resource(TimeUpdateStrategy::ManualDuration(TICK));
+            commands.insert_resource(ReplayRecorder {
+                path,
+                replay: Replay::default(),
+                tick: 0,
+            });
+        }
+        ReplayMode::Play(path) => {
+            let replay = match Replay::load(&path) {
+                Ok(replay) => replay,
+                Err(error) => {
+                    error!("could not load replay {}: {}", path.display(), error);
+                    *mode = ReplayMode::Off;
+                    return;
+                }
+            };
+            *rules = replay.rules.clone();
+            let kind = ArenaKind::from_name(&replay.arena).unwrap_or(ArenaKind::Level1);
+            *arena = ArenaConfig {
+                kind,
+                fixed_seed: replay.arena_seed,
+                seed: replay.arena_seed.unwrap_or(0),
+            };
+            *game_rng = GameRng::new(replay.rounds.first().map(|(_, seed)| *seed));
+            next_state.set(GameState::Playing);
+            // playing faster than real time needs more frames than the display refreshes
+            for mut window in &mut windows {
+                window.present_mode = PresentMode::AutoNoVsync;
+            }
+            info!(
+                "playing back {} with {} ticks, space pauses, n steps, 1, 2 and 4 set the speed",
+                path.display(),
+                replay.ticks().len()
+            );
+            commands.insert_resource(ReplayPlayback {
+                ticks: replay.ticks(),
+                rounds: replay.rounds,
+                tick: 0,
+                paused: false,
+                speed: 1.0,
+                step: false,
+                advancing: false,
+                budget: TICK,
+                last_frame: None,
+            });
+        }
+    }
+}
+
+// whatever the fixed clock had left over from the settings screen would shift the first ticks
+pub fn restart_fixed_clock(mut fixed: ResMut<Time<Fixed>>) {
+    *fixed = Time::<Fixed>::from_duration(fixed.timestep());
+}
+
+pub fn start_recording(
+    recorder: Option<ResMut<ReplayRecorder>>,
+    rules: Res<MatchRules>,
+    arena: Res<ArenaConfig>,
+) {
+    let Some(mut recorder) = recorder else {
+        return;
+    };
+    recorder.replay = Replay {
+        arena: arena.kind.name().to_string(),
+        arena_seed: arena.fixed_seed,
+        rules: rules.clone(),
+        ..default()
+    };
+    recorder.tick = 0;
+}
+
+pub fn record_tick(
+    mut recorder: ResMut<ReplayRecorder>,
+    game_rng: Res<GameRng>,
+    keys: Res<Input<KeyCode>>,
+    mut event_reader: EventReader<ResetGameEvent>,
+) {
+    let tick = recorder.tick;
+    if event_reader.read().last().is_some() {
+        recorder.replay.rounds.push((tick, game_rng.seed));
+    }
+    let input = [1, 2, 3, 4].map(|slot| read_input(&Bindings::for_slot(slot), &keys));
+    recorder.replay.push(input);
+    recorder.tick += 1;
+}
+
+// written whenever a round ends and when the game closes, so a crash loses at most one round
+pub fn save_recording(
+    recorder: Res<ReplayRecorder>,
+    mut round_over: EventReader<RoundOverEvent>,
+    mut exit: EventReader<AppExit>,
+) {
+    if round_over.read().last().is_none() && exit.read().last().is_none() {
+        return;
+    }
+    match recorder.replay.save(&recorder.path) {
+        Ok(()) => info!(
+            "saved replay with {} ticks to {}",
+            recorder.tick,
+            recorder.path.display()
+        ),
+        Err(error) => warn!(
+            "could not save replay to {}: {}",
+            recorder.path.display(),
+            error
+        ),
+    }
+}
+
+// decides once per frame whether a tick gets played, the clock only moves on those frames
+pub fn pace_replay(mut playback: ResMut<ReplayPlayback>, mut strategy: ResMut<TimeUpdateStrategy>) {
+    let now = Instant::now();
+    let elapsed = playback
+        .last_frame
+        .map_or(Duration::ZERO, |last| now - last);
+    playback.last_frame = Some(now);
+    playback.advancing = false;
+    if playback.tick < playback.ticks.len() {
+        if playback.paused {
+            playback.advancing = std::mem::take(&mut playback.step);
+        } else {
+            let budget = playback.budget + elapsed.mul_f32(playback.speed);
+            playback.budget = budget.min(MAX_BUDGET);
+            if playback.budget >= TICK {
+                playback.budget -= TICK;
+                playback.advancing = true;
+            }
+        }
+    }
+    *strategy = TimeUpdateStrategy::ManualDuration(match playback.advancing {
+        true => TICK,
+        false => Duration::ZERO,
+    });
+}
+
+pub fn replay_controls(mut playback: ResMut<ReplayPlayback>, keys: Res<Input<KeyCode>>) {
+    if keys.just_pressed(KeyCode::Space) {
+        playback.paused = !playback.paused;
+        playback.budget = Duration::ZERO;
+    }
+    if keys.just_pressed(KeyCode::N) {
+        playback.paused = true;
+        playback.step = true;
+    }
+    for (key, speed) in SPEEDS {
+        if keys.just_pressed(key) {
+            playback.speed = speed;
+        }
+    }
+}
+
+// overrides the player keys with the recorded ones, real key presses never reach the game
+pub fn play_tick(
+    mut playback: ResMut<ReplayPlayback>,
+    mut game_rng: ResMut<GameRng>,
+    mut keys: ResMut<Input<KeyCode>>,
+    mut event_writer: EventWriter<ResetGameEvent>,
+) {
+    if !playback.advancing {
+        return;
+    }
+    let tick = playback.tick;
+    for (slot, input) in playback.ticks[tick].into_iter().enumerate() {
+        apply_input(&Bindings::for_slot(slot as i32 + 1), input, &mut keys);
+    }
+    // the match start already resets by itself
+    let restart = playback
+        .rounds
+        .iter()
+        .find(|(round_tick, _)| *round_tick as usize == tick && tick > 0);
+    if let Some((_, seed)) = restart {
+        game_rng.fixed_seed = Some(*seed);
+        event_writer.send_default();
+    }
+    playback.tick += 1;
+    if playback.tick == playback.ticks.len() {
+        info!("replay finished");
+    }
+}
@@ -0,0 +1,391 @@
+use bevy::{app::AppExit, prelude::*};
+use serde::{Serialize, Serializer};
+use std::{path::PathBuf, time::SystemTime};
+
+use crate::arena::ArenaConfig;
+use crate::client::connected;
+use crate::game_utils::{
+    BulletType, GameState, PlayerDamageEvent, PlayerSlot, PlayerSlots, ResetGameEvent,
+    RoundOverEvent,
+};
+use crate::profiles::{profile_player, Profiles};
+use crate::replay::replaying;
+use crate::round::RoundState;
+use crate::rules::MatchRules;
+use crate::StapidSet;
+
+const RESULTS_DIR: &str = "stapid_results";
+
+// a json file per match for whoever keeps the tournament brackets
+pub struct ResultsPlugin;
+
+#[derive(Resource, Clone, PartialEq, Debug)]
+pub enum ResultsOutput {
+    Off,
+    Stdout,
+    Directory(PathBuf),
+}
+
+#[derive(Clone, Serialize)]
+pub struct KillRecord {
+    pub round: u32,
+    // since the round started
+    pub secs: f32,
+    // None when the arena got the player
+    pub killer: Option<i32>,
+    pub victim: i32,
+    #[serde(rename = "bullet", serialize_with = "bullet_name")]
+    pub bullet_type: Option<BulletType>,
+}
+
+#[derive(Clone, Serialize)]
+pub struct RoundResult {
+    pub round: u32,
+    pub winner: Option<i32>,
+    pub secs: f32,
+}
+
+// what the results file is made of, rolled back together with the gameplay
+#[derive(Resource, Clone, Default)]
+pub struct MatchLog {
+    // unix time
+    pub started: u64,
+    pub round: u32,
+    pub round_secs: f32,
+    pub rounds: Vec<RoundResult>,
+    pub kills: Vec<KillRecord>,
+}
+
+// the whole file, `final` stays false while the match can still go on
+#[derive(Serialize)]
+struct MatchResults<'a> {
+    #[serde(rename = "final")]
+    finished: bool,
+    // unix time
+    started: u64,
+    updated: u64,
+    arena: ArenaResult,
+    rules: &'a MatchRules,
+    players: Vec<PlayerResult>,
+    rounds: &'a [RoundResult],
+    kills: &'a [KillRecord],
+    standings: Vec<Standing>,
+}
+
+#[derive(Serialize)]
+struct ArenaResult {
+    kind: &'static str,
+    seed: u64,
+}
+
+#[derive(Serialize)]
+struct PlayerResult {
+    player: i32,
+    name: String,
+    slot: &'static str,
+    profile: Option<String>,
+}
+
+#[derive(Serialize)]
+struct Standing {
+    place: usize,
+    #[serde(rename = "player")]
+    player_number: i32,
+    #[serde(rename = "round_wins")]
+    wins: u32,
+    kills: u32,
+    deaths: u32,
+}
+
+impl Plugin for ResultsPlugin {
+    fn build(&self, app: &mut App) {
+        app.insert_resource(ResultsOutput::from_args())
+            .init_resource::<MatchLog>()
+            .add_event::<ResetGameEvent>()
+            .add_event::<RoundOverEvent>()
+            .add_event::<PlayerDamageEvent>()
+            .add_systems(OnEnter(GameState::Playing), start_match_log)
+            .add_systems(
+                Update,
+                next_round
+                    .run_if(on_event::<ResetGameEvent>())
+                    .in_set(StapidSet::Reset),
+            )
+            .add_systems(
+                Update,
+                log_kills.run_if(not(connected)).in_set(StapidSet::Effects),
+            )
+            .add_systems(
+                Update,
+                log_round_over
+                    .after(StapidSet::Round)
+                    .run_if(in_state(GameState::Playing))
+                    .run_if(not(replaying))
+                    .run_if(not(connected)),
+            )
+            // after a rollback simulated the same ticks again, so every round is written once
+            .add_systems(Last, write_match_results);
+    }
+}
+
+impl ResultsOutput {
+    // `--results <dir>` writes somewhere else, `--results -` prints to stdout and
+    // `--no-results` keeps nothing
+    pub fn from_args() -> ResultsOutput {
+        let args: Vec<String> = std::env::args().collect();
+        if args.iter().any(|arg| arg == "--no-results") {
+            return ResultsOutput::Off;
+        }
+        let value = args
+            .iter()
+            .position(|arg| arg == "--results")
+            .and_then(|index| args.get(index + 1));
+        match value.map(String::as_str) {
+            Some("-") => ResultsOutput::Stdout,
+            Some(dir) => ResultsOutput::Directory(PathBuf::from(dir)),
+            None => ResultsOutput::Directory(PathBuf::from(RESULTS_DIR)),
+        }
+    }
+}
+
+impl MatchLog {
+    pub fn file_name(&self) -> String {
+        format!("stapid_match_{}.json", self.started)
+    }
+
+    // most round wins first, then kills, then the fewest deaths
+    fn standings(&self, slots: &PlayerSlots) -> Vec<Standing> {
+        let mut standings: Vec<Standing> = (1..5)
+            .filter(|player_number| slots.taken(*player_number))
+            .map(|player_number| Standing {
+                place: 0,
+                player_number,
+                wins: self
+                    .rounds
+                    .iter()
+                    .filter(|round| round.winner == Some(player_number))
+                    .count() as u32,
+                kills: self
+                    .kills
+                    .iter()
+                    .filter(|kill| {
+                        kill.killer == Some(player_number) && kill.victim != player_number
+                    })
+                    .count() as u32,
+                deaths: self
+                    .kills
+                    .iter()
+                    .filter(|kill| kill.victim == player_number)
+                    .count() as u32,
+            })
+            .collect();
+        standings.sort_by_key(|standing| {
+            (
+                std::cmp::Reverse(standing.wins),
+                std::cmp::Reverse(standing.kills),
+                standing.deaths,
+                standing.player_number,
+            )
+        });
+        for (index, standing) in standings.iter_mut().enumerate() {
+            standing.place = index + 1;
+        }
+        standings
+    }
+
+    // whoever the standings put first
+    pub fn winner(&self, slots: &PlayerSlots) -> Option<i32> {
+        self.standings(slots)
+            .first()
+            .map(|standing| standing.player_number)
+    }
+
+    fn document<'a>(
+        &'a self,
+        finished: bool,
+        arena: &ArenaConfig,
+        rules: &'a MatchRules,
+        slots: &PlayerSlots,
+        profiles: &Profiles,
+    ) -> MatchResults<'a> {
+        let profile = profile_player(slots).zip(profiles.current());
+        let players = (1..5)
+            .filter(|player_number| slots.taken(*player_number))
+            .map(|player_number| {
+                let profile = match profile {
+                    Some((number, profile)) if number == player_number => {
+                        Some(profile.name.clone())
+                    }
+                    _ => None,
+                };
+                PlayerResult {
+                    player: player_number,
+                    name: profile
+                        .clone()
+                        .unwrap_or_else(|| format!("player{}", player_number)),
+                    slot: match slots.get(player_number) {
+                        PlayerSlot::Remote => "remote",
+                        _ => "local",
+                    },
+                    profile,
+                }
+            })
+            .collect();
+        MatchResults {
+            finished,
+            started: self.started,
+            updated: unix_now(),
+            arena: ArenaResult {
+                kind: arena.kind.name(),
+                seed: arena.seed,
+            },
+            rules,
+            players,
+            rounds: &self.rounds,
+            kills: &self.kills,
+            standings: self.standings(slots),
+        }
+    }
+}
+
+// the results and the round stats files are both written this way
+pub fn to_json<T: Serialize>(value: &T) -> String {
+    // plain structs and maps with number keys, serde_json has nothing to refuse
+    serde_json::to_string_pretty(value).expect("results serialize") + "\n"
+}
+
+fn bullet_name<S: Serializer>(
+    bullet: &Option<BulletType>,
+    serializer: S,
+) -> Result<S::Ok, S::Error> {
+    bullet.as_ref().map(BulletType::name).serialize(serializer)
+}
+
+fn unix_now() -> u64 {
+    SystemTime::now()
+        .duration_since(SystemTime::UNIX_EPOCH)
+        .map_or(0, |since| since.as_secs())
+}
+
+pub fn start_match_log(mut log: ResMut<MatchLog>) {
+    *log = MatchLog {
+        started: unix_now(),
+        ..default()
+    };
+}
+
+pub fn next_round(mut log: ResMut<MatchLog>) {
+    log.round += 1;
+    log.round_secs = 0.0;
+}
+
+pub fn log_kills(
+    mut log: ResMut<MatchLog>,
+    mut damage: EventReader<PlayerDamageEvent>,
+    round: Res<RoundState>,
+    time: Res<Time>,
+) {
+    if round.over {
+        damage.clear();
+        return;
+    }
+    log.round_secs += time.delta_seconds();
+    for hit in damage.read() {
+        if !hit.lethal {
+            continue;
+        }
+        let kill = KillRecord {
+            round: log.round,
+            secs: log.round_secs,
+            killer: hit.attacker,
+            victim: hit.victim,
+            bullet_type: hit.bullet_type.clone(),
+        };
+        log.kills.push(kill);
+    }
+}
+
+pub fn log_round_over(mut log: ResMut<MatchLog>, mut event_reader: EventReader<RoundOverEvent>) {
+    for event in event_reader.read() {
+        let result = RoundResult {
+            round: log.round,
+            winner: event.winner,
+            secs: log.round_secs,
+        };
+        log.rounds.push(result);
+    }
+}
+
+// the file is written again after every round, so it always holds the whole match, and a last
+// time once the match is left or the game closes
+#[allow(clippy::too_many_arguments)]
+pub fn write_match_results(
+    log: Res<MatchLog>,
+    output: Res<ResultsOutput>,
+    arena: Res<ArenaConfig>,
+    rules: Res<MatchRules>,
+    slots: Res<PlayerSlots>,
+    profiles: Res<Profiles>,
+    state: Res<State<GameState>>,
+    mut exit: EventReader<AppExit>,
+    mut written: Local<(u64, usize, bool)>,
+) {
+    let finished = *state.get() != GameState::Playing || exit.read().last().is_some();
+    let key = (log.started, log.rounds.len(), finished);
+    if *written == key || log.rounds.is_empty() {
+        return;
+    }
+    *written = key;
+    let results = log.document(finished, &arena, &rules, &slots, &profiles);
+    match &*output {
+        ResultsOutput::Off => (),
+        // one document per line, only the one marked final holds the finished match
+        ResultsOutput::Stdout => {
+            println!(
+                "{}",
+                serde_json::to_string(&results).expect("results serialize")
+            );
+        }
+        ResultsOutput::Directory(dir) => {
+            let path = dir.join(log.file_name());
+            let result =
+                std::fs::create_dir_all(dir).and_then(|_| std::fs::write(&path, to_json(&results)));
+            if let Err(error) = result {
+                warn!(
+                    "could not write the results to {}: {}",
+                    path.display(),
+                    error
+                );
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn kills_name_the_bullet() {
+        let kills = [
+            KillRecord {
+                round: 1,
+                secs: 2.5,
+                killer: Some(2),
+                victim: 1,
+                bullet_type: Some(BulletType::IceBullet),
+            },
+            KillRecord {
+                round: 1,
+                secs: 4.0,
+                killer: None,
+                victim: 2,
+                bullet_type: None,
+            },
+        ];
+        let json: serde_json::Value = serde_json::from_str(&to_json(&kills)).unwrap();
+        assert_eq!(json[0]["bullet"], "Ice");
+        assert_eq!(json[0]["killer"], 2);
+        assert!(json[1]["bullet"].is_null() && json[1]["killer"].is_null());
+    }
+}
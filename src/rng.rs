@@ -0,0 +1,68 @@
+use bevy::prelude::*;
+use rand::{Rng, RngCore, SeedableRng};
+use rand_chacha::ChaCha8Rng;
+
+use crate::game_utils::ResetGameEvent;
+
+// every random roll of the gameplay goes through this, a round started with the same seed
+// plays out the same way given the same inputs
+#[derive(Resource, Clone)]
+pub struct GameRng {
+    pub fixed_seed: Option<u64>,
+    pub seed: u64,
+    rng: ChaCha8Rng,
+}
+
+impl GameRng {
+    pub fn new(fixed_seed: Option<u64>) -> GameRng {
+        let seed = fixed_seed.unwrap_or(0);
+        GameRng {
+            fixed_seed,
+            seed,
+            rng: ChaCha8Rng::seed_from_u64(seed),
+        }
+    }
+
+    // `--seed <number>` makes every round start from that seed
+    pub fn from_args() -> GameRng {
+        let args: Vec<String> = std::env::args().collect();
+        let fixed_seed = args
+            .iter()
+            .position(|arg| arg == "--seed")
+            .and_then(|index| args.get(index + 1))
+            .and_then(|seed| seed.parse().ok());
+        GameRng::new(fixed_seed)
+    }
+
+    pub fn reseed(&mut self) {
+        self.seed = self.fixed_seed.unwrap_or_else(|| rand::thread_rng().gen());
+        self.rng = ChaCha8Rng::seed_from_u64(self.seed);
+    }
+}
+
+impl RngCore for GameRng {
+    fn next_u32(&mut self) -> u32 {
+        self.rng.next_u32()
+    }
+
+    fn next_u64(&mut self) -> u64 {
+        self.rng.next_u64()
+    }
+
+    fn fill_bytes(&mut self, dest: &mut [u8]) {
+        self.rng.fill_bytes(dest)
+    }
+
+    fn try_fill_bytes(&mut self, dest: &mut [u8]) -> Result<(), rand::Error> {
+        self.rng.try_fill_bytes(dest)
+    }
+}
+
+// runs first on a reset so everything spawned for the new round already rolls from the new seed
+pub fn reseed_rng(mut game_rng: ResMut<GameRng>, mut event_reader: EventReader<ResetGameEvent>) {
+    if event_reader.read().last().is_none() {
+        return;
+    }
+    game_rng.reseed();
+    info!("round started with seed {}", game_rng.seed);
+}
@@ -0,0 +1,325 @@
+use bevy::{
+    ecs::event::ManualEventReader,
+    prelude::*,
+    render::view::{InheritedVisibility, ViewVisibility},
+    ui::Node,
+    window::Window,
+};
+use std::any::{Any, TypeId};
+
+use crate::arena::{ArenaConfig, SpawnPoints};
+use crate::arena_gen::{FreeCells, GeneratedWall};
+use crate::audio::SoundEvent;
+use crate::game_objects::{
+    Bullet, Destructible, Explosion, Hazard, KinematicWall, MovingWall, Player, PowerUp,
+    RotatingWall, Teleported, Totem, Wall, WallCracks,
+};
+use crate::game_utils::{
+    AnimationTimer, Collider, Direction, HitCooldownTimer, InvulnerableBlinkTimer, Name,
+    PlayerDamageEvent, PlayerDeadEvent, PlayerHitEvent, PlayerPowerUpEvent, PlayerShootEvent,
+    PowerUpPickedEvent, ResetGameEvent, RoundOverEvent, UpdateUIEvent, WallDamageEvent,
+    WallRebuildTimer,
+};
+use crate::ghost::{Ghost, TargetMark};
+use crate::killcam::KillCamOverlay;
+use crate::modes::{CaptureTotem, HillZone, ModeScores, PendingRespawn};
+use crate::profiles::StatsTracker;
+use crate::results::MatchLog;
+use crate::rng::GameRng;
+use crate::round::{ClosingWall, RoundState};
+use crate::round_stats::RoundStats;
+
+type Stored = Box<dyn Any + Send + Sync>;
+
+// what a snapshot holds and how to put it back, every component of an entity that can be
+// spawned during a round has to be listed or it is gone after a rollback respawned it
+#[derive(Resource, Default)]
+pub struct RollbackRegistry {
+    components: Vec<RollbackComponent>,
+    resources: Vec<RollbackResource>,
+    init: Vec<fn(&mut World)>,
+    // carried events are put aside at the end of a tick and sent again at the start of the next
+    put_aside: Vec<fn(&mut World)>,
+    send_again: Vec<fn(&mut World)>,
+    clear_events: Vec<fn(&mut World)>,
+}
+
+struct RollbackComponent {
+    type_id: TypeId,
+    save: fn(&World, Entity) -> Option<Stored>,
+    load: fn(&mut World, Entity, &Stored),
+}
+
+struct RollbackResource {
+    save: fn(&World) -> Option<Stored>,
+    load: fn(&mut World, &Stored),
+}
+
+// the gameplay at the start of one tick
+pub struct WorldSnapshot {
+    entities: Vec<(Entity, Vec<Option<Stored>>)>,
+    resources: Vec<Option<Stored>>,
+}
+
+// events a system sends after their readers already ran this tick, they are handed over to the
+// next tick explicitly so a rollback does not have to know which reader saw what
+#[derive(Resource)]
+pub struct CarriedEvents<T: Event> {
+    events: Vec<T>,
+    reader: ManualEventReader<T>,
+}
+
+impl<T: Event> Default for CarriedEvents<T> {
+    fn default() -> Self {
+        CarriedEvents {
+            events: Vec::new(),
+            reader: ManualEventReader::default(),
+        }
+    }
+}
+
+impl RollbackRegistry {
+    pub fn gameplay() -> RollbackRegistry {
+        RollbackRegistry::default()
+            .component::<Transform>()
+            .component::<GlobalTransform>()
+            .component::<Visibility>()
+            .component::<InheritedVisibility>()
+            .component::<ViewVisibility>()
+            .component::<Sprite>()
+            .component::<Handle<Image>>()
+            .component::<TextureAtlasSprite>()
+            .component::<Handle<TextureAtlas>>()
+            .component::<Player>()
+            .component::<Bullet>()
+            .component::<Explosion>()
+            .component::<PowerUp>()
+            .component::<Totem>()
+            .component::<Wall>()
+            .component::<GeneratedWall>()
+            .component::<Destructible>()
+            .component::<WallCracks>()
+            .component::<Hazard>()
+            .component::<MovingWall>()
+            .component::<RotatingWall>()
+            .component::<KinematicWall>()
+            .component::<Teleported>()
+            .component::<ClosingWall>()
+            .component::<Direction>()
+            .component::<Name>()
+            .component::<Collider>()
+            .component::<HitCooldownTimer>()
+            .component::<AnimationTimer>()
+            .component::<WallRebuildTimer>()
+            .component::<InvulnerableBlinkTimer>()
+            .component::<Ghost>()
+            .component::<TargetMark>()
+            .component::<HillZone>()
+            .component::<CaptureTotem>()
+            .component::<PendingRespawn>()
+            .resource::<RoundState>()
+            .resource::<RoundStats>()
+            .resource::<MatchLog>()
+            .resource::<StatsTracker>()
+            .resource::<ModeScores>()
+            .resource::<GameRng>()
+            .resource::<ArenaConfig>()
+            .resource::<SpawnPoints>()
+            .resource::<FreeCells>()
+            .resource::<Time>()
+            .resource::<Time<Virtual>>()
+            .resource::<Time<Fixed>>()
+            .event::<ResetGameEvent>()
+            .event::<RoundOverEvent>()
+            .event::<PlayerPowerUpEvent>()
+            .event::<PlayerShootEvent>()
+            .event::<PlayerDamageEvent>()
+            .event::<PowerUpPickedEvent>()
+            .event::<WallDamageEvent>()
+            .event::<SoundEvent>()
+            .carried_event::<UpdateUIEvent>()
+            .carried_event::<PlayerDeadEvent>()
+            .carried_event::<PlayerHitEvent>()
+    }
+
+    pub fn component<T: Component + Clone>(mut self) -> Self {
+        self.components.push(RollbackComponent {
+            type_id: TypeId::of::<T>(),
+            save: |world, entity| {
+                let component = world.get::<T>(entity)?;
+                Some(Box::new(component.clone()))
+            },
+            load: |world, entity, stored| {
+                if let Some(component) = stored.downcast_ref::<T>() {
+                    world.entity_mut(entity).insert(component.clone());
+                }
+            },
+        });
+        self
+    }
+
+    pub fn resource<T: Resource + Clone>(mut self) -> Self {
+        self.resources.push(RollbackResource {
+            save: |world| {
+                let resource = world.get_resource::<T>()?;
+                Some(Box::new(resource.clone()))
+            },
+            load: |world, stored| {
+                if let Some(resource) = stored.downcast_ref::<T>() {
+                    world.insert_resource(resource.clone());
+                }
+            },
+        });
+        self
+    }
+
+    // events that are only ever read within the tick they were sent in
+    pub fn event<T: Event>(mut self) -> Self {
+        self.clear_events.push(|world| {
+            if let Some(mut events) = world.get_resource_mut::<Events<T>>() {
+                events.clear();
+            }
+        });
+        self
+    }
+
+    pub fn carried_event<T: Event + Clone>(mut self) -> Self {
+        self = self.event::<T>();
+        self.init.push(|world| {
+            world.init_resource::<CarriedEvents<T>>();
+        });
+        self.resources.push(RollbackResource {
+            save: |world| {
+                let carried = world.get_resource::<CarriedEvents<T>>()?;
+                Some(Box::new(carried.events.clone()))
+            },
+            load: |world, stored| {
+                if let Some(events) = stored.downcast_ref::<Vec<T>>() {
+                    world.resource_mut::<CarriedEvents<T>>().events = events.clone();
+                }
+            },
+        });
+        self.put_aside.push(|world| {
+            world.resource_scope(|world, mut carried: Mut<CarriedEvents<T>>| {
+                let Some(events) = world.get_resource::<Events<T>>() else {
+                    return;
+                };
+                let carried = &mut *carried;
+                carried.events = carried.reader.read(events).cloned().collect();
+            });
+        });
+        self.send_again.push(|world| {
+            world.resource_scope(|world, mut carried: Mut<CarriedEvents<T>>| {
+                let Some(mut events) = world.get_resource_mut::<Events<T>>() else {
+                    return;
+                };
+                events.extend(std::mem::take(&mut carried.events));
+                // handed over already, these must not be carried along again
+                carried.reader.read(&events).count();
+            });
+        });
+        self
+    }
+
+    pub fn init(&self, world: &mut World) {
+        for init in &self.init {
+            init(world);
+        }
+    }
+
+    pub fn start_tick(&self, world: &mut World) {
+        for send_again in &self.send_again {
+            send_again(world);
+        }
+    }
+
+    pub fn end_tick(&self, world: &mut World) {
+        for put_aside in &self.put_aside {
+            put_aside(world);
+        }
+        for clear_events in &self.clear_events {
+            clear_events(world);
+        }
+    }
+
+    // everything in the arena, the camera, the hud and the kill-cam are left alone
+    pub fn tracked(&self, world: &World) -> Vec<Entity> {
+        let ignored = [
+            TypeId::of::<Camera>(),
+            TypeId::of::<Node>(),
+            TypeId::of::<Window>(),
+            TypeId::of::<KillCamOverlay>(),
+        ];
+        world
+            .iter_entities()
+            .filter(|entity| {
+                !ignored.iter().any(|id| entity.contains_type_id(*id))
+                    && self
+                        .components
+                        .iter()
+                        .any(|component| entity.contains_type_id(component.type_id))
+            })
+            .map(|entity| entity.id())
+            .collect()
+    }
+
+    // the type ids of every registered component, to tell which ones a snapshot misses
+    pub fn component_ids(&self) -> Vec<TypeId> {
+        self.components
+            .iter()
+            .map(|component| component.type_id)
+            .collect()
+    }
+
+    pub fn save(&self, world: &World) -> WorldSnapshot {
+        let entities = self
+            .tracked(world)
+            .into_iter()
+            .map(|entity| {
+                let components = self
+                    .components
+                    .iter()
+                    .map(|component| (component.save)(world, entity))
+                    .collect();
+                (entity, components)
+            })
+            .collect();
+        let resources = self
+            .resources
+            .iter()
+            .map(|resource| (resource.save)(world))
+            .collect();
+        WorldSnapshot {
+            entities,
+            resources,
+        }
+    }
+
+    // entities keep their ids, the ones despawned since come back under the same id so
+    // components pointing at them stay valid
+    pub fn load(&self, world: &mut World, snapshot: &WorldSnapshot) {
+        for entity in self.tracked(world) {
+            world.despawn(entity);
+        }
+        for (entity, components) in &snapshot.entities {
+            if world.get_or_spawn(*entity).is_none() {
+                warn!("could not bring back {:?} on a rollback", entity);
+                continue;
+            }
+            for (component, stored) in self.components.iter().zip(components) {
+                if let Some(stored) = stored {
+                    (component.load)(world, *entity, stored);
+                }
+            }
+        }
+        for (resource, stored) in self.resources.iter().zip(&snapshot.resources) {
+            if let Some(stored) = stored {
+                (resource.load)(world, stored);
+            }
+        }
+        // whatever the dropped ticks sent is gone with them
+        for clear_events in &self.clear_events {
+            clear_events(world);
+        }
+    }
+}
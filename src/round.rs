@@ -0,0 +1,358 @@
+use bevy::{prelude::*, utils::Duration};
+
+use crate::client::connected;
+use crate::combat::spawn_totem;
+use crate::constants::{WALL_BOTTOM, WALL_LEFT, WALL_RIGHT, WALL_THICKNESS, WALL_TOP};
+use crate::game_objects::{KinematicWall, MovingWall, Player};
+use crate::game_utils::{
+    Collider, GameState, PlayerDeadEvent, PlayerHitEvent, PlayerPowerUpEvent, ResetGameEvent,
+    RoundOverEvent, UpdateUIEvent,
+};
+use crate::ghost::{clear_ghosts, follow_marks, ghost_interactions, move_ghosts, spawn_ghosts};
+use crate::modes::{
+    capture_the_totem, capture_the_totem_selected, deathmatch_deaths, deathmatch_selected,
+    king_of_the_hill, king_of_the_hill_selected, reset_modes, respawn_players, ModeScores,
+};
+use crate::netcode::netplay;
+use crate::rng::{reseed_rng, GameRng};
+use crate::rules::{GameMode, MatchRules, SuddenDeath};
+use crate::settings::{
+    despawn_settings_screen, settings_clicked, spawn_settings_screen, update_settings_text,
+};
+use crate::StapidSet;
+
+// what is left of the arena once the closing walls stopped
+const SHRUNK_WIDTH: f32 = 600.0;
+const SHRUNK_HEIGHT: f32 = 400.0;
+const SHRINK_SECS: f32 = 30.0;
+
+// the match flow: settings screen, game state, round timer, modes and ghosts, it also puts
+// the sets the other plugins use in order
+pub struct RoundPlugin;
+
+#[derive(Resource, Clone, Default)]
+pub struct RoundState {
+    pub timer: Option<Timer>,
+    pub sudden_death: bool,
+    pub over: bool,
+    pub winner: Option<i32>,
+    // player number and lifes of everyone still standing, taken while at least two were
+    pub standings: Vec<(i32, i32)>,
+    // one life sudden death leaves everybody on a single life, what they had before still
+    // breaks the tie when the last ones go down together
+    pub lifes_at_sudden_death: Vec<(i32, i32)>,
+}
+
+#[derive(Component, Clone)]
+pub struct ClosingWall;
+
+#[derive(Component)]
+pub struct RoundText;
+
+impl Plugin for RoundPlugin {
+    fn build(&self, app: &mut App) {
+        app.insert_resource(MatchRules::from_args())
+            .insert_resource(GameRng::from_args())
+            .init_resource::<RoundState>()
+            .init_resource::<ModeScores>()
+            .add_state::<GameState>()
+            .add_event::<ResetGameEvent>()
+            .add_event::<RoundOverEvent>()
+            .add_event::<UpdateUIEvent>()
+            .add_event::<PlayerDeadEvent>()
+            .add_event::<PlayerHitEvent>()
+            .add_event::<PlayerPowerUpEvent>()
+            .configure_sets(
+                FixedUpdate,
+                (
+                    StapidSet::MoveWalls,
+                    StapidSet::Collision,
+                    StapidSet::WallDamage,
+                    StapidSet::MoveBullets,
+                )
+                    .chain()
+                    .run_if(in_state(GameState::Playing)),
+            )
+            .configure_sets(
+                Update,
+                (
+                    StapidSet::Reset,
+                    StapidSet::Spawn,
+                    StapidSet::Input,
+                    StapidSet::Effects,
+                    StapidSet::Hud,
+                    StapidSet::Round,
+                    StapidSet::KillCam,
+                )
+                    .chain()
+                    .run_if(in_state(GameState::Playing)),
+            )
+            // pre-match settings
+            .add_systems(OnEnter(GameState::Settings), spawn_settings_screen)
+            .add_systems(
+                Update,
+                (
+                    settings_clicked.run_if(not(netplay)).run_if(not(connected)),
+                    update_settings_text,
+                )
+                    .chain()
+                    .run_if(in_state(GameState::Settings)),
+            )
+            .add_systems(OnExit(GameState::Settings), despawn_settings_screen)
+            .add_systems(OnEnter(GameState::Playing), start_match)
+            .add_systems(Update, (reseed_rng, clear_ghosts).in_set(StapidSet::Reset))
+            .add_systems(
+                Update,
+                spawn_ghosts
+                    .before(spawn_totem)
+                    .run_if(not(deathmatch_selected))
+                    .in_set(StapidSet::Spawn),
+            )
+            .add_systems(
+                Update,
+                (move_ghosts, ghost_interactions, follow_marks)
+                    .chain()
+                    .in_set(StapidSet::Input),
+            )
+            .add_systems(
+                Update,
+                (
+                    reset_round,
+                    reset_modes,
+                    tick_round,
+                    king_of_the_hill.run_if(king_of_the_hill_selected),
+                    capture_the_totem.run_if(capture_the_totem_selected),
+                    (deathmatch_deaths, respawn_players).run_if(deathmatch_selected),
+                    check_round_over.run_if(not(deathmatch_selected)),
+                )
+                    .chain()
+                    .in_set(StapidSet::Round),
+            );
+    }
+}
+
+impl RoundState {
+    pub fn remaining_secs(&self) -> Option<f32> {
+        self.timer.as_ref().map(|timer| timer.remaining_secs())
+    }
+
+    pub fn finish(&mut self, winner: Option<i32>) -> RoundOverEvent {
+        self.over = true;
+        self.winner = winner;
+        RoundOverEvent { winner }
+    }
+}
+
+pub fn start_match(
+    mut event_writer: EventWriter<ResetGameEvent>,
+    mut event_writer_powerup: EventWriter<PlayerPowerUpEvent>,
+    rules: Res<MatchRules>,
+) {
+    event_writer.send_default();
+    for _ in 0..rules.starting_powerups {
+        event_writer_powerup.send_default();
+    }
+}
+
+pub fn reset_round(
+    mut commands: Commands,
+    mut round: ResMut<RoundState>,
+    closing_walls: Query<Entity, With<ClosingWall>>,
+    rules: Res<MatchRules>,
+    mut event_reader: EventReader<ResetGameEvent>,
+) {
+    if event_reader.read().last().is_none() {
+        return;
+    }
+    for entity in &closing_walls {
+        commands.entity(entity).despawn();
+    }
+    *round = RoundState {
+        timer: match rules.round_secs {
+            0 => None,
+            secs => Some(Timer::new(
+                Duration::from_secs(secs as u64),
+                TimerMode::Once,
+            )),
+        },
+        ..default()
+    };
+}
+
+#[allow(clippy::too_many_arguments)]
+pub fn tick_round(
+    mut commands: Commands,
+    mut round: ResMut<RoundState>,
+    mut players: Query<&mut Player>,
+    mut event_writer: EventWriter<UpdateUIEvent>,
+    mut event_writer_round: EventWriter<RoundOverEvent>,
+    scores: Res<ModeScores>,
+    rules: Res<MatchRules>,
+    time: Res<Time>,
+) {
+    if round.over || round.sudden_death {
+        return;
+    }
+    let Some(timer) = round.timer.as_mut() else {
+        return;
+    };
+    if !timer.tick(time.delta()).just_finished() {
+        return;
+    }
+    // nobody stays dead in deathmatch, the kill count decides once the time is up
+    if rules.mode == GameMode::Deathmatch {
+        event_writer_round.send(round.finish(scores.leader()));
+        return;
+    }
+    round.sudden_death = true;
+    match rules.sudden_death {
+        SuddenDeath::ShrinkingArena => spawn_closing_walls(&mut commands),
+        SuddenDeath::OneLife => {
+            round.lifes_at_sudden_death = players
+                .iter()
+                .filter(|player| player.lifes > 0)
+                .map(|player| (player.player_number, player.lifes))
+                .collect();
+            for mut player in &mut players {
+                if player.lifes > 1 {
+                    player.lifes = 1;
+                    event_writer.send(UpdateUIEvent {
+                        player_number: player.player_number as usize,
+                    });
+                }
+            }
+        }
+    }
+}
+
+// the round is over once one player is left, if the last ones go down in the same tick
+// the one who had the most lifes left before that, or before one life sudden death, wins
+pub fn check_round_over(
+    mut round: ResMut<RoundState>,
+    players: Query<&Player>,
+    mut event_writer: EventWriter<RoundOverEvent>,
+) {
+    if round.over {
+        return;
+    }
+    let alive: Vec<(i32, i32)> = players
+        .iter()
+        .filter(|player| player.lifes > 0)
+        .map(|player| (player.player_number, player.lifes))
+        .collect();
+    if alive.len() >= 2 {
+        round.standings = alive;
+        return;
+    }
+    // nobody was standing together yet, players are still being spawned
+    if round.standings.is_empty() {
+        return;
+    }
+    let winner = match alive.first() {
+        Some((player_number, _)) => Some(*player_number),
+        None => {
+            let standings: Vec<(i32, i32)> = round
+                .standings
+                .iter()
+                .map(|(player_number, lifes)| {
+                    let before = round
+                        .lifes_at_sudden_death
+                        .iter()
+                        .find(|(number, _)| number == player_number);
+                    (*player_number, before.map_or(*lifes, |(_, lifes)| *lifes))
+                })
+                .collect();
+            let most_lifes = standings.iter().map(|(_, lifes)| *lifes).max();
+            let leaders: Vec<i32> = standings
+                .iter()
+                .filter(|(_, lifes)| Some(*lifes) == most_lifes)
+                .map(|(player_number, _)| *player_number)
+                .collect();
+            match leaders[..] {
+                [player_number] => Some(player_number),
+                _ => None,
+            }
+        }
+    };
+    event_writer.send(round.finish(winner));
+}
+
+pub fn update_round_text(
+    mut text_query: Query<&mut Text, With<RoundText>>,
+    round: Res<RoundState>,
+) {
+    let value = if round.over {
+        match round.winner {
+            Some(player_number) => format!("Player {}\nwins!", player_number),
+            None => String::from("Draw!"),
+        }
+    } else if round.sudden_death {
+        String::from("Sudden\ndeath!")
+    } else if let Some(remaining) = round.remaining_secs() {
+        let secs = remaining.ceil() as u32;
+        format!("Time:\n{}:{:02}", secs / 60, secs % 60)
+    } else {
+        String::new()
+    };
+    for mut text in &mut text_query {
+        if text.sections[0].value != value {
+            text.sections[0].value = value.clone();
+        }
+    }
+}
+
+// four slabs start just behind the outer walls and slide inward, anyone caught between
+// a slab and another wall gets crushed by the kinematic wall handling
+fn spawn_closing_walls(commands: &mut Commands) {
+    let width = WALL_RIGHT - WALL_LEFT + WALL_THICKNESS;
+    let height = WALL_BOTTOM - WALL_TOP + WALL_THICKNESS;
+    let travel_x = (WALL_RIGHT - WALL_LEFT - SHRUNK_WIDTH) / 2.0;
+    let travel_y = (WALL_BOTTOM - WALL_TOP - SHRUNK_HEIGHT) / 2.0;
+    let slabs = [
+        (
+            Vec2::new(WALL_LEFT - travel_x / 2.0, 0.0),
+            Vec2::new(travel_x, 0.0),
+            Vec2::new(travel_x, height),
+        ),
+        (
+            Vec2::new(WALL_RIGHT + travel_x / 2.0, 0.0),
+            Vec2::new(-travel_x, 0.0),
+            Vec2::new(travel_x, height),
+        ),
+        (
+            Vec2::new(0.0, WALL_TOP - travel_y / 2.0),
+            Vec2::new(0.0, travel_y),
+            Vec2::new(width, travel_y),
+        ),
+        (
+            Vec2::new(0.0, WALL_BOTTOM + travel_y / 2.0),
+            Vec2::new(0.0, -travel_y),
+            Vec2::new(width, travel_y),
+        ),
+    ];
+    for (start, travel, size) in slabs {
+        commands.spawn((
+            SpriteBundle {
+                sprite: Sprite {
+                    color: Color::rgb(0.2, 0.2, 0.25),
+                    custom_size: Option::Some(Vec2 { x: 1.0, y: 1.0 }),
+                    ..default()
+                },
+                transform: Transform {
+                    translation: start.extend(2.5),
+                    scale: size.extend(0.0),
+                    ..default()
+                },
+                ..default()
+            },
+            MovingWall {
+                waypoints: vec![start + travel],
+                speed: travel.length() / SHRINK_SECS,
+                next: 0,
+            },
+            KinematicWall::default(),
+            Collider,
+            ClosingWall,
+        ));
+    }
+}
@@ -0,0 +1,292 @@
+use bevy::prelude::*;
+use serde::Serialize;
+use std::{collections::BTreeMap, path::PathBuf, time::SystemTime};
+
+use crate::client::connected;
+use crate::game_objects::Player;
+use crate::game_utils::{
+    BulletType, PlayerDamageEvent, PlayerShootEvent, PowerUpPickedEvent, ResetGameEvent,
+};
+use crate::killcam::kill_cam_running;
+use crate::results;
+use crate::round::RoundState;
+use crate::StapidSet;
+
+// nothing in the key sets of the four players uses the function keys
+const EXPORT_KEY: KeyCode = KeyCode::F2;
+const BULLETS: [BulletType; 4] = [
+    BulletType::NormalBullet,
+    BulletType::IceBullet,
+    BulletType::ExplosiveBullet,
+    BulletType::BouncyBullet,
+];
+
+// what everyone did during the round, shown as a table once it is over
+pub struct RoundStatsPlugin;
+
+#[derive(Clone, Default, PartialEq, Debug, Serialize)]
+pub struct PlayerRoundStats {
+    // by BulletType::name
+    pub shots: BTreeMap<&'static str, u32>,
+    // bullets and explosions that reached somebody else
+    pub hits: u32,
+    pub damage_dealt: i32,
+    pub damage_taken: i32,
+    pub stuns_given: u32,
+    // own grenades and bouncy balls, the lifes they took count as taken
+    pub self_hits: u32,
+    pub powerups: u32,
+    pub alive_secs: f32,
+}
+
+#[derive(Resource, Clone, Default, Serialize)]
+pub struct RoundStats {
+    pub players: BTreeMap<i32, PlayerRoundStats>,
+}
+
+// what F2 saves, the players keyed by their number
+#[derive(Serialize)]
+struct RoundStatsFile<'a> {
+    winner: Option<i32>,
+    #[serde(flatten)]
+    stats: &'a RoundStats,
+}
+
+#[derive(Component)]
+pub struct RoundStatsPanel;
+
+impl Plugin for RoundStatsPlugin {
+    fn build(&self, app: &mut App) {
+        app.init_resource::<RoundStats>()
+            .add_event::<ResetGameEvent>()
+            .add_event::<PlayerShootEvent>()
+            .add_event::<PlayerDamageEvent>()
+            .add_event::<PowerUpPickedEvent>()
+            .add_systems(
+                Update,
+                clear_round_stats
+                    .run_if(on_event::<ResetGameEvent>())
+                    .in_set(StapidSet::Reset),
+            )
+            // a client only sees snapshots, none of the events happen on its side
+            .add_systems(
+                Update,
+                track_round_stats
+                    .run_if(not(connected))
+                    .in_set(StapidSet::Effects),
+            )
+            .add_systems(
+                Update,
+                (show_round_stats, export_round_stats)
+                    .run_if(not(kill_cam_running))
+                    .run_if(not(connected))
+                    .in_set(StapidSet::Hud),
+            );
+    }
+}
+
+impl PlayerRoundStats {
+    pub fn shots_fired(&self) -> u32 {
+        self.shots.values().sum()
+    }
+
+    // a grenade can hit more than one player, so it stops at 100
+    pub fn accuracy(&self) -> f32 {
+        match self.shots_fired() {
+            0 => 0.0,
+            shots => (self.hits as f32 * 100.0 / shots as f32).min(100.0),
+        }
+    }
+}
+
+impl RoundStats {
+    pub fn get(&self, player_number: i32) -> PlayerRoundStats {
+        self.players
+            .get(&player_number)
+            .cloned()
+            .unwrap_or_default()
+    }
+
+    pub fn table(&self) -> String {
+        let mut rows = vec![format!(
+            "{:<10}{:>8}{:>5}{:>9}{:>8}{:>6}{:>6}{:>7}{:>7}{:>6}{:>6}{:>10}{:>7}",
+            "Player",
+            "Normal",
+            "Ice",
+            "Grenade",
+            "Bouncy",
+            "Hits",
+            "Acc",
+            "Dealt",
+            "Taken",
+            "Stun",
+            "Self",
+            "Power-ups",
+            "Alive"
+        )];
+        for (player_number, stats) in &self.players {
+            let shots = |bullet: &BulletType| stats.shots.get(bullet.name()).copied().unwrap_or(0);
+            rows.push(format!(
+                "{:<10}{:>8}{:>5}{:>9}{:>8}{:>6}{:>5.0}%{:>7}{:>7}{:>6}{:>6}{:>10}{:>6.1}s",
+                format!("Player {}", player_number),
+                shots(&BULLETS[0]),
+                shots(&BULLETS[1]),
+                shots(&BULLETS[2]),
+                shots(&BULLETS[3]),
+                stats.hits,
+                stats.accuracy(),
+                stats.damage_dealt,
+                stats.damage_taken,
+                stats.stuns_given,
+                stats.self_hits,
+                stats.powerups,
+                stats.alive_secs
+            ));
+        }
+        rows.join("\n")
+    }
+
+    pub fn to_json(&self, winner: Option<i32>) -> String {
+        results::to_json(&RoundStatsFile {
+            winner,
+            stats: self,
+        })
+    }
+}
+
+pub fn clear_round_stats(mut stats: ResMut<RoundStats>) {
+    *stats = RoundStats::default();
+}
+
+// whatever happens after the round is decided is not part of it
+pub fn track_round_stats(
+    mut stats: ResMut<RoundStats>,
+    mut shots: EventReader<PlayerShootEvent>,
+    mut damage: EventReader<PlayerDamageEvent>,
+    mut picked: EventReader<PowerUpPickedEvent>,
+    players: Query<&Player>,
+    round: Res<RoundState>,
+    time: Res<Time>,
+) {
+    if round.over {
+        shots.clear();
+        damage.clear();
+        picked.clear();
+        return;
+    }
+    let players_stats = &mut stats.players;
+    for shot in shots.read() {
+        *players_stats
+            .entry(shot.player_number)
+            .or_default()
+            .shots
+            .entry(shot.bullet_type.name())
+            .or_insert(0) += 1;
+    }
+    for hit in damage.read() {
+        players_stats.entry(hit.victim).or_default().damage_taken += hit.damage;
+        let Some(attacker) = hit.attacker else {
+            continue;
+        };
+        let attacker_stats = players_stats.entry(attacker).or_default();
+        if attacker == hit.victim {
+            attacker_stats.self_hits += 1;
+            continue;
+        }
+        attacker_stats.hits += 1;
+        attacker_stats.damage_dealt += hit.damage;
+        if hit.stunned {
+            attacker_stats.stuns_given += 1;
+        }
+    }
+    for pickup in picked.read() {
+        players_stats
+            .entry(pickup.player_number)
+            .or_default()
+            .powerups += 1;
+    }
+    for player in &players {
+        let player_stats = players_stats.entry(player.player_number).or_default();
+        if player.lifes > 0 {
+            player_stats.alive_secs += time.delta_seconds();
+        }
+    }
+}
+
+pub fn show_round_stats(
+    mut commands: Commands,
+    asset_server: Res<AssetServer>,
+    stats: Res<RoundStats>,
+    round: Res<RoundState>,
+    panels: Query<Entity, With<RoundStatsPanel>>,
+) {
+    if !round.over {
+        for entity in &panels {
+            commands.entity(entity).despawn_recursive();
+        }
+        return;
+    }
+    if !panels.is_empty() {
+        return;
+    }
+    let text_style = |font_size: f32| TextStyle {
+        font: asset_server.load("/assets/fonts/PixeloidSans.ttf"),
+        font_size,
+        color: Color::WHITE,
+    };
+    // leaves the side panel with the reset button uncovered
+    commands
+        .spawn((
+            NodeBundle {
+                style: Style {
+                    position_type: PositionType::Absolute,
+                    left: Val::Px(200.0),
+                    right: Val::Px(40.0),
+                    top: Val::Percent(30.0),
+                    flex_direction: FlexDirection::Column,
+                    align_items: AlignItems::Center,
+                    padding: UiRect::all(Val::Px(16.0)),
+                    ..default()
+                },
+                background_color: Color::rgba(0.0, 0.0, 0.0, 0.85).into(),
+                z_index: ZIndex::Global(5),
+                ..default()
+            },
+            RoundStatsPanel,
+        ))
+        .with_children(|parent| {
+            parent.spawn(TextBundle::from_section("Round stats", text_style(30.0)));
+            parent.spawn(
+                TextBundle::from_section(stats.table(), text_style(16.0)).with_style(Style {
+                    margin: UiRect::all(Val::Px(12.0)),
+                    ..default()
+                }),
+            );
+            parent.spawn(TextBundle::from_section(
+                "F2 saves this table as json",
+                text_style(16.0),
+            ));
+        });
+}
+
+pub fn export_round_stats(
+    keys: Res<Input<KeyCode>>,
+    stats: Res<RoundStats>,
+    round: Res<RoundState>,
+) {
+    if !round.over || !keys.just_pressed(EXPORT_KEY) {
+        return;
+    }
+    let secs = SystemTime::now()
+        .duration_since(SystemTime::UNIX_EPOCH)
+        .map_or(0, |since| since.as_secs());
+    let path = PathBuf::from(format!("stapid_round_{}.json", secs));
+    match std::fs::write(&path, stats.to_json(round.winner)) {
+        Ok(()) => info!("round stats saved to {}", path.display()),
+        Err(error) => warn!(
+            "could not save round stats to {}: {}",
+            path.display(),
+            error
+        ),
+    }
+}
@@ -0,0 +1,200 @@
+use bevy::{prelude::*, utils::Duration};
+use serde::{Deserialize, Serialize};
+use std::path::PathBuf;
+
+const RULES_FILE: &str = "stapid_rules.ron";
+
+#[derive(Clone, Copy, PartialEq)]
+pub enum RulesPreset {
+    Classic,
+    OneShot,
+    PowerUpFrenzy,
+}
+
+#[derive(Clone, Copy, PartialEq, Serialize, Deserialize)]
+pub enum SuddenDeath {
+    ShrinkingArena,
+    OneLife,
+}
+
+#[derive(Clone, Copy, PartialEq, Serialize, Deserialize)]
+pub enum GameMode {
+    Elimination,
+    KingOfTheHill,
+    CaptureTheTotem,
+    Deathmatch,
+}
+
+// fields missing from an older rules file fall back to the classic values
+#[derive(Resource, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct MatchRules {
+    pub lifes: i32,
+    pub player_speed: f32,
+    pub invulnerability_ms: u64,
+    pub stun_ms: u64,
+    pub shot_cooldown_ms: u64,
+    pub starting_powerups: u32,
+    // 0 means the round only ends once a single player is left
+    pub round_secs: u32,
+    pub sudden_death: SuddenDeath,
+    pub mode: GameMode,
+}
+
+impl RulesPreset {
+    pub const ALL: [RulesPreset; 3] = [
+        RulesPreset::Classic,
+        RulesPreset::OneShot,
+        RulesPreset::PowerUpFrenzy,
+    ];
+
+    pub fn name(&self) -> &'static str {
+        match self {
+            RulesPreset::Classic => "Classic",
+            RulesPreset::OneShot => "One-shot",
+            RulesPreset::PowerUpFrenzy => "Power-up frenzy",
+        }
+    }
+
+    pub fn rules(&self) -> MatchRules {
+        let classic = MatchRules {
+            lifes: 3,
+            player_speed: 2.5,
+            invulnerability_ms: 2000,
+            stun_ms: 2000,
+            shot_cooldown_ms: 200,
+            starting_powerups: 2,
+            round_secs: 0,
+            sudden_death: SuddenDeath::ShrinkingArena,
+            mode: GameMode::Elimination,
+        };
+        match self {
+            RulesPreset::Classic => classic,
+            RulesPreset::OneShot => MatchRules {
+                lifes: 1,
+                shot_cooldown_ms: 400,
+                starting_powerups: 0,
+                round_secs: 90,
+                ..classic
+            },
+            RulesPreset::PowerUpFrenzy => MatchRules {
+                lifes: 5,
+                starting_powerups: 8,
+                round_secs: 180,
+                sudden_death: SuddenDeath::OneLife,
+                ..classic
+            },
+        }
+    }
+}
+
+impl SuddenDeath {
+    pub fn name(&self) -> &'static str {
+        match self {
+            SuddenDeath::ShrinkingArena => "Shrinking arena",
+            SuddenDeath::OneLife => "One life",
+        }
+    }
+}
+
+impl GameMode {
+    pub const ALL: [GameMode; 4] = [
+        GameMode::Elimination,
+        GameMode::KingOfTheHill,
+        GameMode::CaptureTheTotem,
+        GameMode::Deathmatch,
+    ];
+
+    pub fn name(&self) -> &'static str {
+        match self {
+            GameMode::Elimination => "Elimination",
+            GameMode::KingOfTheHill => "King of the Hill",
+            GameMode::CaptureTheTotem => "Capture the Totem",
+            GameMode::Deathmatch => "Deathmatch",
+        }
+    }
+
+    // score that ends the round, elimination only ends when one player is left
+    pub fn target_score(&self) -> Option<f32> {
+        match self {
+            GameMode::Elimination => None,
+            GameMode::KingOfTheHill => Some(30.0),
+            GameMode::CaptureTheTotem => Some(3.0),
+            GameMode::Deathmatch => Some(10.0),
+        }
+    }
+
+    pub fn hud_line(&self, score: f32) -> Option<String> {
+        match self {
+            GameMode::Elimination => None,
+            GameMode::KingOfTheHill => Some(format!("Hill: {}s", score.floor())),
+            GameMode::CaptureTheTotem => Some(format!("Captures: {}", score.floor())),
+            GameMode::Deathmatch => Some(format!("Kills: {}", score.floor())),
+        }
+    }
+}
+
+impl Default for MatchRules {
+    fn default() -> Self {
+        RulesPreset::Classic.rules()
+    }
+}
+
+impl MatchRules {
+    pub fn invulnerability(&self) -> Duration {
+        Duration::from_millis(self.invulnerability_ms)
+    }
+
+    pub fn stun(&self) -> Duration {
+        Duration::from_millis(self.stun_ms)
+    }
+
+    pub fn shot_cooldown(&self) -> Duration {
+        Duration::from_millis(self.shot_cooldown_ms)
+    }
+
+    // the preset these rules came from, None once something was changed by hand
+    pub fn preset(&self) -> Option<RulesPreset> {
+        RulesPreset::ALL
+            .into_iter()
+            .find(|preset| preset.rules() == *self)
+    }
+
+    // a missing file just means classic rules, a broken one is reported and ignored
+    pub fn from_args() -> MatchRules {
+        let path = rules_path();
+        let Ok(content) = std::fs::read_to_string(&path) else {
+            return MatchRules::default();
+        };
+        match ron::from_str(&content) {
+            Ok(rules) => rules,
+            Err(error) => {
+                warn!("ignoring match rules in {}: {}", path.display(), error);
+                MatchRules::default()
+            }
+        }
+    }
+
+    pub fn save(&self) {
+        let path = rules_path();
+        let result = ron::ser::to_string_pretty(self, ron::ser::PrettyConfig::default())
+            .map_err(|error| error.to_string())
+            .and_then(|content| std::fs::write(&path, content).map_err(|error| error.to_string()));
+        if let Err(error) = result {
+            warn!(
+                "could not save match rules to {}: {}",
+                path.display(),
+                error
+            );
+        }
+    }
+}
+
+fn rules_path() -> PathBuf {
+    let args: Vec<String> = std::env::args().collect();
+    args.iter()
+        .position(|arg| arg == "--rules")
+        .and_then(|index| args.get(index + 1))
+        .map(PathBuf::from)
+        .unwrap_or_else(|| PathBuf::from(RULES_FILE))
+}
@@ -0,0 +1,791 @@
+use bevy::{
+    input::InputSystem,
+    log::LogPlugin,
+    prelude::*,
+    utils::{Duration, Instant},
+};
+use std::{
+    collections::VecDeque,
+    io::ErrorKind,
+    net::{Ipv4Addr, SocketAddr, UdpSocket},
+};
+
+use crate::arena::ArenaConfig;
+use crate::game_objects::{Bullet, Destructible, Player, PowerUp, Totem};
+use crate::game_utils::{Bindings, BulletType, Direction, GameState, PlayerSlots, ResetGameEvent};
+use crate::headless::headless_app;
+use crate::netcode::NetSetup;
+use crate::profiles::Profiles;
+use crate::replay::{apply_input, TICK};
+use crate::round::RoundState;
+use crate::rules::MatchRules;
+
+pub const SERVER_PORT: u16 = 7000;
+// a client's input may run this far ahead of the ticks it has been connected for, more than
+// that is a client running its clock fast
+const MAX_AHEAD: u32 = 60;
+// inputs waiting for their tick, older ones are dropped beyond this to keep the delay down
+const MAX_QUEUED: usize = 8;
+const SILENT_AFTER: Duration = Duration::from_secs(5);
+const RESTART_SECS: f32 = 3.0;
+
+const JOIN: u8 = 10;
+const INPUT: u8 = 11;
+const LEAVE: u8 = 12;
+const ACCEPTED: u8 = 20;
+const FULL: u8 = 21;
+const SNAPSHOT: u8 = 22;
+
+// `stapid-server` runs the match without a window, clients only send their keys and draw
+// what the server tells them
+pub struct ServerConfig {
+    pub port: u16,
+    pub players: usize,
+    // stops after this many ticks, runs forever without
+    pub ticks: Option<u32>,
+    pub log: bool,
+}
+
+pub enum ClientMessage {
+    Join,
+    // the latest inputs that were not acknowledged yet, `first` is the sequence number of the
+    // first one
+    Input {
+        player: u8,
+        first: u32,
+        inputs: Vec<u16>,
+    },
+    Leave,
+}
+
+pub enum ServerMessage {
+    Accepted {
+        player: u8,
+        players: u8,
+        setup: NetSetup,
+    },
+    Full,
+    Snapshot(Snapshot),
+}
+
+// the state after one server tick as far as a client draws it
+#[derive(Clone, Default, PartialEq, Debug)]
+pub struct Snapshot {
+    pub tick: u32,
+    // the last input of the receiving client that went into this tick
+    pub ack: u32,
+    // counts the resets, a client resets its arena whenever this changes
+    pub round: u32,
+    pub arena_seed: u64,
+    pub over: bool,
+    pub winner: Option<i32>,
+    pub sudden_death: bool,
+    pub remaining_ms: Option<u32>,
+    pub players: Vec<PlayerState>,
+    pub objects: Vec<ObjectState>,
+    // indices of the destroyed walls, with the destructible walls ordered by position
+    pub broken: Vec<u16>,
+}
+
+#[derive(Clone, PartialEq, Debug)]
+pub struct PlayerState {
+    pub number: i32,
+    pub position: Vec2,
+    pub lifes: i32,
+    pub direction_x: u8,
+    pub direction_y: u8,
+    pub invulnerable: bool,
+    pub stunned: bool,
+    pub power: Option<u8>,
+}
+
+// everything else that moves or comes and goes, the id stays the same while it exists
+#[derive(Clone, PartialEq, Debug)]
+pub struct ObjectState {
+    pub id: u64,
+    pub kind: ObjectKind,
+    pub position: Vec2,
+    pub size: Vec2,
+}
+
+#[derive(Clone, Copy, PartialEq, Debug)]
+pub enum ObjectKind {
+    Bullet,
+    IceBullet,
+    Grenade,
+    BouncyBall,
+    PowerUp,
+    Totem,
+}
+
+#[derive(Resource)]
+pub struct Server {
+    pub players: usize,
+    pub tick: u32,
+    pub round: u32,
+    // input packets that were thrown away for coming from the wrong place or running ahead
+    pub rejected: u32,
+    socket: UdpSocket,
+    setup: NetSetup,
+    clients: Vec<Option<ServerClient>>,
+    restart: Option<Timer>,
+}
+
+struct ServerClient {
+    address: SocketAddr,
+    queue: VecDeque<(u32, u16)>,
+    // the highest sequence number received and the last one applied
+    received: u32,
+    applied: u32,
+    held: u16,
+    joined_tick: u32,
+    heard: Instant,
+}
+
+// appends little endian numbers to a packet
+struct Writer(Vec<u8>);
+
+struct Reader<'a> {
+    packet: &'a [u8],
+    at: usize,
+}
+
+impl Writer {
+    fn u8(&mut self, value: u8) {
+        self.0.push(value);
+    }
+
+    fn u16(&mut self, value: u16) {
+        self.0.extend_from_slice(&value.to_le_bytes());
+    }
+
+    fn u32(&mut self, value: u32) {
+        self.0.extend_from_slice(&value.to_le_bytes());
+    }
+
+    fn u64(&mut self, value: u64) {
+        self.0.extend_from_slice(&value.to_le_bytes());
+    }
+
+    fn vec2(&mut self, value: Vec2) {
+        self.0.extend_from_slice(&value.x.to_le_bytes());
+        self.0.extend_from_slice(&value.y.to_le_bytes());
+    }
+}
+
+impl<'a> Reader<'a> {
+    fn bytes<const N: usize>(&mut self) -> Option<[u8; N]> {
+        let bytes = self.packet.get(self.at..self.at + N)?.try_into().ok()?;
+        self.at += N;
+        Some(bytes)
+    }
+
+    fn u8(&mut self) -> Option<u8> {
+        Some(self.bytes::<1>()?[0])
+    }
+
+    fn u16(&mut self) -> Option<u16> {
+        Some(u16::from_le_bytes(self.bytes()?))
+    }
+
+    fn u32(&mut self) -> Option<u32> {
+        Some(u32::from_le_bytes(self.bytes()?))
+    }
+
+    fn u64(&mut self) -> Option<u64> {
+        Some(u64::from_le_bytes(self.bytes()?))
+    }
+
+    fn vec2(&mut self) -> Option<Vec2> {
+        let x = f32::from_le_bytes(self.bytes()?);
+        let y = f32::from_le_bytes(self.bytes()?);
+        Some(Vec2::new(x, y))
+    }
+
+    fn rest(&self) -> &'a [u8] {
+        &self.packet[self.at.min(self.packet.len())..]
+    }
+}
+
+impl ServerConfig {
+    // `--port <port>`, `--players <2-4>` and `--ticks <count>` to stop on its own
+    pub fn from_args() -> ServerConfig {
+        let args: Vec<String> = std::env::args().collect();
+        let value_of = |flag: &str| {
+            args.iter()
+                .position(|arg| arg == flag)
+                .and_then(|index| args.get(index + 1))
+        };
+        ServerConfig {
+            port: value_of("--port")
+                .and_then(|port| port.parse().ok())
+                .unwrap_or(SERVER_PORT),
+            players: value_of("--players")
+                .and_then(|players| players.parse().ok())
+                .unwrap_or(2usize)
+                .clamp(2, 4),
+            ticks: value_of("--ticks").and_then(|ticks| ticks.parse().ok()),
+            log: true,
+        }
+    }
+}
+
+impl ClientMessage {
+    pub fn encode(&self) -> Vec<u8> {
+        match self {
+            ClientMessage::Join => vec![JOIN],
+            ClientMessage::Input {
+                player,
+                first,
+                inputs,
+            } => {
+                let mut writer = Writer(vec![INPUT, *player]);
+                writer.u32(*first);
+                writer.u8(inputs.len() as u8);
+                for input in inputs {
+                    writer.u16(*input);
+                }
+                writer.0
+            }
+            ClientMessage::Leave => vec![LEAVE],
+        }
+    }
+
+    pub fn decode(packet: &[u8]) -> Option<ClientMessage> {
+        let mut reader = Reader { packet, at: 1 };
+        match *packet.first()? {
+            JOIN => Some(ClientMessage::Join),
+            INPUT => {
+                let player = reader.u8()?;
+                let first = reader.u32()?;
+                let count = reader.u8()?;
+                let inputs = (0..count)
+                    .map(|_| reader.u16())
+                    .collect::<Option<Vec<u16>>>()?;
+                Some(ClientMessage::Input {
+                    player,
+                    first,
+                    inputs,
+                })
+            }
+            LEAVE => Some(ClientMessage::Leave),
+            _ => None,
+        }
+    }
+}
+
+impl ServerMessage {
+    pub fn encode(&self) -> Vec<u8> {
+        match self {
+            ServerMessage::Accepted {
+                player,
+                players,
+                setup,
+            } => {
+                let mut packet = vec![ACCEPTED, *player, *players];
+                packet.extend_from_slice(ron::to_string(setup).unwrap_or_default().as_bytes());
+                packet
+            }
+            ServerMessage::Full => vec![FULL],
+            ServerMessage::Snapshot(snapshot) => snapshot.encode(),
+        }
+    }
+
+    pub fn decode(packet: &[u8]) -> Option<ServerMessage> {
+        let mut reader = Reader { packet, at: 1 };
+        match *packet.first()? {
+            ACCEPTED => {
+                let player = reader.u8()?;
+                let players = reader.u8()?;
+                let setup = ron::from_str(std::str::from_utf8(reader.rest()).ok()?).ok()?;
+                Some(ServerMessage::Accepted {
+                    player,
+                    players,
+                    setup,
+                })
+            }
+            FULL => Some(ServerMessage::Full),
+            SNAPSHOT => Some(ServerMessage::Snapshot(Snapshot::decode(&mut reader)?)),
+            _ => None,
+        }
+    }
+}
+
+impl Snapshot {
+    fn encode(&self) -> Vec<u8> {
+        let mut writer = Writer(vec![SNAPSHOT]);
+        writer.u32(self.tick);
+        writer.u32(self.ack);
+        writer.u32(self.round);
+        writer.u64(self.arena_seed);
+        writer.u8(self.over as u8 | (self.sudden_death as u8) << 1);
+        writer.u32(self.winner.map_or(u32::MAX, |winner| winner as u32));
+        writer.u32(self.remaining_ms.unwrap_or(u32::MAX));
+        writer.u8(self.players.len() as u8);
+        for player in &self.players {
+            writer.u8(player.number as u8);
+            writer.vec2(player.position);
+            writer.u8(player.lifes.clamp(0, 255) as u8);
+            writer.u8(player.direction_x);
+            writer.u8(player.direction_y);
+            writer.u8(player.invulnerable as u8 | (player.stunned as u8) << 1);
+            writer.u8(player.power.unwrap_or(u8::MAX));
+        }
+        writer.u16(self.objects.len() as u16);
+        for object in &self.objects {
+            writer.u64(object.id);
+            writer.u8(object.kind as u8);
+            writer.vec2(object.position);
+            writer.vec2(object.size);
+        }
+        writer.u16(self.broken.len() as u16);
+        for wall in &self.broken {
+            writer.u16(*wall);
+        }
+        writer.0
+    }
+
+    fn decode(reader: &mut Reader) -> Option<Snapshot> {
+        let tick = reader.u32()?;
+        let ack = reader.u32()?;
+        let round = reader.u32()?;
+        let arena_seed = reader.u64()?;
+        let flags = reader.u8()?;
+        let winner = Some(reader.u32()?).filter(|winner| *winner != u32::MAX);
+        let remaining_ms = Some(reader.u32()?).filter(|remaining| *remaining != u32::MAX);
+        let players = (0..reader.u8()?)
+            .map(|_| {
+                let number = reader.u8()? as i32;
+                let position = reader.vec2()?;
+                let lifes = reader.u8()? as i32;
+                let direction_x = reader.u8()?;
+                let direction_y = reader.u8()?;
+                let flags = reader.u8()?;
+                let power = Some(reader.u8()?).filter(|power| *power != u8::MAX);
+                Some(PlayerState {
+                    number,
+                    position,
+                    lifes,
+                    direction_x,
+                    direction_y,
+                    invulnerable: flags & 1 != 0,
+                    stunned: flags & 2 != 0,
+                    power,
+                })
+            })
+            .collect::<Option<Vec<PlayerState>>>()?;
+        let objects = (0..reader.u16()?)
+            .map(|_| {
+                Some(ObjectState {
+                    id: reader.u64()?,
+                    kind: ObjectKind::from_code(reader.u8()?)?,
+                    position: reader.vec2()?,
+                    size: reader.vec2()?,
+                })
+            })
+            .collect::<Option<Vec<ObjectState>>>()?;
+        let broken = (0..reader.u16()?)
+            .map(|_| reader.u16())
+            .collect::<Option<Vec<u16>>>()?;
+        Some(Snapshot {
+            tick,
+            ack,
+            round,
+            arena_seed,
+            over: flags & 1 != 0,
+            winner: winner.map(|winner| winner as i32),
+            sudden_death: flags & 2 != 0,
+            remaining_ms,
+            players,
+            objects,
+            broken,
+        })
+    }
+}
+
+impl ObjectKind {
+    const ALL: [ObjectKind; 6] = [
+        ObjectKind::Bullet,
+        ObjectKind::IceBullet,
+        ObjectKind::Grenade,
+        ObjectKind::BouncyBall,
+        ObjectKind::PowerUp,
+        ObjectKind::Totem,
+    ];
+
+    fn from_code(code: u8) -> Option<ObjectKind> {
+        ObjectKind::ALL.get(code as usize).copied()
+    }
+
+    pub fn texture(&self) -> &'static str {
+        match self {
+            ObjectKind::Bullet => "/assets/images/bullet.png",
+            ObjectKind::IceBullet => "/assets/images/freezing_bullet.png",
+            ObjectKind::Grenade => "/assets/images/granate.png",
+            ObjectKind::BouncyBall => "/assets/images/bouncy_ball.png",
+            ObjectKind::PowerUp => "/assets/images/coin.png",
+            ObjectKind::Totem => "/assets/images/dead.png",
+        }
+    }
+}
+
+pub fn direction_code(direction: &Direction) -> u8 {
+    match direction {
+        Direction::None => 0,
+        Direction::Up => 1,
+        Direction::Down => 2,
+        Direction::Right => 3,
+        Direction::Left => 4,
+    }
+}
+
+pub fn direction_from_code(code: u8) -> Direction {
+    match code {
+        1 => Direction::Up,
+        2 => Direction::Down,
+        3 => Direction::Right,
+        4 => Direction::Left,
+        _ => Direction::None,
+    }
+}
+
+// the order BulletType::convert_int reads them back in
+pub fn power_code(power: &BulletType) -> Option<u8> {
+    match power {
+        BulletType::IceBullet => Some(0),
+        BulletType::ExplosiveBullet => Some(1),
+        BulletType::BouncyBullet => Some(2),
+        BulletType::NormalBullet => None,
+    }
+}
+
+pub fn server_app(config: &ServerConfig) -> Result<App, String> {
+    let socket = UdpSocket::bind((Ipv4Addr::UNSPECIFIED, config.port))
+        .and_then(|socket| {
+            socket.set_nonblocking(true)?;
+            Ok(socket)
+        })
+        .map_err(|error| format!("could not listen on port {}: {}", config.port, error))?;
+    let mut app = headless_app(TICK);
+    app.insert_resource(PlayerSlots::networked(config.players, 0))
+        .insert_resource(Profiles::default())
+        .add_systems(PreUpdate, receive_inputs.after(InputSystem))
+        .add_systems(Last, (restart_finished_round, send_snapshots).chain());
+    if config.log {
+        // every sprite the headless app asks for fails to load on purpose
+        app.add_plugins(LogPlugin {
+            filter: String::from("wgpu=error,naga=warn,bevy_asset=off"),
+            ..default()
+        });
+    }
+    app.finish();
+    app.cleanup();
+    let arena = app.world.resource::<ArenaConfig>();
+    let setup = NetSetup {
+        seed: 0,
+        rules: app.world.resource::<MatchRules>().clone(),
+        arena: arena.kind.name().to_string(),
+        arena_seed: arena.fixed_seed,
+    };
+    info!(
+        "serving {} players on {}",
+        config.players,
+        socket
+            .local_addr()
+            .map_or_else(|_| String::from("?"), |address| address.to_string())
+    );
+    app.insert_resource(Server {
+        players: config.players,
+        tick: 0,
+        round: 0,
+        rejected: 0,
+        socket,
+        setup,
+        clients: (0..config.players).map(|_| None).collect(),
+        restart: None,
+    });
+    Ok(app)
+}
+
+pub fn run(config: ServerConfig) -> Result<(), String> {
+    let mut app = server_app(&config)?;
+    serve(&mut app, config.ticks);
+    Ok(())
+}
+
+// one tick every 16.7ms, a server that fell behind does not try to catch up
+pub fn serve(app: &mut App, ticks: Option<u32>) {
+    let mut next = Instant::now();
+    while ticks.is_none_or(|ticks| app.world.resource::<Server>().tick < ticks) {
+        app.update();
+        next += TICK;
+        let now = Instant::now();
+        match next > now {
+            true => std::thread::sleep(next - now),
+            false => next = now,
+        }
+    }
+}
+
+impl Server {
+    pub fn local_addr(&self) -> Option<SocketAddr> {
+        self.socket.local_addr().ok()
+    }
+
+    pub fn connected(&self) -> usize {
+        self.clients.iter().flatten().count()
+    }
+
+    fn send(&self, address: SocketAddr, message: &ServerMessage) {
+        // a client that went away refuses the packet, the silence check takes care of it
+        let _ = self.socket.send_to(&message.encode(), address);
+    }
+
+    fn handle(&mut self, address: SocketAddr, message: ClientMessage, now: Instant) {
+        let slot = self
+            .clients
+            .iter()
+            .position(|client| client.as_ref().is_some_and(|c| c.address == address));
+        match (message, slot) {
+            (ClientMessage::Join, Some(slot)) => self.accept(slot, address),
+            (ClientMessage::Join, None) => {
+                let Some(slot) = self.clients.iter().position(Option::is_none) else {
+                    self.send(address, &ServerMessage::Full);
+                    return;
+                };
+                info!("{} joined as player {}", address, slot + 1);
+                self.clients[slot] = Some(ServerClient {
+                    address,
+                    queue: VecDeque::new(),
+                    received: 0,
+                    applied: 0,
+                    held: 0,
+                    joined_tick: self.tick,
+                    heard: now,
+                });
+                self.accept(slot, address);
+            }
+            (
+                ClientMessage::Input {
+                    player,
+                    first,
+                    inputs,
+                },
+                Some(slot),
+            ) => {
+                let tick = self.tick;
+                let Some(client) = self.clients[slot].as_mut() else {
+                    return;
+                };
+                client.heard = now;
+                // a client only speaks for its own slot and only for ticks that could have
+                // passed since it joined
+                let last = u32::try_from(inputs.len().saturating_sub(1))
+                    .ok()
+                    .and_then(|count| first.checked_add(count));
+                let Some(last) = last.filter(|last| {
+                    player as usize == slot + 1 && *last <= tick - client.joined_tick + MAX_AHEAD
+                }) else {
+                    self.rejected += 1;
+                    return;
+                };
+                for (seq, input) in (first..=last).zip(inputs) {
+                    if seq <= client.received {
+                        continue;
+                    }
+                    client.received = seq;
+                    client.queue.push_back((seq, input));
+                }
+                while client.queue.len() > MAX_QUEUED {
+                    // a dropped input still counts for the keys it pressed
+                    let Some((_, dropped)) = client.queue.pop_front() else {
+                        break;
+                    };
+                    if let Some((_, next)) = client.queue.front_mut() {
+                        *next |= dropped & 0xff00;
+                    }
+                }
+            }
+            (ClientMessage::Input { .. }, None) => self.rejected += 1,
+            (ClientMessage::Leave, Some(slot)) => {
+                info!("player {} left", slot + 1);
+                self.clients[slot] = None;
+            }
+            (ClientMessage::Leave, None) => (),
+        }
+    }
+
+    fn accept(&self, slot: usize, address: SocketAddr) {
+        let accepted = ServerMessage::Accepted {
+            player: slot as u8 + 1,
+            players: self.players as u8,
+            setup: self.setup.clone(),
+        };
+        self.send(address, &accepted);
+    }
+}
+
+// takes in what the clients sent and puts this tick's input of every slot on the keyboard,
+// the match starts once all slots are taken
+pub fn receive_inputs(
+    mut server: ResMut<Server>,
+    mut keys: ResMut<Input<KeyCode>>,
+    state: Res<State<GameState>>,
+    mut next_state: ResMut<NextState<GameState>>,
+) {
+    let now = Instant::now();
+    let mut buffer = [0; 2048];
+    loop {
+        match server.socket.recv_from(&mut buffer) {
+            Ok((size, address)) => {
+                if let Some(message) = ClientMessage::decode(&buffer[..size]) {
+                    server.handle(address, message, now);
+                }
+            }
+            Err(error) if error.kind() == ErrorKind::WouldBlock => break,
+            Err(_) => continue,
+        }
+    }
+    for (slot, client) in server.clients.iter_mut().enumerate() {
+        if client
+            .as_ref()
+            .is_some_and(|client| now - client.heard > SILENT_AFTER)
+        {
+            info!("player {} stopped answering and lost the slot", slot + 1);
+            *client = None;
+        }
+    }
+    if *state.get() != GameState::Playing && server.connected() == server.players {
+        info!("all {} players are here, starting", server.players);
+        next_state.set(GameState::Playing);
+    }
+    for (slot, client) in server.clients.iter_mut().enumerate() {
+        let input = match client {
+            Some(client) => match client.queue.pop_front() {
+                Some((seq, input)) => {
+                    client.applied = seq;
+                    client.held = input & 0x00ff;
+                    input
+                }
+                // late, the keys stay held until the input shows up
+                None => client.held,
+            },
+            None => 0,
+        };
+        apply_input(&Bindings::for_slot(slot as i32 + 1), input, &mut keys);
+    }
+}
+
+pub fn restart_finished_round(
+    mut server: ResMut<Server>,
+    round: Res<RoundState>,
+    mut event_writer: EventWriter<ResetGameEvent>,
+) {
+    if !round.over {
+        server.restart = None;
+        return;
+    }
+    let timer = server
+        .restart
+        .get_or_insert_with(|| Timer::from_seconds(RESTART_SECS, TimerMode::Once));
+    if timer.tick(TICK).just_finished() {
+        event_writer.send_default();
+    }
+}
+
+#[allow(clippy::too_many_arguments)]
+#[allow(clippy::type_complexity)]
+pub fn send_snapshots(
+    mut server: ResMut<Server>,
+    players: Query<(&Player, &Transform)>,
+    bullets: Query<(Entity, &Bullet, &Transform)>,
+    pickups: Query<(Entity, &Transform, Option<&PowerUp>), Or<(With<PowerUp>, With<Totem>)>>,
+    walls: Query<(&Transform, &Visibility), With<Destructible>>,
+    round: Res<RoundState>,
+    arena: Res<ArenaConfig>,
+    mut event_reader: EventReader<ResetGameEvent>,
+) {
+    server.round += event_reader.read().count() as u32;
+    let mut snapshot = Snapshot {
+        tick: server.tick,
+        ack: 0,
+        round: server.round,
+        arena_seed: arena.seed,
+        over: round.over,
+        winner: round.winner,
+        sudden_death: round.sudden_death,
+        remaining_ms: round
+            .remaining_secs()
+            .map(|remaining| (remaining * 1000.0) as u32),
+        players: players
+            .iter()
+            .map(|(player, transform)| PlayerState {
+                number: player.player_number,
+                position: transform.translation.truncate(),
+                lifes: player.lifes,
+                direction_x: direction_code(&player.direction.direction_x),
+                direction_y: direction_code(&player.direction.direction_y),
+                invulnerable: player.invulnerable,
+                stunned: player.stunned,
+                power: player.power_up_type.as_ref().and_then(power_code),
+            })
+            .collect(),
+        objects: Vec::new(),
+        broken: broken_walls(&walls),
+    };
+    for (entity, bullet, transform) in &bullets {
+        let kind = match bullet.bullet_type {
+            BulletType::NormalBullet => ObjectKind::Bullet,
+            BulletType::IceBullet => ObjectKind::IceBullet,
+            BulletType::ExplosiveBullet => ObjectKind::Grenade,
+            BulletType::BouncyBullet => ObjectKind::BouncyBall,
+        };
+        snapshot.objects.push(object_state(entity, kind, transform));
+    }
+    for (entity, transform, powerup) in &pickups {
+        let kind = match powerup {
+            Some(_) => ObjectKind::PowerUp,
+            None => ObjectKind::Totem,
+        };
+        snapshot.objects.push(object_state(entity, kind, transform));
+    }
+    for client in server.clients.iter().flatten() {
+        snapshot.ack = client.applied;
+        server.send(client.address, &ServerMessage::Snapshot(snapshot.clone()));
+    }
+    server.tick += 1;
+}
+
+fn object_state(entity: Entity, kind: ObjectKind, transform: &Transform) -> ObjectState {
+    ObjectState {
+        id: entity.to_bits(),
+        kind,
+        position: transform.translation.truncate(),
+        size: transform.scale.truncate(),
+    }
+}
+
+// both sides spawn the same arena, so the position order of its walls is the same too
+pub fn wall_order<'a>(walls: impl Iterator<Item = &'a Transform>) -> Vec<usize> {
+    let positions: Vec<(i32, i32)> = walls
+        .map(|transform| {
+            (
+                transform.translation.x.round() as i32,
+                transform.translation.y.round() as i32,
+            )
+        })
+        .collect();
+    let mut order: Vec<usize> = (0..positions.len()).collect();
+    order.sort_by_key(|index| positions[*index]);
+    order
+}
+
+fn broken_walls(walls: &Query<(&Transform, &Visibility), With<Destructible>>) -> Vec<u16> {
+    let walls: Vec<(&Transform, &Visibility)> = walls.iter().collect();
+    wall_order(walls.iter().map(|(transform, _)| *transform))
+        .into_iter()
+        .enumerate()
+        .filter(|(_, index)| walls[*index].1 == Visibility::Hidden)
+        .map(|(position, _)| position as u16)
+        .collect()
+}
@@ -0,0 +1,343 @@
+use bevy::prelude::*;
+
+use crate::game_utils::GameState;
+use crate::rules::{GameMode, MatchRules, RulesPreset, SuddenDeath};
+
+#[derive(Component)]
+pub struct SettingsScreen;
+
+#[derive(Component)]
+pub struct SettingsButton {
+    pub action: SettingsAction,
+}
+
+#[derive(Component)]
+pub struct SettingsText {
+    pub field: Option<RuleField>,
+}
+
+#[derive(Clone, Copy)]
+pub enum SettingsAction {
+    Preset(RulesPreset),
+    Decrease(RuleField),
+    Increase(RuleField),
+    Start,
+    Lobby,
+    Stats,
+}
+
+#[derive(Clone, Copy)]
+pub enum RuleField {
+    Mode,
+    Lifes,
+    Speed,
+    Invulnerability,
+    Stun,
+    ShotCooldown,
+    StartingPowerUps,
+    RoundTime,
+    SuddenDeath,
+}
+
+impl RuleField {
+    pub const ALL: [RuleField; 9] = [
+        RuleField::Mode,
+        RuleField::Lifes,
+        RuleField::Speed,
+        RuleField::Invulnerability,
+        RuleField::Stun,
+        RuleField::ShotCooldown,
+        RuleField::StartingPowerUps,
+        RuleField::RoundTime,
+        RuleField::SuddenDeath,
+    ];
+
+    pub fn describe(&self, rules: &MatchRules) -> String {
+        match self {
+            RuleField::Mode => format!("Mode: {}", rules.mode.name()),
+            RuleField::Lifes => format!("Lifes: {}", rules.lifes),
+            RuleField::Speed => format!("Speed: {:.1}", rules.player_speed),
+            RuleField::Invulnerability => {
+                format!(
+                    "Invulnerability: {:.2}s",
+                    rules.invulnerability_ms as f32 / 1000.0
+                )
+            }
+            RuleField::Stun => format!("Stun: {:.2}s", rules.stun_ms as f32 / 1000.0),
+            RuleField::ShotCooldown => format!("Shot cooldown: {}ms", rules.shot_cooldown_ms),
+            RuleField::StartingPowerUps => {
+                format!("Power-ups at start: {}", rules.starting_powerups)
+            }
+            RuleField::RoundTime => match rules.round_secs {
+                0 => String::from("Round time: Off"),
+                secs => format!("Round time: {}:{:02}", secs / 60, secs % 60),
+            },
+            RuleField::SuddenDeath => format!("Sudden death: {}", rules.sudden_death.name()),
+        }
+    }
+
+    // steps is +1 or -1, every field stays inside a range that still makes a playable match
+    pub fn adjust(&self, rules: &mut MatchRules, steps: i32) {
+        let step_ms = |value: u64, step: i64, min: i64, max: i64| {
+            (value as i64 + step * steps as i64).clamp(min, max) as u64
+        };
+        match self {
+            RuleField::Mode => {
+                let count = GameMode::ALL.len() as i32;
+                let current = GameMode::ALL
+                    .iter()
+                    .position(|mode| *mode == rules.mode)
+                    .unwrap_or(0) as i32;
+                rules.mode = GameMode::ALL[(current + steps).rem_euclid(count) as usize];
+            }
+            RuleField::Lifes => rules.lifes = (rules.lifes + steps).clamp(1, 9),
+            RuleField::Speed => {
+                rules.player_speed = (rules.player_speed + 0.5 * steps as f32).clamp(0.5, 6.0)
+            }
+            RuleField::Invulnerability => {
+                rules.invulnerability_ms = step_ms(rules.invulnerability_ms, 250, 0, 5000)
+            }
+            RuleField::Stun => rules.stun_ms = step_ms(rules.stun_ms, 250, 0, 5000),
+            RuleField::ShotCooldown => {
+                rules.shot_cooldown_ms = step_ms(rules.shot_cooldown_ms, 50, 50, 2000)
+            }
+            RuleField::StartingPowerUps => {
+                rules.starting_powerups =
+                    (rules.starting_powerups as i32 + steps).clamp(0, 10) as u32
+            }
+            RuleField::RoundTime => {
+                rules.round_secs = (rules.round_secs as i32 + 30 * steps).clamp(0, 600) as u32
+            }
+            // only two kinds, both buttons flip between them
+            RuleField::SuddenDeath => {
+                rules.sudden_death = match rules.sudden_death {
+                    SuddenDeath::ShrinkingArena => SuddenDeath::OneLife,
+                    SuddenDeath::OneLife => SuddenDeath::ShrinkingArena,
+                }
+            }
+        }
+    }
+}
+
+pub fn spawn_settings_screen(
+    mut commands: Commands,
+    asset_server: Res<AssetServer>,
+    rules: Res<MatchRules>,
+) {
+    let font = asset_server.load("/assets/fonts/PixeloidSans.ttf");
+    let text_style = |font_size: f32| TextStyle {
+        font: font.clone(),
+        font_size,
+        color: Color::WHITE,
+    };
+    commands
+        .spawn((
+            NodeBundle {
+                style: Style {
+                    width: Val::Percent(100.0),
+                    height: Val::Percent(100.0),
+                    flex_direction: FlexDirection::Column,
+                    align_items: AlignItems::Center,
+                    justify_content: JustifyContent::Center,
+                    ..default()
+                },
+                background_color: Color::rgba(0.0, 0.0, 0.0, 0.85).into(),
+                z_index: ZIndex::Global(10),
+                ..default()
+            },
+            SettingsScreen,
+        ))
+        .with_children(|parent| {
+            parent.spawn(TextBundle::from_section("Match rules", text_style(40.0)));
+            parent
+                .spawn(NodeBundle {
+                    style: Style {
+                        margin: UiRect::all(Val::Px(8.0)),
+                        ..default()
+                    },
+                    ..default()
+                })
+                .with_children(|row| {
+                    for preset in RulesPreset::ALL {
+                        spawn_button(
+                            row,
+                            &asset_server,
+                            preset.name(),
+                            text_style(20.0),
+                            SettingsButton {
+                                action: SettingsAction::Preset(preset),
+                            },
+                        );
+                    }
+                });
+            parent.spawn((
+                TextBundle::from_section(preset_text(&rules), text_style(20.0)),
+                SettingsText { field: None },
+            ));
+            for field in RuleField::ALL {
+                parent
+                    .spawn(NodeBundle {
+                        style: Style {
+                            align_items: AlignItems::Center,
+                            ..default()
+                        },
+                        ..default()
+                    })
+                    .with_children(|row| {
+                        spawn_button(
+                            row,
+                            &asset_server,
+                            "-",
+                            text_style(20.0),
+                            SettingsButton {
+                                action: SettingsAction::Decrease(field),
+                            },
+                        );
+                        row.spawn((
+                            TextBundle::from_section(field.describe(&rules), text_style(20.0))
+                                .with_style(Style {
+                                    width: Val::Px(400.0),
+                                    justify_content: JustifyContent::Center,
+                                    ..default()
+                                }),
+                            SettingsText { field: Some(field) },
+                        ));
+                        spawn_button(
+                            row,
+                            &asset_server,
+                            "+",
+                            text_style(20.0),
+                            SettingsButton {
+                                action: SettingsAction::Increase(field),
+                            },
+                        );
+                    });
+            }
+            parent.spawn(NodeBundle::default()).with_children(|row| {
+                spawn_button(
+                    row,
+                    &asset_server,
+                    "Start",
+                    text_style(30.0),
+                    SettingsButton {
+                        action: SettingsAction::Start,
+                    },
+                );
+                spawn_button(
+                    row,
+                    &asset_server,
+                    "LAN game",
+                    text_style(30.0),
+                    SettingsButton {
+                        action: SettingsAction::Lobby,
+                    },
+                );
+                spawn_button(
+                    row,
+                    &asset_server,
+                    "Stats",
+                    text_style(30.0),
+                    SettingsButton {
+                        action: SettingsAction::Stats,
+                    },
+                );
+            });
+        });
+}
+
+pub fn spawn_button(
+    parent: &mut ChildBuilder,
+    asset_server: &Res<AssetServer>,
+    label: &str,
+    text_style: TextStyle,
+    button: impl Bundle,
+) {
+    parent
+        .spawn((
+            ButtonBundle {
+                style: Style {
+                    margin: UiRect::all(Val::Px(8.0)),
+                    padding: UiRect {
+                        left: Val::Px(17.0),
+                        right: Val::Px(17.0),
+                        top: Val::Px(15.0),
+                        bottom: Val::Px(15.0),
+                    },
+                    justify_content: JustifyContent::Center,
+                    ..default()
+                },
+                image: asset_server.load("/assets/images/hud_64_32.png").into(),
+                ..default()
+            },
+            button,
+        ))
+        .with_children(|subparent| {
+            subparent.spawn(TextBundle::from_section(label, text_style));
+        });
+}
+
+fn preset_text(rules: &MatchRules) -> String {
+    match rules.preset() {
+        Some(preset) => format!("Preset: {}", preset.name()),
+        None => String::from("Preset: Custom"),
+    }
+}
+
+pub fn settings_clicked(
+    interaction_query: Query<(&Interaction, &SettingsButton), Changed<Interaction>>,
+    mut rules: ResMut<MatchRules>,
+    mut next_state: ResMut<NextState<GameState>>,
+    keys: Res<Input<KeyCode>>,
+) {
+    let mut start = keys.just_pressed(KeyCode::Return);
+    let mut lobby = false;
+    let mut stats = false;
+    for (interaction, button) in &interaction_query {
+        if *interaction != Interaction::Pressed {
+            continue;
+        }
+        match button.action {
+            SettingsAction::Preset(preset) => *rules = preset.rules(),
+            SettingsAction::Decrease(field) => field.adjust(&mut rules, -1),
+            SettingsAction::Increase(field) => field.adjust(&mut rules, 1),
+            SettingsAction::Start => start = true,
+            SettingsAction::Lobby => lobby = true,
+            SettingsAction::Stats => stats = true,
+        }
+    }
+    if stats {
+        next_state.set(GameState::Stats);
+        return;
+    }
+    if start || lobby {
+        rules.save();
+        next_state.set(match lobby {
+            true => GameState::Lobby,
+            false => GameState::Playing,
+        });
+    }
+}
+
+pub fn update_settings_text(
+    mut text_query: Query<(&mut Text, &SettingsText)>,
+    rules: Res<MatchRules>,
+) {
+    if !rules.is_changed() {
+        return;
+    }
+    for (mut text, settings_text) in &mut text_query {
+        text.sections[0].value = match settings_text.field {
+            Some(field) => field.describe(&rules),
+            None => preset_text(&rules),
+        };
+    }
+}
+
+pub fn despawn_settings_screen(
+    mut commands: Commands,
+    screens: Query<Entity, With<SettingsScreen>>,
+) {
+    for entity in &screens {
+        commands.entity(entity).despawn_recursive();
+    }
+}
@@ -0,0 +1,187 @@
+use bevy::{prelude::*, sprite::collide_aabb::collide, utils::HashMap};
+
+use crate::audio::SoundEvent;
+use crate::game_objects::{
+    Destructible, KinematicWall, MovingWall, Player, RotatingWall, WallCracks,
+};
+use crate::game_utils::{Collider, ResetGameEvent, WallDamageEvent, WallRebuildTimer};
+
+pub fn damage_walls(
+    mut commands: Commands,
+    mut walls: Query<(Entity, &Transform, &mut Destructible), With<Collider>>,
+    cracks: Query<(Entity, &WallCracks)>,
+    mut event_reader: EventReader<WallDamageEvent>,
+    mut event_writer_sound: EventWriter<SoundEvent>,
+    asset_server: Res<AssetServer>,
+) {
+    // several bullets can hit the same wall in one tick, sum them up first
+    let mut damage_per_wall: HashMap<Entity, i32> = HashMap::new();
+    for event in event_reader.read() {
+        *damage_per_wall.entry(event.wall).or_insert(0) += event.damage;
+    }
+    for (wall, damage) in damage_per_wall {
+        let Ok((entity, transform, mut destructible)) = walls.get_mut(wall) else {
+            continue;
+        };
+        let old_sprite = destructible.crack_sprite();
+        destructible.hp -= damage;
+        let new_sprite = destructible.crack_sprite();
+        if old_sprite != new_sprite {
+            despawn_cracks(&mut commands, &cracks, entity);
+            if let Some(sprite) = new_sprite {
+                spawn_cracks(&mut commands, &asset_server, entity, transform, sprite);
+            }
+        }
+        if destructible.hp > 0 {
+            continue;
+        }
+        commands
+            .entity(entity)
+            .remove::<Collider>()
+            .insert(Visibility::Hidden);
+        if let Some(rebuild_after) = destructible.rebuild_after {
+            commands.entity(entity).insert(WallRebuildTimer {
+                timer: Timer::new(rebuild_after, TimerMode::Once),
+            });
+        }
+        event_writer_sound.send(SoundEvent::Explosion);
+    }
+}
+
+pub fn move_kinematic_walls(
+    mut walls: Query<(
+        &mut Transform,
+        &mut KinematicWall,
+        Option<&mut MovingWall>,
+        Option<&RotatingWall>,
+    )>,
+    time: Res<Time>,
+) {
+    let delta = time.delta_seconds();
+    if delta <= 0.0 {
+        return;
+    }
+    for (mut transform, mut kinematic, moving, rotating) in &mut walls {
+        let start = transform.translation.truncate();
+        if let Some(mut moving) = moving {
+            if let Some(target) = moving.waypoints.get(moving.next).copied() {
+                let to_target = target - start;
+                let step = moving.speed * delta;
+                if to_target.length() <= step {
+                    transform.translation.x = target.x;
+                    transform.translation.y = target.y;
+                    moving.next = (moving.next + 1) % moving.waypoints.len();
+                } else {
+                    let next = start + to_target.normalize() * step;
+                    transform.translation.x = next.x;
+                    transform.translation.y = next.y;
+                }
+            }
+        }
+        kinematic.velocity = (transform.translation.truncate() - start) / delta;
+        kinematic.angular_velocity = 0.0;
+        if let Some(rotating) = rotating {
+            let rotation = Quat::from_rotation_z(rotating.angular_speed * delta);
+            let offset = (transform.translation.truncate() - rotating.pivot).extend(0.0);
+            let position = rotating.pivot + (rotation * offset).truncate();
+            transform.translation.x = position.x;
+            transform.translation.y = position.y;
+            transform.rotation = rotation * transform.rotation;
+            kinematic.angular_velocity = rotating.angular_speed;
+            kinematic.pivot = rotating.pivot;
+        }
+    }
+}
+
+pub fn rebuild_walls(
+    mut commands: Commands,
+    mut walls: Query<(
+        Entity,
+        &Transform,
+        &mut Destructible,
+        &mut WallRebuildTimer,
+        &mut Visibility,
+    )>,
+    players: Query<&Transform, With<Player>>,
+    time: Res<Time>,
+) {
+    for (entity, transform, mut destructible, mut rebuild_timer, mut visibility) in &mut walls {
+        rebuild_timer.timer.tick(time.delta());
+        if !rebuild_timer.timer.finished() {
+            continue;
+        }
+        // wait until nobody stands where the wall comes back
+        let blocked = players.iter().any(|player_transform| {
+            collide(
+                transform.translation,
+                transform.scale.truncate(),
+                player_transform.translation,
+                player_transform.scale.truncate(),
+            )
+            .is_some()
+        });
+        if blocked {
+            continue;
+        }
+        destructible.hp = destructible.max_hp;
+        *visibility = Visibility::Inherited;
+        commands
+            .entity(entity)
+            .remove::<WallRebuildTimer>()
+            .insert(Collider);
+    }
+}
+
+pub fn reset_walls(
+    mut commands: Commands,
+    mut walls: Query<(Entity, &mut Destructible, &mut Visibility)>,
+    cracks: Query<Entity, With<WallCracks>>,
+    mut event_reader: EventReader<ResetGameEvent>,
+) {
+    for _ in event_reader.read() {
+        for entity in &cracks {
+            commands.entity(entity).despawn();
+        }
+        for (entity, mut destructible, mut visibility) in &mut walls {
+            destructible.hp = destructible.max_hp;
+            *visibility = Visibility::Inherited;
+            commands
+                .entity(entity)
+                .remove::<WallRebuildTimer>()
+                .insert(Collider);
+        }
+    }
+}
+
+fn despawn_cracks(commands: &mut Commands, cracks: &Query<(Entity, &WallCracks)>, wall: Entity) {
+    for (entity, crack) in cracks {
+        if crack.wall == wall {
+            commands.entity(entity).despawn();
+        }
+    }
+}
+
+fn spawn_cracks(
+    commands: &mut Commands,
+    asset_server: &Res<AssetServer>,
+    wall: Entity,
+    wall_transform: &Transform,
+    sprite: &'static str,
+) {
+    commands.spawn((
+        WallCracks { wall },
+        SpriteBundle {
+            sprite: Sprite {
+                custom_size: Option::Some(Vec2 { x: 1.0, y: 1.0 }),
+                ..default()
+            },
+            texture: asset_server.load(sprite),
+            transform: Transform {
+                translation: wall_transform.translation + Vec3::new(0.0, 0.0, 0.5),
+                scale: wall_transform.scale,
+                ..default()
+            },
+            ..default()
+        },
+    ));
+}
@@ -0,0 +1,21 @@
+use bevy::{prelude::*, time::TimeUpdateStrategy};
+use stapid::headless::headless_app;
+use stapid::killcam::KillCamPlugin;
+use stapid::profiles::Profiles;
+use stapid::replay::TICK;
+use stapid::results::ResultsOutput;
+use stapid::rules::MatchRules;
+
+// the headless game with the kill-cam back in, saved rules or profiles from a previous game must
+// not change the outcome, and the rounds played here are nobody's results
+pub fn test_app(clock: TimeUpdateStrategy) -> App {
+    let mut app = headless_app(TICK);
+    app.add_plugins(KillCamPlugin)
+        .insert_resource(clock)
+        .insert_resource(MatchRules::default())
+        .insert_resource(Profiles::default())
+        .insert_resource(ResultsOutput::Off);
+    app.finish();
+    app.cleanup();
+    app
+}
@@ -0,0 +1,569 @@
+mod common;
+
+use bevy::{
+    prelude::*,
+    time::TimeUpdateStrategy,
+    utils::{Duration, HashSet},
+};
+use stapid::arena::{ArenaConfig, ArenaKind};
+use stapid::arena_gen::{FreeCells, GeneratedWall};
+use stapid::arena_layout::{ArenaFiles, ArenaLayout};
+use stapid::game_objects::{
+    Bullet, Destructible, Hazard, HazardBundle, Player, PowerUp, Totem, Wall,
+};
+use stapid::game_utils::{
+    BulletType, Collider, Direction, DirectionHelper, GameState, Name, PlayerPowerUpEvent,
+    ResetGameEvent,
+};
+use stapid::geometry::Obb;
+use stapid::killcam::KillCamOverlay;
+use stapid::modes::ModeScores;
+use stapid::replay::TICK;
+use stapid::results::{MatchLog, ResultsOutput};
+use stapid::rng::GameRng;
+use stapid::round::{RoundState, RoundText};
+use stapid::round_stats::{RoundStats, RoundStatsPanel};
+use stapid::rules::{GameMode, MatchRules, SuddenDeath};
+
+use common::test_app;
+
+const SEED: u64 = 42;
+
+// the arena gets cleared out so every test only deals with what it spawned itself
+fn headless_app() -> App {
+    let mut app = test_app(TimeUpdateStrategy::ManualDuration(TICK));
+    app.insert_resource(GameRng::new(Some(SEED)));
+    app.world
+        .resource_mut::<NextState<GameState>>()
+        .set(GameState::Playing);
+    step(&mut app, 2);
+
+    let leftovers: Vec<Entity> = app
+        .world
+        .query_filtered::<Entity, Or<(With<Sprite>, With<TextureAtlasSprite>)>>()
+        .iter(&app.world)
+        .collect();
+    for entity in leftovers {
+        app.world.despawn(entity);
+    }
+    app
+}
+
+fn step(app: &mut App, frames: u32) {
+    for _ in 0..frames {
+        app.update();
+    }
+}
+
+fn step_secs(app: &mut App, secs: f32) {
+    step(app, (secs / TICK.as_secs_f32()).ceil() as u32);
+}
+
+fn spawn_player(app: &mut App, player_number: i32, position: Vec2) -> Entity {
+    let rules = app.world.resource::<MatchRules>().clone();
+    let name = format!("player{}", player_number);
+    let player = Player::new(
+        player_number,
+        name.clone(),
+        KeyCode::ControlLeft,
+        KeyCode::ShiftLeft,
+        KeyCode::Q,
+        KeyCode::E,
+        KeyCode::W,
+        KeyCode::S,
+        KeyCode::D,
+        KeyCode::A,
+        DirectionHelper {
+            direction_x: Direction::Right,
+            direction_y: Direction::None,
+        },
+    )
+    .with_rules(&rules);
+    app.world
+        .spawn((
+            player,
+            sprite(position, Vec2::splat(50.0)),
+            Collider,
+            Direction::Right,
+            Name::new(name),
+        ))
+        .id()
+}
+
+fn spawn_wall(app: &mut App, position: Vec2, size: Vec2) {
+    app.world.spawn((sprite(position, size), Collider));
+}
+
+fn spawn_bullet(app: &mut App, bullet_type: BulletType, position: Vec2, velocity: Vec2) -> Entity {
+    let direction = DirectionHelper {
+        direction_x: Direction::Right,
+        direction_y: Direction::None,
+    };
+    let mut bullet = Bullet::bullet_from_enum(Some(&bullet_type), &direction).with_owner(2);
+    bullet.velocity = velocity;
+    app.world
+        .spawn((bullet, sprite(position, Vec2::splat(30.0))))
+        .id()
+}
+
+fn sprite(position: Vec2, size: Vec2) -> SpriteBundle {
+    SpriteBundle {
+        sprite: Sprite {
+            custom_size: Option::Some(Vec2 { x: 1.0, y: 1.0 }),
+            ..default()
+        },
+        transform: Transform {
+            translation: position.extend(2.0),
+            scale: size.extend(0.0),
+            ..default()
+        },
+        ..default()
+    }
+}
+
+fn player(app: &App, entity: Entity) -> &Player {
+    app.world.get::<Player>(entity).unwrap()
+}
+
+fn count_kill_cam_overlay(app: &mut App) -> usize {
+    app.world
+        .query_filtered::<(), With<KillCamOverlay>>()
+        .iter(&app.world)
+        .count()
+}
+
+fn round_text(app: &mut App) -> String {
+    app.world
+        .query_filtered::<&Text, With<RoundText>>()
+        .single(&app.world)
+        .sections[0]
+        .value
+        .clone()
+}
+
+#[test]
+fn normal_bullet_takes_one_life() {
+    let mut app = headless_app();
+    let target = spawn_player(&mut app, 1, Vec2::new(-200.0, 0.0));
+    spawn_player(&mut app, 2, Vec2::new(200.0, 200.0));
+    let bullet = spawn_bullet(
+        &mut app,
+        BulletType::NormalBullet,
+        Vec2::new(-100.0, 0.0),
+        Vec2::new(-20.0, 0.0),
+    );
+    step(&mut app, 30);
+
+    assert!(app.world.get_entity(bullet).is_none());
+    assert_eq!(player(&app, target).lifes, 2);
+    assert_eq!(player(&app, target).last_hit_by, Some(2));
+}
+
+#[test]
+fn ice_bullet_stun_wears_off_after_two_seconds() {
+    let mut app = headless_app();
+    let target = spawn_player(&mut app, 1, Vec2::new(-200.0, 0.0));
+    spawn_player(&mut app, 2, Vec2::new(200.0, 200.0));
+    spawn_bullet(
+        &mut app,
+        BulletType::IceBullet,
+        Vec2::new(-150.0, 0.0),
+        Vec2::new(-40.0, 0.0),
+    );
+    step(&mut app, 3);
+    assert!(player(&app, target).stunned);
+    assert_eq!(player(&app, target).lifes, 3);
+
+    step_secs(&mut app, 1.8);
+    assert!(player(&app, target).stunned);
+    step_secs(&mut app, 0.3);
+    assert!(!player(&app, target).stunned);
+}
+
+#[test]
+fn bouncy_bullet_despawns_after_four_wall_hits() {
+    let mut app = headless_app();
+    spawn_wall(&mut app, Vec2::new(-150.0, 0.0), Vec2::new(20.0, 400.0));
+    spawn_wall(&mut app, Vec2::new(150.0, 0.0), Vec2::new(20.0, 400.0));
+    let bullet = spawn_bullet(
+        &mut app,
+        BulletType::BouncyBullet,
+        Vec2::ZERO,
+        Vec2::new(20.0, 0.0),
+    );
+
+    let mut bounces_left = HashSet::new();
+    for _ in 0..200 {
+        let Some(bullet) = app.world.get::<Bullet>(bullet) else {
+            break;
+        };
+        bounces_left.insert(bullet.bounces_left);
+        step(&mut app, 1);
+    }
+
+    assert!(app.world.get_entity(bullet).is_none());
+    assert_eq!(bounces_left, HashSet::from_iter([4, 3, 2, 1]));
+}
+
+#[test]
+fn explosion_takes_two_lives() {
+    let mut app = headless_app();
+    let target = spawn_player(&mut app, 1, Vec2::new(-200.0, 0.0));
+    spawn_player(&mut app, 2, Vec2::new(200.0, 200.0));
+    spawn_bullet(
+        &mut app,
+        BulletType::ExplosiveBullet,
+        Vec2::new(-200.0, 0.0),
+        Vec2::ZERO,
+    );
+    step(&mut app, 3);
+
+    assert_eq!(player(&app, target).lifes, 1);
+    assert_eq!(player(&app, target).last_hit_by, Some(2));
+}
+
+#[test]
+fn dead_player_leaves_a_totem() {
+    let mut app = headless_app();
+    let target = spawn_player(&mut app, 1, Vec2::new(-200.0, 0.0));
+    spawn_player(&mut app, 2, Vec2::new(200.0, 200.0));
+    app.world.get_mut::<Player>(target).unwrap().lifes = 1;
+    spawn_bullet(
+        &mut app,
+        BulletType::NormalBullet,
+        Vec2::new(-150.0, 0.0),
+        Vec2::new(-20.0, 0.0),
+    );
+    step(&mut app, 10);
+
+    assert!(app.world.get_entity(target).is_none());
+    let totems: Vec<Vec3> = app
+        .world
+        .query_filtered::<&Transform, With<Totem>>()
+        .iter(&app.world)
+        .map(|transform| transform.translation)
+        .collect();
+    assert_eq!(totems.len(), 1);
+    assert_eq!(totems[0].truncate(), Vec2::new(-200.0, 0.0));
+}
+
+#[test]
+fn final_kill_plays_the_kill_cam_before_the_round_text() {
+    let mut app = headless_app();
+    let target = spawn_player(&mut app, 1, Vec2::new(-200.0, 0.0));
+    spawn_player(&mut app, 2, Vec2::new(200.0, 200.0));
+    app.world.get_mut::<Player>(target).unwrap().lifes = 1;
+    spawn_bullet(
+        &mut app,
+        BulletType::NormalBullet,
+        Vec2::new(-150.0, 0.0),
+        Vec2::new(-20.0, 0.0),
+    );
+    step_secs(&mut app, 1.0);
+    assert!(count_kill_cam_overlay(&mut app) > 0);
+    assert!(!round_text(&mut app).contains("wins"));
+
+    // about two seconds of the round at quarter speed
+    step_secs(&mut app, 8.5);
+    assert_eq!(count_kill_cam_overlay(&mut app), 0);
+    assert_eq!(round_text(&mut app), "Player 2\nwins!");
+}
+
+#[test]
+fn same_seed_rolls_the_same_powerups() {
+    let rolls = || {
+        let mut app = headless_app();
+        app.world.send_event(PlayerPowerUpEvent {});
+        step(&mut app, 1);
+        let position = app
+            .world
+            .query_filtered::<&Transform, With<PowerUp>>()
+            .single(&app.world)
+            .translation;
+
+        spawn_player(&mut app, 1, position.truncate());
+        spawn_player(&mut app, 2, Vec2::new(200.0, 200.0));
+        step(&mut app, 3);
+        let player = app
+            .world
+            .query::<&Player>()
+            .iter(&app.world)
+            .find(|player| player.player_number == 1)
+            .unwrap();
+        assert!(player.powerup);
+        (position, player.power_up_type.clone().unwrap().to_string())
+    };
+
+    assert_eq!(rolls(), rolls());
+}
+
+// both players shoot once, player 2's bullet hits player 1 and player 2 stands in its own
+// grenade, the table shows up once the kill-cam is done and nothing counts after the round
+#[test]
+fn round_stats_count_shots_hits_and_self_hits() {
+    let mut app = headless_app();
+    let target = spawn_player(&mut app, 1, Vec2::new(-200.0, 0.0));
+    spawn_player(&mut app, 2, Vec2::new(200.0, 200.0));
+    spawn_bullet(
+        &mut app,
+        BulletType::NormalBullet,
+        Vec2::new(-150.0, 0.0),
+        Vec2::new(-20.0, 0.0),
+    );
+    spawn_bullet(
+        &mut app,
+        BulletType::ExplosiveBullet,
+        Vec2::new(200.0, 200.0),
+        Vec2::ZERO,
+    );
+    app.world
+        .resource_mut::<Input<KeyCode>>()
+        .press(KeyCode::ControlLeft);
+    step(&mut app, 1);
+    app.world
+        .resource_mut::<Input<KeyCode>>()
+        .reset(KeyCode::ControlLeft);
+    step(&mut app, 30);
+
+    let stats = app.world.resource::<RoundStats>().clone();
+    let hit = stats.get(1);
+    assert_eq!(hit.shots.get("Normal"), Some(&1));
+    assert_eq!(hit.damage_taken, 1);
+    assert_eq!(hit.hits, 0);
+    let shooter = stats.get(2);
+    assert_eq!(shooter.shots_fired(), 1);
+    assert_eq!(shooter.hits, 1);
+    assert_eq!(shooter.accuracy(), 100.0);
+    assert_eq!(shooter.damage_dealt, 1);
+    assert_eq!(shooter.self_hits, 1);
+    assert_eq!(shooter.damage_taken, 2);
+    assert!(hit.alive_secs > 0.4 && shooter.alive_secs > 0.4);
+
+    app.world.get_mut::<Player>(target).unwrap().lifes = 0;
+    step_secs(&mut app, 10.0);
+    let stats = app.world.resource::<RoundStats>().clone();
+    assert_eq!(stats.get(1).alive_secs, hit.alive_secs);
+    let json: serde_json::Value = serde_json::from_str(&stats.to_json(Some(2))).unwrap();
+    assert_eq!(json["winner"], 2);
+    assert_eq!(json["players"]["2"]["shots"]["Normal"], 1);
+    assert_eq!(json["players"]["2"]["hits"], 1);
+    let panel = app
+        .world
+        .query_filtered::<(), With<RoundStatsPanel>>()
+        .iter(&app.world)
+        .count();
+    assert_eq!(panel, 1);
+
+    app.world.send_event(ResetGameEvent {});
+    step(&mut app, 2);
+    let stats = app.world.resource::<RoundStats>();
+    assert!(stats
+        .players
+        .values()
+        .all(|player| player.shots_fired() == 0));
+}
+
+// the file is there after the first round and holds both rounds after the second, with the
+// kill, both winners and player 2 on top of the standings
+#[test]
+fn finished_rounds_write_the_match_results() {
+    let dir = std::env::temp_dir().join(format!("stapid_results_{}", std::process::id()));
+    let _ = std::fs::remove_dir_all(&dir);
+    let mut app = headless_app();
+    app.insert_resource(ResultsOutput::Directory(dir.clone()));
+    let target = spawn_player(&mut app, 1, Vec2::new(-200.0, 0.0));
+    spawn_player(&mut app, 2, Vec2::new(200.0, 200.0));
+    app.world.get_mut::<Player>(target).unwrap().lifes = 1;
+    spawn_bullet(
+        &mut app,
+        BulletType::NormalBullet,
+        Vec2::new(-150.0, 0.0),
+        Vec2::new(-20.0, 0.0),
+    );
+    step(&mut app, 10);
+
+    let path = dir.join(app.world.resource::<MatchLog>().file_name());
+    let read = || -> serde_json::Value {
+        serde_json::from_str(&std::fs::read_to_string(&path).expect("no results written")).unwrap()
+    };
+    let json = read();
+    assert_eq!(json["final"], false);
+    assert_eq!(json["rounds"][0]["round"], 1);
+    assert_eq!(json["rounds"][0]["winner"], 2);
+    let kill = &json["kills"][0];
+    assert_eq!((&kill["killer"], &kill["victim"]), (&2.into(), &1.into()));
+    assert_eq!(kill["bullet"], "Normal");
+    assert!(json["arena"]["kind"].is_string());
+    assert_eq!(json["rules"]["mode"], "Elimination");
+
+    app.world.send_event(ResetGameEvent {});
+    step(&mut app, 2);
+    let mut players = app.world.query::<&mut Player>();
+    for mut player in players.iter_mut(&mut app.world) {
+        if player.player_number != 2 {
+            player.lifes = 0;
+        }
+    }
+    step(&mut app, 3);
+    let json = read();
+    assert_eq!(json["rounds"][1]["round"], 2);
+    assert_eq!(json["rounds"][1]["winner"], 2);
+    assert_eq!(
+        json["standings"][0],
+        serde_json::json!({"place": 1, "player": 2, "round_wins": 2, "kills": 1, "deaths": 0})
+    );
+
+    app.world
+        .resource_mut::<NextState<GameState>>()
+        .set(GameState::Settings);
+    step(&mut app, 2);
+    assert_eq!(read()["final"], true);
+    let _ = std::fs::remove_dir_all(&dir);
+}
+
+// only the wall the arena file marks can be shot down, and it grows back after its own delay
+#[test]
+fn arena_files_mark_the_destructible_walls() {
+    let dir = std::env::temp_dir().join(format!("stapid_arenas_{}", std::process::id()));
+    std::fs::create_dir_all(&dir).unwrap();
+    std::fs::write(
+        dir.join("level1.ron"),
+        r#"(walls: [
+            (position: (100.0, 0.0), size: (16.0, 200.0), texture: "bricks_8_100_rotate.png"),
+            (
+                position: (-100.0, 0.0),
+                size: (16.0, 200.0),
+                texture: "bricks_8_100_rotate.png",
+                destructible: Some(3),
+                rebuild: Some(7),
+            ),
+        ])"#,
+    )
+    .unwrap();
+    let mut app = test_app(TimeUpdateStrategy::ManualDuration(TICK));
+    app.insert_resource(ArenaFiles { dir: dir.clone() });
+    app.update();
+
+    let mut walls: Vec<_> = app
+        .world
+        .query_filtered::<(&Transform, Option<&Destructible>), With<Wall>>()
+        .iter(&app.world)
+        .filter(|(transform, _)| transform.translation.y == 0.0)
+        .filter(|(transform, _)| transform.translation.x.abs() == 100.0)
+        .map(|(transform, destructible)| {
+            let destructible =
+                destructible.map(|destructible| (destructible.hp, destructible.rebuild_after));
+            (transform.translation.x, destructible)
+        })
+        .collect();
+    walls.sort_by(|a, b| a.0.total_cmp(&b.0));
+    assert_eq!(
+        walls,
+        vec![
+            (-100.0, Some((3, Some(Duration::from_secs(7))))),
+            (100.0, None)
+        ]
+    );
+    let _ = std::fs::remove_dir_all(&dir);
+
+    // the files the game ships with keep the four middle segments of level 1 breakable
+    let shipped = ArenaLayout::load(&ArenaFiles::default(), ArenaKind::Level1);
+    let breakable = shipped
+        .walls
+        .iter()
+        .filter(|wall| wall.destructible.is_some())
+        .count();
+    assert_eq!((shipped.walls.len(), breakable), (12, 4));
+}
+
+// on a generated arena every power-up lands in the middle of a cell no wall runs through
+#[test]
+fn generated_arenas_keep_power_ups_out_of_the_walls() {
+    let mut app = test_app(TimeUpdateStrategy::ManualDuration(TICK));
+    app.insert_resource(ArenaConfig {
+        kind: ArenaKind::Generated,
+        fixed_seed: Some(11),
+        seed: 11,
+    })
+    .insert_resource(GameRng::new(Some(SEED)));
+    app.world
+        .resource_mut::<NextState<GameState>>()
+        .set(GameState::Playing);
+    step(&mut app, 2);
+    for _ in 0..20 {
+        app.world.send_event(PlayerPowerUpEvent {});
+    }
+    step(&mut app, 1);
+
+    let free_cells = app.world.resource::<FreeCells>().cells.clone();
+    let walls: Vec<Obb> = app
+        .world
+        .query_filtered::<&Transform, With<GeneratedWall>>()
+        .iter(&app.world)
+        .map(Obb::from_transform)
+        .collect();
+    assert!(!walls.is_empty());
+    let powerups: Vec<Transform> = app
+        .world
+        .query_filtered::<&Transform, With<PowerUp>>()
+        .iter(&app.world)
+        .copied()
+        .collect();
+    assert!(powerups.len() >= 20);
+    for powerup in powerups {
+        assert!(free_cells.contains(&powerup.translation.truncate()));
+        let body = Obb::from_transform(&powerup);
+        assert!(walls.iter().all(|wall| wall.penetration(&body).is_none()));
+    }
+}
+
+// one life sudden death puts both on a single life, when they go down in the same tick the
+// one who had more lifes when the time ran out still wins
+#[test]
+fn one_life_sudden_death_remembers_the_lifes_for_the_tiebreak() {
+    let mut app = headless_app();
+    app.world.resource_mut::<MatchRules>().sudden_death = SuddenDeath::OneLife;
+    app.world.resource_mut::<RoundState>().timer = Some(Timer::from_seconds(0.2, TimerMode::Once));
+    let first = spawn_player(&mut app, 1, Vec2::new(-200.0, 0.0));
+    let second = spawn_player(&mut app, 2, Vec2::new(200.0, 0.0));
+    app.world.get_mut::<Player>(first).unwrap().lifes = 2;
+    app.world.get_mut::<Player>(second).unwrap().lifes = 3;
+    step_secs(&mut app, 0.5);
+    assert!(app.world.resource::<RoundState>().sudden_death);
+    assert_eq!(
+        (player(&app, first).lifes, player(&app, second).lifes),
+        (1, 1)
+    );
+
+    for entity in [first, second] {
+        app.world.get_mut::<Player>(entity).unwrap().lifes = 0;
+    }
+    step(&mut app, 1);
+    let round = app.world.resource::<RoundState>();
+    assert!(round.over);
+    assert_eq!(round.winner, Some(2));
+}
+
+// whoever shot a player last gets no deathmatch kill when the spikes finish them off, just like
+// the match results log it
+#[test]
+fn environmental_deaths_give_nobody_the_kill() {
+    let mut app = headless_app();
+    app.world.resource_mut::<MatchRules>().mode = GameMode::Deathmatch;
+    let victim = spawn_player(&mut app, 1, Vec2::new(-200.0, 0.0));
+    spawn_player(&mut app, 2, Vec2::new(200.0, 200.0));
+    let mut hit = app.world.get_mut::<Player>(victim).unwrap();
+    hit.lifes = 1;
+    hit.last_hit_by = Some(2);
+    app.world.spawn(HazardBundle::new(
+        Hazard::Spikes,
+        Vec2::new(-200.0, 0.0),
+        Vec2::new(40.0, 40.0),
+    ));
+    step(&mut app, 3);
+
+    assert_eq!(app.world.resource::<ModeScores>().get(2), 0.0);
+    let kills = &app.world.resource::<MatchLog>().kills;
+    assert_eq!(kills.len(), 1);
+    assert_eq!((kills[0].killer, kills[0].victim), (None, 1));
+}
@@ -0,0 +1,89 @@
+mod common;
+
+use bevy::{prelude::*, time::TimeUpdateStrategy, utils::Duration};
+use stapid::game_objects::Player;
+use stapid::game_utils::{GameState, PlayerSlots};
+use stapid::lobby::{Lobby, LobbyEvent};
+use stapid::netcode::{NetPhase, NetSession};
+use std::net::{Ipv4Addr, SocketAddr};
+use std::time::Instant;
+
+use common::test_app;
+
+fn in_lobby() -> App {
+    let mut app = test_app(TimeUpdateStrategy::Automatic);
+    app.world
+        .resource_mut::<NextState<GameState>>()
+        .set(GameState::Lobby);
+    app.update();
+    app
+}
+
+fn update_until(apps: &mut [App], done: impl Fn(&[App]) -> bool, what: &str) {
+    let started = Instant::now();
+    while !done(apps) {
+        assert!(started.elapsed().as_secs() < 20, "{}", what);
+        for app in apps.iter_mut() {
+            app.update();
+        }
+        std::thread::sleep(Duration::from_millis(1));
+    }
+}
+
+fn player_numbers(app: &mut App) -> Vec<i32> {
+    let mut numbers: Vec<i32> = app
+        .world
+        .query::<&Player>()
+        .iter(&app.world)
+        .map(|player| player.player_number)
+        .collect();
+    numbers.sort();
+    numbers
+}
+
+// the joining player takes the first free slot and the match only has the players who are there
+#[test]
+fn joining_players_take_the_free_slots() {
+    let mut apps = [in_lobby(), in_lobby()];
+    apps[0].world.send_event(LobbyEvent::Host(0));
+    apps[0].update();
+    let port = apps[0]
+        .world
+        .resource::<Lobby>()
+        .hosting_address()
+        .expect("not hosting")
+        .port();
+    apps[1].world.send_event(LobbyEvent::Join(SocketAddr::from((
+        Ipv4Addr::LOCALHOST,
+        port,
+    ))));
+    update_until(
+        &mut apps,
+        |apps| apps[1].world.resource::<Lobby>().joined_as().is_some(),
+        "the host never answered",
+    );
+    assert_eq!(apps[1].world.resource::<Lobby>().joined_as(), Some(2));
+    assert_eq!(apps[0].world.resource::<PlayerSlots>().count(), 2);
+
+    apps[0].world.send_event(LobbyEvent::Start);
+    update_until(
+        &mut apps,
+        |apps| {
+            apps.iter().all(|app| {
+                app.world
+                    .get_resource::<NetSession>()
+                    .is_some_and(|session| session.phase == NetPhase::Running && session.tick > 10)
+            })
+        },
+        "the match never started",
+    );
+    for (app, player) in apps.iter_mut().zip([1, 2]) {
+        assert_eq!(app.world.resource::<NetSession>().player, player);
+        assert_eq!(app.world.resource::<NetSession>().players, 2);
+        assert_eq!(
+            *app.world.resource::<State<GameState>>().get(),
+            GameState::Playing
+        );
+        assert_eq!(player_numbers(app), vec![1, 2]);
+    }
+}
@@ -0,0 +1,174 @@
+mod common;
+
+use bevy::{prelude::*, time::TimeUpdateStrategy, utils::Duration};
+use rand::{rngs::StdRng, Rng, SeedableRng};
+use stapid::game_utils::Bindings;
+use stapid::netcode::{NetConfig, NetPhase, NetSession};
+use stapid::profiles::{Profiles, StatsTracker};
+use stapid::results::MatchLog;
+use stapid::rollback::RollbackRegistry;
+use stapid::round_stats::RoundStats;
+use std::net::{SocketAddr, UdpSocket};
+use std::time::Instant;
+
+use common::test_app;
+
+const TICKS: u32 = 300;
+
+fn peer(player: usize, addresses: &[SocketAddr]) -> App {
+    let mut app = test_app(TimeUpdateStrategy::Automatic);
+    app.insert_resource(NetConfig {
+        player,
+        addresses: addresses.to_vec(),
+        latency: Duration::from_millis(50),
+        loss: 0.1,
+    });
+    // binds the socket and sets up the session
+    app.update();
+    app
+}
+
+fn free_addresses(count: usize) -> Vec<SocketAddr> {
+    let sockets: Vec<UdpSocket> = (0..count)
+        .map(|_| UdpSocket::bind("127.0.0.1:0").unwrap())
+        .collect();
+    sockets
+        .iter()
+        .map(|socket| socket.local_addr().unwrap())
+        .collect()
+}
+
+fn session(app: &App) -> &NetSession {
+    app.world.resource::<NetSession>()
+}
+
+// both peers mash their keys until every tick up to `ticks` is final on both sides
+fn mash(peers: &mut [App; 2], ticks: u32, seed: u64) {
+    let mut rng = StdRng::seed_from_u64(seed);
+    let started = Instant::now();
+    while peers.iter().any(|app| session(app).final_tick() < ticks) {
+        assert!(
+            started.elapsed().as_secs() < 60,
+            "the peers stopped advancing"
+        );
+        for app in peers.iter_mut() {
+            let mut keys = app.world.resource_mut::<Input<KeyCode>>();
+            keys.clear();
+            for key in Bindings::for_slot(1).keys() {
+                if !rng.gen_bool(0.05) {
+                    continue;
+                }
+                match keys.pressed(key) {
+                    true => keys.release(key),
+                    false => keys.press(key),
+                }
+            }
+            app.update();
+        }
+        std::thread::sleep(Duration::from_millis(1));
+    }
+}
+
+// both peers mash their keys over a slow and lossy loopback, whatever they predicted wrong gets
+// rolled back and they still have to agree on every tick
+#[test]
+fn peers_agree_on_every_tick_over_a_bad_connection() {
+    let addresses = free_addresses(2);
+    let mut peers = [peer(1, &addresses), peer(2, &addresses)];
+    mash(&mut peers, TICKS, 3);
+
+    let [first, second] = &peers;
+    assert!(session(first).phase == NetPhase::Running);
+    assert!(session(first).rollbacks + session(second).rollbacks > 0);
+    assert_eq!(session(first).desyncs + session(second).desyncs, 0);
+    let compared: Vec<u32> = (1..=TICKS)
+        .filter(|tick| session(first).checksums.contains_key(tick))
+        .collect();
+    assert!(compared.len() as u32 > TICKS / 2);
+    for tick in compared {
+        assert_eq!(
+            session(first).checksums.get(&tick),
+            session(second).checksums.get(&tick),
+            "tick {}",
+            tick
+        );
+    }
+}
+
+// a component the registry does not know would get lost when a rollback respawns its entity
+#[test]
+fn rollback_covers_every_component_of_the_arena() {
+    let addresses = free_addresses(2);
+    let mut peers = [peer(1, &addresses), peer(2, &addresses)];
+    let started = Instant::now();
+    while peers.iter().any(|app| session(app).tick < 60) {
+        assert!(started.elapsed().as_secs() < 30, "the peers never started");
+        for app in &mut peers {
+            app.update();
+        }
+        std::thread::sleep(Duration::from_millis(1));
+    }
+
+    let app = &mut peers[0];
+    let registry = app.world.resource::<RollbackRegistry>();
+    let known = registry.component_ids();
+    for entity in registry.tracked(&app.world) {
+        for component in app.world.inspect_entity(entity) {
+            let Some(type_id) = component.type_id() else {
+                continue;
+            };
+            assert!(
+                known.contains(&type_id),
+                "{} is not rolled back",
+                component.name()
+            );
+        }
+    }
+}
+
+// the ticks a rollback simulates again must not count twice into the profile, what it has for
+// the match are the shots the round stats have as well
+#[test]
+fn rollbacks_do_not_count_into_the_lifetime_stats() {
+    let addresses = free_addresses(2);
+    let mut peers = [peer(1, &addresses), peer(2, &addresses)];
+    for app in &mut peers {
+        let mut profiles = app.world.resource_mut::<Profiles>();
+        profiles.select_or_create("ada").unwrap();
+    }
+    mash(&mut peers, TICKS, 5);
+    for app in &mut peers {
+        app.world.resource_mut::<Input<KeyCode>>().reset_all();
+    }
+    let settled = TICKS + 30;
+    let started = Instant::now();
+    while peers.iter().any(|app| session(app).final_tick() < settled) {
+        assert!(
+            started.elapsed().as_secs() < 30,
+            "the peers stopped advancing"
+        );
+        for app in &mut peers {
+            app.update();
+        }
+        std::thread::sleep(Duration::from_millis(1));
+    }
+
+    assert!(session(&peers[0]).rollbacks + session(&peers[1]).rollbacks > 0);
+    for app in &peers {
+        assert!(app.world.resource::<MatchLog>().rounds.is_empty());
+        let own = session(app).player as i32;
+        let tracked = &app.world.resource::<StatsTracker>().stats;
+        let shots: u32 = app.world.resource::<RoundStats>().players[&own]
+            .shots
+            .values()
+            .sum();
+        assert!(shots > 0, "player {} never shot", own);
+        assert_eq!(tracked.shots_fired, shots, "player {}", own);
+        assert_eq!(
+            tracked.shots_by_bullet.values().sum::<u32>(),
+            shots,
+            "player {}",
+            own
+        );
+    }
+}
@@ -0,0 +1,141 @@
+mod common;
+
+use bevy::{app::AppExit, prelude::*, time::TimeUpdateStrategy};
+use stapid::game_objects::Player;
+use stapid::game_utils::{BulletType, GameState, PlayerShootEvent};
+use stapid::profiles::{Profile, Profiles};
+use stapid::replay::TICK;
+use stapid::round::RoundState;
+use std::path::PathBuf;
+
+use common::test_app;
+
+fn profiles_file(name: &str) -> PathBuf {
+    let path = std::env::temp_dir().join(format!("stapid_{}_{}.ron", name, std::process::id()));
+    let _ = std::fs::remove_file(&path);
+    path
+}
+
+fn app_with(profiles: Profiles) -> App {
+    let mut app = test_app(TimeUpdateStrategy::ManualDuration(TICK));
+    app.insert_resource(profiles);
+    app.world
+        .resource_mut::<NextState<GameState>>()
+        .set(GameState::Playing);
+    for _ in 0..3 {
+        app.update();
+    }
+    app
+}
+
+fn player_mut(app: &mut App, number: i32) -> Mut<'_, Player> {
+    let entity = app
+        .world
+        .query::<(Entity, &Player)>()
+        .iter(&app.world)
+        .find(|(_, player)| player.player_number == number)
+        .map(|(entity, _)| entity)
+        .expect("player missing");
+    app.world.get_mut::<Player>(entity).unwrap()
+}
+
+// the profile plays player 1 under its own name and color, what that player does ends up in
+// the file once the match is over, not after every round
+#[test]
+fn a_finished_match_adds_to_the_lifetime_stats() {
+    let path = profiles_file("profiles");
+    let mut profiles = Profiles::load(path.clone());
+    profiles.select_or_create("ada").unwrap();
+    let mut app = app_with(profiles);
+
+    let (name, color) = app
+        .world
+        .query::<(&Player, &Sprite)>()
+        .iter(&app.world)
+        .find(|(player, _)| player.player_number == 1)
+        .map(|(player, sprite)| (player.display_name.clone(), sprite.color))
+        .unwrap();
+    assert_eq!(name, "ada");
+    assert_eq!(color, app.world.resource::<Profiles>().profiles[0].tint());
+
+    app.world.send_event(PlayerShootEvent {
+        player_number: 1,
+        bullet_type: BulletType::NormalBullet,
+    });
+    app.update();
+    for (number, hit_by) in [(2, 1), (3, 1), (4, 2)] {
+        let mut player = player_mut(&mut app, number);
+        player.lifes = 0;
+        player.last_hit_by = Some(hit_by);
+    }
+    for _ in 0..3 {
+        app.update();
+    }
+    assert!(app.world.resource::<RoundState>().over);
+    assert_eq!(
+        app.world.resource::<Profiles>().profiles[0].stats,
+        Default::default(),
+        "a round is not the whole match"
+    );
+
+    app.world.send_event(AppExit);
+    for _ in 0..2 {
+        app.update();
+    }
+    let stats = Profiles::load(path.clone()).profiles[0].stats.clone();
+    assert_eq!(
+        stats,
+        app.world.resource::<Profiles>().profiles[0].stats,
+        "the stats were not saved"
+    );
+    assert_eq!(stats.matches_played, 1);
+    assert_eq!(stats.matches_won, 1);
+    assert_eq!(stats.kills, 2);
+    assert_eq!(stats.deaths, 0);
+    assert_eq!(stats.shots_fired, 1);
+    assert_eq!(stats.favorite_bullet(), Some("Normal"));
+    let _ = std::fs::remove_file(path);
+}
+
+// without a selected profile nothing is counted and nothing is written
+#[test]
+fn nobody_is_tracked_without_a_profile() {
+    let path = profiles_file("no_profile");
+    let mut profiles = Profiles::load(path.clone());
+    profiles.profiles.push(Profile {
+        name: String::from("grace"),
+        ..default()
+    });
+    let mut app = app_with(profiles);
+    for number in 2..5 {
+        player_mut(&mut app, number).lifes = 0;
+    }
+    for _ in 0..3 {
+        app.update();
+    }
+    assert!(app.world.resource::<RoundState>().over);
+    app.world.send_event(AppExit);
+    app.update();
+    assert_eq!(
+        app.world.resource::<Profiles>().profiles[0].stats,
+        Default::default()
+    );
+    assert!(!path.exists());
+}
+
+// names only differing in case or spaces are the same profile, an empty name or one a player
+// without a profile goes by never becomes one
+#[test]
+fn profile_names_are_unique_and_not_empty() {
+    let mut profiles = Profiles::default();
+    profiles.select_or_create("ada").unwrap();
+    profiles.select_or_create("grace").unwrap();
+    profiles.select_or_create(" Ada ").unwrap();
+    assert_eq!(profiles.profiles.len(), 2);
+    assert_eq!(profiles.current().unwrap().name, "ada");
+
+    assert!(profiles.select_or_create("  ").is_err());
+    assert!(profiles.select_or_create("Player2").is_err());
+    assert_eq!(profiles.profiles.len(), 2);
+    assert_eq!(profiles.current().unwrap().name, "ada");
+}
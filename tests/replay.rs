@@ -0,0 +1,97 @@
+mod common;
+
+use bevy::{app::AppExit, prelude::*, time::TimeUpdateStrategy};
+use rand::{rngs::StdRng, Rng, SeedableRng};
+use stapid::game_objects::{Bullet, Player, PowerUp, Totem};
+use stapid::game_utils::{Bindings, GameState, ResetGameEvent};
+use stapid::replay::{ReplayMode, ReplayPlayback, TICK};
+
+use common::test_app;
+
+fn headless_match(mode: ReplayMode) -> App {
+    let mut app = test_app(TimeUpdateStrategy::ManualDuration(TICK));
+    app.insert_resource(mode);
+    app
+}
+
+// positions are compared bit for bit, a replay that is merely close is still a broken one
+fn snapshot(app: &mut App) -> Vec<String> {
+    let mut lines: Vec<String> = app
+        .world
+        .query::<(&Transform, &Player)>()
+        .iter(&app.world)
+        .map(|(transform, player)| {
+            format!(
+                "player {} lifes {} at {:?}",
+                player.player_number, player.lifes, transform.translation
+            )
+        })
+        .collect();
+    for (name, count) in [
+        ("bullets", count::<Bullet>(app)),
+        ("power-ups", count::<PowerUp>(app)),
+        ("totems", count::<Totem>(app)),
+    ] {
+        lines.push(format!("{} {}", name, count));
+    }
+    lines.sort();
+    lines
+}
+
+fn count<T: Component>(app: &mut App) -> usize {
+    app.world
+        .query_filtered::<(), With<T>>()
+        .iter(&app.world)
+        .count()
+}
+
+#[test]
+fn replay_reproduces_the_recorded_match() {
+    let path = std::env::temp_dir().join(format!("stapid-replay-{}.ron", std::process::id()));
+    let mut recording = headless_match(ReplayMode::Record(path.clone()));
+    recording
+        .world
+        .resource_mut::<NextState<GameState>>()
+        .set(GameState::Playing);
+    // every player hammers random keys, halfway through the round gets restarted
+    let mut rng = StdRng::seed_from_u64(7);
+    for frame in 0..900 {
+        let mut keys = recording.world.resource_mut::<Input<KeyCode>>();
+        keys.clear();
+        for slot in 1..=4 {
+            for key in Bindings::for_slot(slot).keys() {
+                if !rng.gen_bool(0.1) {
+                    continue;
+                }
+                match keys.pressed(key) {
+                    true => keys.release(key),
+                    false => keys.press(key),
+                }
+            }
+        }
+        if frame == 450 {
+            recording.world.send_event(ResetGameEvent {});
+        }
+        recording.update();
+    }
+    recording.world.send_event(AppExit);
+    recording.update();
+    let recorded = snapshot(&mut recording);
+
+    // paused and stepped one tick per frame, so the playback does not depend on the real clock
+    let mut playback = headless_match(ReplayMode::Play(path.clone()));
+    playback.update();
+    playback.world.resource_mut::<ReplayPlayback>().paused = true;
+    for _ in 0..1000 {
+        let mut progress = playback.world.resource_mut::<ReplayPlayback>();
+        if progress.tick == progress.ticks.len() {
+            break;
+        }
+        progress.step = true;
+        playback.update();
+    }
+    std::fs::remove_file(&path).unwrap();
+
+    assert_eq!(playback.world.resource::<ReplayPlayback>().ticks.len(), 901);
+    assert_eq!(snapshot(&mut playback), recorded);
+}
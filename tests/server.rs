@@ -0,0 +1,154 @@
+mod common;
+
+use bevy::{prelude::*, time::TimeUpdateStrategy, utils::Duration};
+use stapid::client::{ClientConfig, LinkPhase, Replicated, ServerLink};
+use stapid::game_objects::Player;
+use stapid::game_utils::Bindings;
+use stapid::results::ResultsOutput;
+use stapid::server::{self, ClientMessage, Server, ServerConfig};
+use std::net::{Ipv4Addr, SocketAddr, UdpSocket};
+use std::sync::mpsc;
+use std::time::Instant;
+
+use common::test_app;
+
+const SERVER_TICKS: u32 = 480;
+
+fn bot(server: SocketAddr) -> App {
+    let mut app = test_app(TimeUpdateStrategy::Automatic);
+    app.insert_resource(ClientConfig { server });
+    app
+}
+
+fn link(app: &App) -> &ServerLink {
+    app.world.resource::<ServerLink>()
+}
+
+fn position_of(app: &mut App, number: i32) -> Vec2 {
+    app.world
+        .query::<(&Player, &Transform)>()
+        .iter(&app.world)
+        .find(|(player, _)| player.player_number == number)
+        .map(|(_, transform)| transform.translation.truncate())
+        .expect("player missing")
+}
+
+// the scripted bots, the first walks right and then up, the second walks left and keeps
+// shooting
+fn press_keys(app: &mut App, bot: usize, tick: u32) {
+    let bindings = Bindings::for_slot(1);
+    let mut keys = app.world.resource_mut::<Input<KeyCode>>();
+    keys.release_all();
+    keys.clear();
+    match bot {
+        0 if tick < 120 => keys.press(bindings.right),
+        0 => keys.press(bindings.up),
+        _ => {
+            keys.press(bindings.left);
+            if tick.is_multiple_of(40) {
+                keys.press(bindings.shoot);
+            }
+        }
+    }
+}
+
+// two bots play on a local server, each predicts its own player, sees the other one where the
+// server had it a moment ago, and a third socket pretending to be player 1 gets nowhere
+#[test]
+fn bots_play_on_an_authoritative_server() {
+    let (sender, receiver) = mpsc::channel();
+    let server = std::thread::spawn(move || {
+        let config = ServerConfig {
+            port: 0,
+            players: 2,
+            ticks: Some(SERVER_TICKS),
+            log: false,
+        };
+        let mut app = server::server_app(&config).expect("could not start the server");
+        app.insert_resource(ResultsOutput::Off);
+        let port = app.world.resource::<Server>().local_addr().unwrap().port();
+        sender.send(port).unwrap();
+        server::serve(&mut app, config.ticks);
+        app.world.resource::<Server>().rejected
+    });
+    let address = SocketAddr::from((Ipv4Addr::LOCALHOST, receiver.recv().unwrap()));
+
+    let mut bots = [bot(address), bot(address)];
+    let mut saw_objects = false;
+    let mut spoofed = false;
+    let started = Instant::now();
+    while bots.iter().any(|app| {
+        app.world
+            .get_resource::<ServerLink>()
+            .is_none_or(|link| link.latest().map_or(0, |latest| latest.tick) < SERVER_TICKS - 60)
+    }) {
+        assert!(started.elapsed().as_secs() < 30, "the bots never got far");
+        for (index, app) in bots.iter_mut().enumerate() {
+            let tick = app
+                .world
+                .get_resource::<ServerLink>()
+                .and_then(|link| link.latest())
+                .map_or(0, |latest| latest.tick);
+            press_keys(app, index, tick);
+            app.update();
+            saw_objects |= app
+                .world
+                .query::<&Replicated>()
+                .iter(&app.world)
+                .next()
+                .is_some();
+        }
+        if !spoofed && bots.iter().all(|app| link(app).phase == LinkPhase::Running) {
+            let socket = UdpSocket::bind("127.0.0.1:0").unwrap();
+            let spoof = ClientMessage::Input {
+                player: 1,
+                first: 1,
+                inputs: vec![0xffff; 8],
+            };
+            socket.send_to(&spoof.encode(), address).unwrap();
+            spoofed = true;
+        }
+        std::thread::sleep(Duration::from_millis(1));
+    }
+
+    for (app, player) in bots.iter().zip([1, 2]) {
+        assert_eq!(link(app).player, player);
+        assert_eq!(link(app).players, 2);
+        assert!(link(app).snapshots_received > SERVER_TICKS / 2);
+        assert!(link(app).latest().unwrap().ack > SERVER_TICKS / 2);
+    }
+    assert!(saw_objects, "no bullet ever showed up");
+
+    // the prediction agrees with the server up to the inputs still on their way
+    let latest = link(&bots[0]).latest().unwrap().clone();
+    let on_server = latest
+        .players
+        .iter()
+        .find(|state| state.number == 1)
+        .unwrap();
+    let predicted = position_of(&mut bots[0], 1);
+    assert!(
+        predicted.distance(Player::spawn_point(1)) > 100.0,
+        "the bot never moved"
+    );
+    assert!(
+        link(&bots[0]).last_error < 1.0,
+        "off by {}",
+        link(&bots[0]).last_error
+    );
+    assert!(predicted.distance(on_server.position) < 60.0);
+    // and the other bot draws player 1 a little behind
+    let seen = position_of(&mut bots[1], 1);
+    assert!(
+        seen.distance(on_server.position) < 60.0,
+        "{} {}",
+        seen,
+        on_server.position
+    );
+
+    assert_eq!(
+        server.join().unwrap(),
+        1,
+        "only the spoofed input is rejected"
+    );
+}